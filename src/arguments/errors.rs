@@ -2,6 +2,8 @@ use std::fmt;
 use std::io::{self, Write, stderr, stdout};
 use std::path::PathBuf;
 use std::process::exit;
+use super::OptionErr;
+use super::options::suggest;
 
 /// A list of all the possible errors that may happen when working with files.
 #[derive(Debug)]
@@ -24,38 +26,204 @@ impl fmt::Display for FileErr {
 /// The error type for the argument module.
 #[derive(Debug)]
 pub enum ParseErr {
+    /// The arg-file-sep parameter was not set.
+    ArgFileSepNoValue,
+    /// The arg-sep parameter was not set.
+    ArgSepNoValue,
+    /// The buffer-size parameter was not set to a valid size.
+    BufferSizeInvalid(usize),
+    /// The buffer-size parameter was not set.
+    BufferSizeNoValue,
+    /// The checkpoint parameter was not set.
+    CheckpointNoValue,
+    /// The colsep parameter was not set.
+    ColsepNoValue,
+    /// The commands-delimiter parameter was not set.
+    CommandsDelimiterNoValue,
+    /// The commands-file parameter was supplied with a command or with input arguments.
+    CommandsFileArguments,
+    /// The commands-file parameter was not set.
+    CommandsFileNoValue,
+    /// The container parameter was not set.
+    ContainerNoValue,
+    /// The dedupe parameter was used with streamed inputs.
+    DedupeStream,
     /// The value of the job delay parameter is not set to a number.
     DelayNaN(usize),
     /// The job delay parameter was not set.
     DelayNoValue,
+    /// The error-output parameter was not set.
+    ErrorOutputNoValue,
+    /// The log-events parameter was not set.
+    EventsNoValue,
+    /// The fail-on-output parameter was not a valid regular expression.
+    FailOnOutputInvalid(String),
+    /// The fail-on-output parameter was not set.
+    FailOnOutputNoValue,
+    /// The fifo-eof parameter was not set.
+    FifoEofNoValue,
     /// An error occurred with accessing the unprocessed file.
     File(FileErr),
+    /// The filter parameter was not a valid expression.
+    FilterInvalid(String),
+    /// The filter parameter was not set.
+    FilterNoValue,
+    /// The follow parameter requires a single file of inputs, given after `::::`.
+    FollowFile,
+    /// The group-by parameter was not set to a column number.
+    GroupByNaN(usize),
+    /// The group-by parameter was not set.
+    GroupByNoValue,
+    /// The group-by parameter can not be used when inputs are streamed.
+    GroupByStream,
+    /// The halt parameter was not a valid halting policy.
+    HaltInvalid(usize),
+    /// The halt parameter was not set.
+    HaltNoValue,
+    /// The halt-on-output parameter was not a valid regular expression.
+    HaltOnOutputInvalid(String),
+    /// The halt-on-output parameter was not set.
+    HaltOnOutputNoValue,
+    /// Inputs were given as arguments when inputs are read from the input-fifo parameter.
+    InputFifoArguments,
+    /// The input-fifo parameter was not set.
+    InputFifoNoValue,
+    /// The ionice parameter was not a valid IO priority.
+    IoniceInvalid(usize),
+    /// The ionice parameter was not set.
+    IoniceNoValue,
+    /// The `{##}` token was used while inputs are streamed, without the total-jobs parameter.
+    JobTotalStream,
     /// The joblog parameter was not set.
     JoblogNoValue,
+    /// The joblog-template parameter contains an invalid token.
+    JoblogTemplateInvalid(String),
+    /// The joblog-template parameter was not set.
+    JoblogTemplateNoValue,
+    /// The retry-failed parameter was given with a joblog-template, whose columns can not be read back.
+    JoblogTemplateRetry,
     /// The jobs number parameter was not set to a number.
     JobsNaN(String),
     /// The jobs number parameter was not set.
     JobsNoValue,
-    /// An invalid argument flag was provided.
+    /// An invalid argument flag was provided. The closest known option is suggested when the error is handled.
     InvalidArgument(usize),
+    /// The log-file parameter was not set.
+    LogFileNoValue,
+    /// The log-level parameter was not a known level.
+    LogLevelInvalid(usize),
+    /// The log-level parameter was not set.
+    LogLevelNoValue,
     /// The value for `max_args` was not set to a number.
     MaxArgsNaN(usize),
     /// No value was provided for the `max_args` flag.
     MaxArgsNoValue,
+    /// The max-starts-per-second parameter was not set to a positive number.
+    MaxStartsNaN(usize),
+    /// The max-starts-per-second parameter was not set.
+    MaxStartsNoValue,
     /// The memfree parameter was invalid.
     MemInvalid(usize),
     /// The memfree parameter was not set.
     MemNoValue,
+    /// The memfree-poll parameter was not set to a number.
+    MemPollNaN(usize),
+    /// The memfree-poll parameter was not set.
+    MemPollNoValue,
+    /// The memfree-timeout parameter was not set to a number.
+    MemTimeoutNaN(usize),
+    /// The memfree-timeout parameter was not set.
+    MemTimeoutNoValue,
     /// No arguments were given, so no action can be taken.
     NoArguments,
     /// An invalid command was supplied whose quotes aren't terminated.
     NonTerminated(String),
+    /// The notify-cmd parameter was not set.
+    NotifyNoValue,
+    /// The on-fail parameter was not set.
+    OnFailNoValue,
+    /// An option was abbreviated ambiguously, or given a value that it does not take.
+    Option(OptionErr),
+    /// The output parameter was not set.
+    OutputNoValue,
+    /// The queue parameter was used with the sqlmaster or sqlworker parameters.
+    QueueArguments,
+    /// The queue parameter was not set.
+    QueueNoValue,
     /// The standard input could not be redirected to the given file
     RedirFile(PathBuf),
+    /// The repeat parameter was not set to a positive number.
+    RepeatNaN(usize),
+    /// The repeat parameter was not set.
+    RepeatNoValue,
+    /// The require-output parameter was not a valid regular expression.
+    RequireOutputInvalid(String),
+    /// The require-output parameter was not set.
+    RequireOutputNoValue,
+    /// The results-json parameter was not set.
+    ResultsJsonNoValue,
+    /// The retries parameter was not set to a number.
+    RetriesNaN(usize),
+    /// The retries parameter was not set.
+    RetriesNoValue,
+    /// The retry-backoff parameter was not set to a number.
+    RetryBackoffNaN(usize),
+    /// The retry-backoff parameter was not set.
+    RetryBackoffNoValue,
+    /// The retry-delay parameter was not set to a number.
+    RetryDelayNaN(usize),
+    /// The retry-delay parameter was not set.
+    RetryDelayNoValue,
+    /// The seed parameter was not set to a number.
+    SeedNaN(usize),
+    /// The seed parameter was not set.
+    SeedNoValue,
+    /// The seqreplace parameter was not set.
+    SeqReplaceNoValue,
+    /// The shebang-wrap parameter was not given an interpreter and a script.
+    ShebangWrapNoValue,
+    /// The shell parameter was not set.
+    ShellNoValue,
+    /// The shell parameter was set to `env`, but the `SHELL` variable is not set.
+    ShellNotSet,
+    /// The skip-comments parameter was given an empty prefix.
+    SkipCommentsNoValue,
+    /// The slotreplace parameter was not set.
+    SlotReplaceNoValue,
+    /// The sort parameter was given an order that is not `lexical` or `numeric`.
+    SortInvalid(String),
+    /// The sort or reverse parameters were used with streamed inputs.
+    SortStream,
+    /// An SQLite queue was given, but the program was built without the `sqlite` feature.
+    SqlDisabled,
+    /// The sqlmaster parameter was not set.
+    SqlMasterNoValue,
+    /// The sqlworker parameter was used with other inputs, or with the sqlmaster parameter.
+    SqlWorkerArguments,
+    /// The sqlworker parameter was not set.
+    SqlWorkerNoValue,
+    /// The stall-timeout parameter was not set to a number.
+    StallTimeoutNaN(usize),
+    /// The stall-timeout parameter was not set.
+    StallTimeoutNoValue,
+    /// The status-file parameter was not set.
+    StatusFileNoValue,
+    /// The tmpdir parameter was not set.
+    TmpdirNoValue,
     /// The timeout parameter was not set to a number.
     TimeoutNaN(usize),
     /// The timeout parameter was not set.
     TimeoutNoValue,
+    /// The timestamp parameter was given a format which is not valid.
+    TimestampInvalid(String),
+    /// The total-jobs parameter was not set to a number.
+    TotalJobsNaN(usize),
+    /// The total-jobs parameter was not set.
+    TotalJobsNoValue,
+    /// The trace-file parameter was not set.
+    TraceFileNoValue,
+    /// The ungroup parameter was used with a parameter that requires the outputs of jobs to be captured.
+    UngroupCaptured,
     /// The workdir parameter was not set.
     WorkDirNoValue,
 }
@@ -64,6 +232,10 @@ impl From<FileErr> for ParseErr {
     fn from(input: FileErr) -> ParseErr { ParseErr::File(input) }
 }
 
+impl From<OptionErr> for ParseErr {
+    fn from(input: OptionErr) -> ParseErr { ParseErr::Option(input) }
+}
+
 impl ParseErr {
     pub fn handle(self, arguments: &[String]) -> ! {
         // Always lock an output buffer before using it.
@@ -76,35 +248,168 @@ impl ParseErr {
             ParseErr::File(file_err) => {
                 let _ = writeln!(stderr, "{}", file_err);
             }
+            ParseErr::ArgFileSepNoValue => {
+                let _ = stderr.write(b"no arg-file-sep parameter was defined.\n");
+            },
+            ParseErr::ArgSepNoValue => {
+                let _ = stderr.write(b"no arg-sep parameter was defined.\n");
+            },
+            ParseErr::BufferSizeInvalid(index) => {
+                let _ = write!(stderr, "invalid buffer size: {}\n", arguments[index]);
+            },
+            ParseErr::BufferSizeNoValue => {
+                let _ = stderr.write(b"no buffer-size parameter was defined.\n");
+            },
+            ParseErr::CheckpointNoValue => {
+                let _ = stderr.write(b"no checkpoint parameter was defined.\n");
+            },
+            ParseErr::ColsepNoValue => {
+                let _ = stderr.write(b"no colsep parameter was defined.\n");
+            },
+            ParseErr::CommandsDelimiterNoValue => {
+                let _ = stderr.write(b"no commands-delimiter parameter was defined.\n");
+            },
+            ParseErr::CommandsFileArguments => {
+                let _ = stderr.write(b"commands-file parameter can not be used with a command or with inputs.\n");
+            },
+            ParseErr::CommandsFileNoValue => {
+                let _ = stderr.write(b"no commands-file parameter was defined.\n");
+            },
+            ParseErr::ContainerNoValue => {
+                let _ = stderr.write(b"no container image was defined.\n");
+            },
+            ParseErr::DedupeStream => {
+                let _ = stderr.write(b"dedupe parameter can not be used with streamed inputs.\n");
+            },
             ParseErr::DelayNaN(index) => {
                 let _ = write!(stderr, "delay parameter, '{}', is not a number.\n", arguments[index]);
             },
             ParseErr::DelayNoValue => {
                 let _ = stderr.write(b"no delay parameter was defined.\n");
             },
+            ParseErr::ErrorOutputNoValue => {
+                let _ = stderr.write(b"no error-output parameter was defined.\n");
+            },
+            ParseErr::EventsNoValue => {
+                let _ = stderr.write(b"no log-events parameter was defined.\n");
+            },
+            ParseErr::FailOnOutputInvalid(why) => {
+                let _ = write!(stderr, "invalid fail-on-output pattern: {}\n", why);
+            },
+            ParseErr::FailOnOutputNoValue => {
+                let _ = stderr.write(b"no fail-on-output parameter was defined.\n");
+            },
+            ParseErr::FifoEofNoValue => {
+                let _ = stderr.write(b"no fifo-eof parameter was defined.\n");
+            },
+            ParseErr::FilterInvalid(why) => {
+                let _ = write!(stderr, "invalid filter expression: {}\n", why);
+            },
+            ParseErr::FilterNoValue => {
+                let _ = stderr.write(b"no filter parameter was defined.\n");
+            },
+            ParseErr::FollowFile => {
+                let _ = stderr.write(b"follow parameter requires a single file of inputs, given after `::::`.\n");
+            },
+            ParseErr::GroupByNaN(index) => {
+                let _ = write!(stderr, "group-by parameter, '{}', is not a column number.\n", arguments[index]);
+            },
+            ParseErr::GroupByNoValue => {
+                let _ = stderr.write(b"no group-by parameter was defined.\n");
+            },
+            ParseErr::GroupByStream => {
+                let _ = stderr.write(b"group-by parameter can not be used with streamed inputs.\n");
+            },
+            ParseErr::HaltInvalid(index) => {
+                let _ = write!(stderr, "invalid halt policy: {}\n", arguments[index]);
+            },
+            ParseErr::HaltNoValue => {
+                let _ = stderr.write(b"no halt parameter was defined.\n");
+            },
+            ParseErr::HaltOnOutputInvalid(why) => {
+                let _ = write!(stderr, "invalid halt-on-output pattern: {}\n", why);
+            },
+            ParseErr::HaltOnOutputNoValue => {
+                let _ = stderr.write(b"no halt-on-output parameter was defined.\n");
+            },
+            ParseErr::InputFifoArguments => {
+                let _ = stderr.write(b"input-fifo parameter can not be used with other inputs.\n");
+            },
+            ParseErr::InputFifoNoValue => {
+                let _ = stderr.write(b"no input-fifo parameter was defined.\n");
+            },
+            ParseErr::IoniceInvalid(index) => {
+                let _ = write!(stderr, "invalid IO priority: {}\n", arguments[index]);
+            },
+            ParseErr::IoniceNoValue => {
+                let _ = stderr.write(b"no ionice parameter was defined.\n");
+            },
+            ParseErr::JobTotalStream => {
+                let _ = stderr.write(b"{##} token requires the total-jobs parameter when inputs are streamed.\n");
+            },
             ParseErr::JoblogNoValue => {
                 let _ = stderr.write(b"no joblog parameter was defined.\n");
             },
+            ParseErr::JoblogTemplateInvalid(token) => {
+                let _ = write!(stderr, "invalid joblog-template token: {}\n", token);
+            },
+            ParseErr::JoblogTemplateNoValue => {
+                let _ = stderr.write(b"no joblog-template parameter was defined.\n");
+            },
+            ParseErr::JoblogTemplateRetry => {
+                let _ = stderr.write(b"retry-failed parameter can not be used with a joblog-template.\n");
+            },
             ParseErr::JobsNaN(value) => {
                 let _ = write!(stderr, "jobs parameter, '{}', is not a number.\n", value);
             },
             ParseErr::JobsNoValue => {
                 let _ = stderr.write(b"no jobs parameter was defined.\n");
             },
+            ParseErr::LogFileNoValue => {
+                let _ = stderr.write(b"no log-file parameter was defined.\n");
+            },
+            ParseErr::LogLevelInvalid(index) => {
+                let _ = write!(stderr, "log-level parameter, '{}', is not one of error, warn, info, debug, or trace.\n",
+                    arguments[index]);
+            },
+            ParseErr::LogLevelNoValue => {
+                let _ = stderr.write(b"no log-level parameter was defined.\n");
+            },
             ParseErr::MaxArgsNaN(index) => {
                 let _ = write!(stderr, "groups parameter, '{}', is not a number.\n", arguments[index]);
             },
             ParseErr::MaxArgsNoValue => {
                 let _ = stderr.write(b"no groups parameter was defined.\n");
             },
+            ParseErr::MaxStartsNaN(index) => {
+                let _ = write!(stderr, "max-starts-per-second parameter, '{}', is not a positive number.\n", arguments[index]);
+            },
+            ParseErr::MaxStartsNoValue => {
+                let _ = stderr.write(b"no max-starts-per-second parameter was defined.\n");
+            },
             ParseErr::MemNoValue => {
                 let _ = stderr.write(b"no memory parameter was defined.\n");
             },
             ParseErr::MemInvalid(index) => {
                 let _ = write!(stderr, "invalid memory value: {}\n", arguments[index]);
+            },
+            ParseErr::MemPollNaN(index) => {
+                let _ = write!(stderr, "memfree-poll parameter, '{}', is not a number.\n", arguments[index]);
+            },
+            ParseErr::MemPollNoValue => {
+                let _ = stderr.write(b"no memfree-poll parameter was defined.\n");
+            },
+            ParseErr::MemTimeoutNaN(index) => {
+                let _ = write!(stderr, "memfree-timeout parameter, '{}', is not a number.\n", arguments[index]);
+            },
+            ParseErr::MemTimeoutNoValue => {
+                let _ = stderr.write(b"no memfree-timeout parameter was defined.\n");
             }
             ParseErr::InvalidArgument(index) => {
-                let _ = write!(stderr, "invalid argument: {}\n", arguments[index]);
+                let _ = write!(stderr, "invalid argument: '{}'\n", arguments[index]);
+                if let Some(option) = suggest(&arguments[index]) {
+                    let _ = write!(stderr, "Tip: did you mean '--{}'?\n", option);
+                }
             },
             ParseErr::NoArguments => {
                 let _ = write!(stderr, "no input arguments were given.\n");
@@ -112,15 +417,138 @@ impl ParseErr {
             ParseErr::NonTerminated(command) => {
                 let _ = write!(stderr, "command is not properly terminated:\n  $ {}\nTip: Try using the --quote parameter to escape your command\n", command);
             },
+            ParseErr::NotifyNoValue => {
+                let _ = stderr.write(b"no notify-cmd parameter was defined.\n");
+            },
+            ParseErr::OnFailNoValue => {
+                let _ = stderr.write(b"no on-fail parameter was defined.\n");
+            },
+            ParseErr::Option(option_err) => {
+                let _ = writeln!(stderr, "{}", option_err);
+            },
+            ParseErr::OutputNoValue => {
+                let _ = stderr.write(b"no output parameter was defined.\n");
+            },
+            ParseErr::QueueArguments => {
+                let _ = stderr.write(b"queue parameter can not be used with the sqlmaster or sqlworker parameters.\n");
+            },
+            ParseErr::QueueNoValue => {
+                let _ = stderr.write(b"no queue parameter was defined.\n");
+            },
             ParseErr::RedirFile(path) => {
                 let _ = write!(stderr, "an error occurred while redirecting file: {:?}\n", path);
             },
+            ParseErr::RepeatNaN(index) => {
+                let _ = write!(stderr, "repeat parameter, '{}', is not a positive number.\n", arguments[index]);
+            },
+            ParseErr::RepeatNoValue => {
+                let _ = stderr.write(b"no repeat parameter was defined.\n");
+            },
+            ParseErr::RequireOutputInvalid(why) => {
+                let _ = write!(stderr, "invalid require-output pattern: {}\n", why);
+            },
+            ParseErr::RequireOutputNoValue => {
+                let _ = stderr.write(b"no require-output parameter was defined.\n");
+            },
+            ParseErr::RetriesNaN(index) => {
+                let _ = write!(stderr, "retries parameter, '{}', is not a number.\n", arguments[index]);
+            },
+            ParseErr::ResultsJsonNoValue => {
+                let _ = stderr.write(b"no results-json parameter was defined.\n");
+            },
+            ParseErr::RetriesNoValue => {
+                let _ = stderr.write(b"no retries parameter was defined.\n");
+            },
+            ParseErr::RetryBackoffNaN(index) => {
+                let _ = write!(stderr, "retry-backoff parameter, '{}', is not a number.\n", arguments[index]);
+            },
+            ParseErr::RetryBackoffNoValue => {
+                let _ = stderr.write(b"no retry-backoff parameter was defined.\n");
+            },
+            ParseErr::RetryDelayNaN(index) => {
+                let _ = write!(stderr, "retry-delay parameter, '{}', is not a number.\n", arguments[index]);
+            },
+            ParseErr::RetryDelayNoValue => {
+                let _ = stderr.write(b"no retry-delay parameter was defined.\n");
+            },
+            ParseErr::SeedNaN(index) => {
+                let _ = write!(stderr, "seed parameter, '{}', is not a number.\n", arguments[index]);
+            },
+            ParseErr::SeedNoValue => {
+                let _ = stderr.write(b"no seed parameter was defined.\n");
+            },
+            ParseErr::SeqReplaceNoValue => {
+                let _ = stderr.write(b"no seqreplace parameter was defined.\n");
+            },
+            ParseErr::ShebangWrapNoValue => {
+                let _ = stderr.write(b"shebang-wrap requires an interpreter and a script.\n");
+            },
+            ParseErr::ShellNoValue => {
+                let _ = stderr.write(b"no shell parameter was defined.\n");
+            },
+            ParseErr::ShellNotSet => {
+                let _ = stderr.write(b"the SHELL environment variable is not set.\n");
+            },
+            ParseErr::SkipCommentsNoValue => {
+                let _ = stderr.write(b"no skip-comments prefix was defined.\n");
+            },
+            ParseErr::SlotReplaceNoValue => {
+                let _ = stderr.write(b"no slotreplace parameter was defined.\n");
+            },
+            ParseErr::SortInvalid(order) => {
+                let _ = write!(stderr, "invalid sort order: {}\n", order);
+            },
+            ParseErr::SortStream => {
+                let _ = stderr.write(b"sort and reverse parameters can not be used with streamed inputs.\n");
+            },
+            ParseErr::SqlDisabled => {
+                let _ = stderr.write(b"sqlmaster, sqlworker, and queue parameters can only use an SQLite database \
+                    when parallel is built with the `sqlite` feature.\n");
+            },
+            ParseErr::SqlMasterNoValue => {
+                let _ = stderr.write(b"no sqlmaster parameter was defined.\n");
+            },
+            ParseErr::SqlWorkerArguments => {
+                let _ = stderr.write(b"sqlworker parameter can not be used with other inputs, or with the sqlmaster \
+                    parameter, as its inputs are claimed from the queue.\n");
+            },
+            ParseErr::SqlWorkerNoValue => {
+                let _ = stderr.write(b"no sqlworker parameter was defined.\n");
+            },
+            ParseErr::StallTimeoutNaN(index) => {
+                let _ = write!(stderr, "invalid stall-timeout value: {}\n", arguments[index]);
+            },
+            ParseErr::StallTimeoutNoValue => {
+                let _ = stderr.write(b"no stall-timeout parameter was defined.\n");
+            },
+            ParseErr::StatusFileNoValue => {
+                let _ = stderr.write(b"no status-file parameter was defined.\n");
+            },
             ParseErr::TimeoutNaN(index) => {
                 let _ = write!(stderr, "invalid timeout value: {}\n", arguments[index]);
             },
             ParseErr::TimeoutNoValue => {
                 let _ = stderr.write(b"no timeout parameter was defined.\n");
             },
+            ParseErr::TimestampInvalid(format) => {
+                let _ = write!(stderr, "invalid timestamp format: '{}'\n", format);
+            },
+            ParseErr::TotalJobsNaN(index) => {
+                let _ = write!(stderr, "total-jobs parameter, '{}', is not a number.\n", arguments[index]);
+            },
+            ParseErr::TmpdirNoValue => {
+                let _ = stderr.write(b"no tmpdir parameter was defined.\n");
+            },
+            ParseErr::TotalJobsNoValue => {
+                let _ = stderr.write(b"no total-jobs parameter was defined.\n");
+            },
+            ParseErr::TraceFileNoValue => {
+                let _ = stderr.write(b"no trace-file parameter was defined.\n");
+            },
+            ParseErr::UngroupCaptured => {
+                let _ = stderr.write(b"ungroup parameter can not be used with parameters that redirect, tint, or scan \
+                    the outputs of jobs.\n");
+            },
             ParseErr::WorkDirNoValue => {
                 let _ = stderr.write(b"no workdir parameter was defined.\n");
             }
@@ -0,0 +1,146 @@
+use regex::Regex;
+use std::str;
+use super::group_key;
+
+/// The comparison that a filter applies to an input, or to a column of an input.
+#[derive(Clone, Debug)]
+enum Operator {
+    /// `~ PATTERN`: the value matches the regular expression.
+    Matches(Regex),
+    /// `^= STRING`: the value begins with the string.
+    Prefix(String),
+    /// `$= STRING`: the value ends with the string.
+    Suffix(String),
+    /// `= STRING` or `== STRING`: the value is the string.
+    Equals(String),
+    /// `< NUMBER`, `<= NUMBER`, `> NUMBER`, and `>= NUMBER`: the value is a number which compares as given.
+    Less(f64),
+    LessEqual(f64),
+    Greater(f64),
+    GreaterEqual(f64),
+}
+
+/// An expression given by the `--filter` parameter, of the form `[COLUMN][!]OPERATOR VALUE`, such as
+/// `~\.jpg$`, `^=img_`, or `2>=100`. When a column is given, the operator is applied to that column of
+/// the input, where columns are separated by the `--colsep` parameter, or by whitespace. An `!` inverts
+/// the expression, so that `!~tmp` skips every input which contains `tmp`.
+#[derive(Clone, Debug)]
+pub struct Filter {
+    column:   Option<usize>,
+    negated:  bool,
+    operator: Operator,
+}
+
+impl Filter {
+    /// Parses a filter expression, returning a description of the problem if it is not valid.
+    pub fn parse(input: &str) -> Result<Filter, String> {
+        let expression = input.trim_start();
+        let digits = expression.bytes().take_while(u8::is_ascii_digit).count();
+        let column = if digits == 0 {
+            None
+        } else {
+            match expression[..digits].parse::<usize>() {
+                Ok(column) if column != 0 => Some(column),
+                _ => return Err(format!("'{}' is not a column number", &expression[..digits]))
+            }
+        };
+
+        let mut expression = expression[digits..].trim_start();
+        let negated = expression.starts_with('!');
+        if negated { expression = expression[1..].trim_start(); }
+
+        // Longer operators are listed first, so that `<=` will not be taken for `<`.
+        const OPERATORS: &'static [&'static str] = &["^=", "$=", "==", "<=", ">=", "~", "=", "<", ">"];
+        let operator = match OPERATORS.iter().find(|&&operator| expression.starts_with(operator)) {
+            Some(&operator) => operator,
+            None => return Err(format!("'{}' does not contain an operator", input))
+        };
+
+        let value = expression[operator.len()..].trim_start();
+        let number = || value.trim().parse::<f64>().map_err(|_| format!("'{}' is not a number", value));
+        let operator = match operator {
+            "~"        => Operator::Matches(Regex::new(value).map_err(|why| why.to_string())?),
+            "^="       => Operator::Prefix(value.to_owned()),
+            "$="       => Operator::Suffix(value.to_owned()),
+            "=" | "==" => Operator::Equals(value.to_owned()),
+            "<"        => Operator::Less(number()?),
+            "<="       => Operator::LessEqual(number()?),
+            ">"        => Operator::Greater(number()?),
+            _          => Operator::GreaterEqual(number()?),
+        };
+
+        Ok(Filter { column: column, negated: negated, operator: operator })
+    }
+
+    /// Returns `true` if the input satisfies the expression. Values that are not numbers never satisfy
+    /// a numeric comparison, and inputs which lack the column are compared as an empty value.
+    pub fn matches(&self, input: &str, colsep: Option<&str>) -> bool {
+        let value = match self.column {
+            Some(column) => group_key(input, column, colsep),
+            None         => input
+        };
+
+        let number = || value.trim().parse::<f64>().ok();
+        let matched = match self.operator {
+            Operator::Matches(ref pattern)  => pattern.is_match(value),
+            Operator::Prefix(ref prefix)    => value.starts_with(prefix.as_str()),
+            Operator::Suffix(ref suffix)    => value.ends_with(suffix.as_str()),
+            Operator::Equals(ref string)    => value == string,
+            Operator::Less(limit)           => number().map_or(false, |number| number < limit),
+            Operator::LessEqual(limit)      => number().map_or(false, |number| number <= limit),
+            Operator::Greater(limit)        => number().map_or(false, |number| number > limit),
+            Operator::GreaterEqual(limit)   => number().map_or(false, |number| number >= limit),
+        };
+
+        matched != self.negated
+    }
+}
+
+/// Every filter that was given, each of which an input must satisfy to be given to a job.
+#[derive(Clone, Debug, Default)]
+pub struct Filters {
+    filters: Vec<Filter>,
+    colsep:  Option<String>,
+}
+
+impl Filters {
+    pub fn new(filters: Vec<Filter>, colsep: Option<String>) -> Filters {
+        Filters { filters: filters, colsep: colsep }
+    }
+
+    pub fn is_empty(&self) -> bool { self.filters.is_empty() }
+
+    /// Returns `true` if the input, which is a single line, satisfies every filter.
+    pub fn keep(&self, input: &str) -> bool {
+        let colsep = self.colsep.as_ref().map(|x| x.as_str());
+        self.filters.iter().all(|filter| filter.matches(input, colsep))
+    }
+
+    /// Like `keep`, but for an input that is terminated by a newline, as inputs are sent by a stream.
+    pub fn keep_line(&self, line: &[u8]) -> bool {
+        let line = if line.last() == Some(&b'\n') { &line[..line.len()-1] } else { line };
+        str::from_utf8(line).map(|input| self.keep(input)).unwrap_or(true)
+    }
+}
+
+#[test]
+fn filter_expressions() {
+    let keep = |expression: &str, input: &str| Filter::parse(expression).unwrap().matches(input, None);
+    assert!(keep(r"~\.jpg$", "photo.jpg") && !keep(r"~\.jpg$", "photo.png"));
+    assert!(keep("!~tmp", "photo.jpg") && !keep("! ~ tmp", "tmp/photo.jpg"));
+    assert!(keep("^=img_", "img_01") && !keep("^=img_", "photo"));
+    assert!(keep("$= .txt", "notes.txt") && keep("!= a", "b") && keep("==a", "a"));
+    assert!(keep("2>=100", "a 100") && !keep("2 >= 100", "a 99.5") && !keep("2<5", "a b"));
+    assert!(keep("3!<5", "a b") && !keep("3 < 5", "a b"));
+
+    let tabs = Filter::parse("2=b c").unwrap();
+    assert!(tabs.matches("a\tb c\td", Some("\t")) && !tabs.matches("a b c", None));
+
+    assert!(Filter::parse("jpg").is_err());
+    assert!(Filter::parse("0=a").is_err());
+    assert!(Filter::parse(">ten").is_err());
+    assert!(Filter::parse("~(").is_err());
+
+    let filters = Filters::new(vec![Filter::parse("^=a").unwrap(), Filter::parse("$=z").unwrap()], None);
+    assert!(filters.keep_line(b"abcz\n") && !filters.keep_line(b"abc\n") && !filters.keep("bz"));
+}
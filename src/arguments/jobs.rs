@@ -1,5 +1,29 @@
-use super::errors::ParseErr;
+use super::errors::{FileErr, ParseErr};
 use num_cpus;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Parses the value of the jobs parameter, which may also be the path of a file that contains the
+/// number of jobs. If so, the path is also returned, so that the file may be re-read while running.
+pub fn parse_value(value: &str) -> Result<(usize, Option<PathBuf>), ParseErr> {
+    match parse(value) {
+        Ok(jobs) => Ok((jobs, None)),
+        Err(_) if Path::new(value).is_file() => {
+            let path = PathBuf::from(value);
+            let jobs = read(&path).map_err(|why| ParseErr::File(FileErr::Read(path.clone(), why)))?;
+            Ok((jobs, Some(path)))
+        },
+        Err(why) => Err(why)
+    }
+}
+
+/// Reads the number of jobs from a file, which may be given in any form that the jobs parameter accepts.
+pub fn read(path: &Path) -> io::Result<usize> {
+    let contents = fs::read_to_string(path)?;
+    parse(contents.trim())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "the file does not contain a number of jobs"))
+}
 
 /// Receives an input that is either an integer, or percent. If the string ends with `%`, it will
 /// be calculated as a percent of the total number of CPU cores. Otherwise, the number provided
@@ -28,12 +28,18 @@ DESCRIPTION
     Unix systems, and `cmd /C` on Windows. This comes at a performance cost, so
     it can be disabled with the --no-shell option.
 
+    If any jobs fail, a summary will be written to the standard error once all
+    jobs have finished, counting the jobs that succeeded, failed, and timed out,
+    listing the first ten failed commands, and giving the wall and CPU time.
+
 INPUT MODES
     Input modes are used to determine whether the following inputs are files
     that contain inputs or inputs themselves. Files with inputs have each
     input stored on a separate line, and each line is considered an entire
     input. When there are multiple collected lists of inputs, each individual
-    input list will be permutated together into a single list.
+    input list will be permutated together into a single list. Lists which are
+    linked together with :::+ or ::::+ are paired element by element, and if
+    their lengths differ, the shorter list will be cycled.
 
     :::
         Denotes that the input arguments that follow are input arguments.
@@ -60,50 +66,238 @@ INPUT TOKENS
     like. Ideas for more tokens are welcome.
 
     -          {}: Will supply the input argument untouched.
-    -         {.}: Removes the extension from the input.
+    -         {.}: Removes the last extension from the input, so that file.tar.gz becomes
+                   file.tar. The leading dot of a hidden file, such as .bashrc, does not
+                   begin an extension, and a trailing dot is removed.
+    -        {..}: Removes every extension from the input, so that file.tar.gz becomes file.
     -   {^abc...}: Removes a custom suffix from the input.
     -         {/}: Displays the base name (file name) of the input.
     -        {//}: Displays the directory name of the input.
     -        {/.}: Displays the base name with the extension removed.
+    -       {/..}: Displays the base name with every extension removed.
+    -       {ext}: Displays the extension of the input, without the dot, if it has one.
+    -  {realpath}: Displays the absolute path of the input, with symbolic links resolved.
+                   Paths which do not exist are made absolute from the working directory.
+    -    {U}, {L}: Displays the input in uppercase, or in lowercase.
+    -   {TOKEN:u}: Where TOKEN is any token which displays the input, such as {/.:u}
+                   or {1:l}, displays its value in uppercase, or with :l, in lowercase.
     -  {/^abc...}: Displays the base name with a custom suffix removed.
     -         {#}: Displays the current job ID as a number counting from 1.
     -        {##}: Displays the total number of jobs to be processed.
+    - {seq-start}: Displays the number of the first record within the job's input.
+    -   {seq-end}: Displays the number of the last record within the job's input. With
+                   --group-by, each group is a single record.
     -         {%}: Displays the thread's ID number.
     -         {N}: Where N is a number, display the associated job number.
     -        {N.}: Removes the extension from the Nth job.
     -        {N/}: Displays the base name (file name) of the Nth job.
     -  {N^abc...}: Removes a custom suffix from the Nth job.
     -       {N//}: Displays the directory name of the Nth job.
+    -      {Next}: Displays the extension of the Nth job.
     -       {N/.}: Displays the base name of the Nth job with the extension removed.
     - {N/^abc...}: Displays the base name of the Nth job with a custom suffix removed.
 
 
 OPTIONS
     Options may also be supplied to the program to change how the program
-    operates:
+    operates. Options end at the command, or at `--`, after which the command
+    may begin with a `-`, as in `parallel -- -v ::: a b`. Values may also be
+    given with `=`, as in `--jobs=8`, short options may be grouped, as in `-vj4`,
+    and long options may be abbreviated to any unambiguous prefix, as in `--verb`:
+
+    --arg-file-sep SEP:
+        Uses SEP instead of `::::` to begin each list of files containing inputs, and SEP+
+        instead of `::::+`, so that `::::` may be given as an input.
+
+    --arg-sep SEP:
+        Uses SEP instead of `:::` to begin each list of inputs, and SEP+ instead of `:::+`,
+        so that `:::` may be given as an input.
+
+    --buffer-size SIZE:
+        Sets the size of the buffers through which inputs are written to and read from the disk,
+        and through which the outputs of jobs are captured and replayed. The size may be suffixed
+        with K or M, as with --memfree. The default is 8K, but larger buffers may be faster on
+        network filesystems, or when jobs write large amounts of output.
+
+    --checkpoint FILE:
+        Periodically records the number of completed jobs, and the number of failures, to FILE. If
+        the run is killed, running it again with the same inputs and FILE will skip the jobs that
+        were completed. FILE is removed once every input has been processed. A checkpoint that was
+        recorded with a different command will not be resumed. Jobs that completed after the last
+        checkpoint will be rerun, and logged again within the --joblog.
+
+    --color:
+        When the output is a terminal, the outputs of each job will be tinted with a color that is
+        chosen by the job's ID, and the standard error of each job will be written in bold.
+
+    --colsep SEP:
+        Defines the separator of the columns of each record for --group-by and --filter, and of
+        the columns of each input for --timeout. By default, columns are separated by whitespace.
+
+    --commands-delimiter LINE:
+        With --commands-file, each job is a block of lines up to the next line that matches LINE,
+        such as `---`, so that commands may span multiple lines. Multi-line commands are always
+        executed within a shell.
+
+    --commands-file FILE:
+        Executes each line of FILE as a job, as if the lines were given as inputs without a command.
+        Empty lines and lines beginning with `#` are skipped. No command or inputs may be given.
+
+    --container:
+        Executes each job within a new container of the given image, using podman if it is
+        installed, or docker otherwise.
+
+    --container-mount:
+        When used with --container, each input that is a path to a file or directory will be
+        mounted into the container, and input tokens will refer to the path within the container.
+
+    --dedupe:
+        Removes duplicate inputs before any job has been dispatched, keeping the first occurrence
+        of each input, and reports how many were skipped. Inputs are compared without their
+        leading and trailing whitespace. Can not be used with streamed inputs.
 
     --delay:
         Delays starting the next job for N amount of seconds, where the seconds can be fractional.
+        A range may also be given, such as `0.5-2`, to delay by a random amount within that range.
+
+    --delay-backoff:
+        Exponentially increases the delay between job starts while jobs are consecutively failing.
+        The backoff starts from the --delay value, or one second, and doubles with each failure.
 
     --dry-run:
         Prints the jobs that will be run to standard output, without running them.
 
+    --dry-run=FILE:
+        Writes the jobs that will be run to an executable shell script, which will exit
+        upon the first command that fails. The script may be reviewed and executed later.
+
+    --error-output TEMPLATE:
+        Writes the standard error of each job to the file named by expanding the input tokens
+        within TEMPLATE, as with --output.
+
     --eta:
         Prints the estimated time to complete based on average runtime of running processes.
 
+    --expand-tokens:
+        When no command was given, so that the inputs are themselves commands, replaces the {#},
+        {##}, and {%} tokens within each input before it is executed, honoring --seqreplace and
+        --slotreplace. The input is still recorded as it was given, such as within the job log.
+
+    --fail-on-output REGEX:
+        Considers a job to have failed if any line of its standard output or error matches
+        the regular expression, even if it exited successfully. Such jobs are recorded with
+        an exit value of 1, and will be retried, logged, and reported as failures.
+
+    --fifo-eof MARKER:
+        Ends the inputs that are read from --input-fifo at the line MARKER, instead of `EOF`.
+
+    --filter EXPR:
+        Only executes the inputs which satisfy EXPR, skipping the others before any job has been
+        dispatched. The expression is an operator followed by a value, optionally preceded by a
+        column number, which compares that column of the input, as separated by --colsep or by
+        whitespace, and by an `!`, which inverts the expression. The operators are `~` for a
+        regular expression match, `^=` and `$=` for a prefix and suffix, `=` for an exact match,
+        and `<`, `<=`, `>`, and `>=` for a numeric comparison, such as `--filter '2>=100'`.
+        If given multiple times, each input must satisfy every expression.
+
+    --follow:
+        Follows the file given after `::::`, which must be the only input list, like `tail -f`:
+        once its lines have been executed, jobs are started for lines as they are appended to
+        the file, until the program is interrupted. The file may be used as a simple task queue.
+
+    --group-by COL:
+        Enables --pipe, and sends every record whose value in the COL column, counting from 1,
+        is the same to the standard input of the same job, so that records may be aggregated
+        by group. Records do not need to be sorted. Can not be used with --stream.
+
+    --halt:
+        Stops starting new jobs once a condition has been met, given as WHEN,CONDITION=N.
+        WHEN may be `soon`, which allows running jobs to finish, or `now`, which kills them.
+        CONDITION may be `fail`, `success`, or `done`, and N may either be a number of jobs
+        or a percentage of all jobs, such as `soon,done=50%` or `now,success=1`. If the run
+        was halted by failed jobs, it exits with the exit value of the job that met the
+        condition, or 128 plus its signal if it was killed.
+
+    --halt-on-output [WHEN,]REGEX:
+        Stops starting new jobs once any job writes a line to its standard output or error
+        that matches the regular expression. WHEN may be `soon`, the default, which allows
+        running jobs to finish, or `now`, which kills them, such as `now,PANIC`. The run will
+        then exit with a status of 1.
+
     -j, --jobs:
         Defines the number of tasks to process in parallel.
         Values may be written as a number (12) or as a percent (150%).
         The default value is the number of CPU cores in the system.
+        The value may also be the path of a file that contains the number of tasks, which
+        will be re-read every second, so that a run may be throttled up or down while it is
+        running. Setting the file to 0 pauses the starting of tasks until it is raised.
+
+    --input-fifo PATH:
+        Reads inputs from the named pipe at PATH, which is created if it does not exist, and
+        starts jobs as inputs are written to it. Any number of processes may write inputs to
+        the pipe while the program is running, as the inputs only end once the line `EOF`, or
+        the line given by --fifo-eof, has been written to the pipe, or once the program has
+        been interrupted.
+
+    --ionice CLASS[:LEVEL]:
+        Spawns each job with the given IO scheduling priority on Linux, so that disk-heavy jobs
+        will not starve interactive processes of IO. The class may be `realtime`, `best-effort`,
+        or `idle`, and the level ranges from 0, the highest priority, to 7, defaulting to 4.
+        The idle class has no level. The realtime class requires elevated privileges.
 
     --joblog:
-        Logs job statistics to a designated file as they are completed.
+        Logs job statistics to a designated file as they are completed. Entries are always appended
+        to the file, and each entry is written while holding an advisory lock, so that a job log
+        may be shared by several instances of parallel.
 
     --joblog-8601:
         Writes the start time in the ISO 8601 format: `YYYY-MM-DD hh:mm:ss`
 
+    --joblog-template TEMPLATE:
+        Chooses the columns of the job log, and their order, such as
+        `{seq}\t{host}\t{runtime}\t{exitval}\t{command}`. The available columns are {seq},
+        {host}, {starttime}, {runtime}, {exitval}, {signal}, {maxrss}, {usertime}, {systime},
+        and {command}, where {maxrss} is the peak resident memory of the job in kilobytes, and
+        {usertime} and {systime} are the CPU time that the job spent in user and kernel mode.
+        Can not be used with --retry-failed, as only the default columns can be read back.
+
+    --json:
+        Writes job lifecycle events to the standard error, as with --log-events.
+
+    --keep-temp:
+        Keeps the directory of the run within the tempdir once the run has completed, which
+        contains the standard output and error of each job, and the unprocessed, processed, and
+        errors files, for debugging. Otherwise, the run's directory is removed.
+
+    --log-events:
+        Writes a JSON object to the designated file for each job that is started, finished, or
+        failed, followed by a summary of the run. A value of `-` denotes the standard error.
+
+    --log-file:
+        Writes the diagnostics that are enabled by --log-level to the designated file, which is
+        appended to, instead of to the standard error. If no level was given, the info level is used.
+
+    --log-level:
+        Writes diagnostics about the operation of parallel itself, such as how jobs are dispatched,
+        waits for memory, retries, and the handling of signals. The level may be error, warn, info,
+        debug, or trace, where each level also includes the levels before it.
+
+    --log-syslog:
+        Writes a record of each completed job, including the job number, exit value, runtime, and
+        command, to the system logger, which is useful when the standard error is discarded.
+
     --memfree:
-        Defines the minimum amount of memory available before starting the next job.
+        Defines the minimum amount of memory available before starting the next job. If parallel
+        is within a cgroup that limits its memory, such as a container, the memory remaining
+        within that limit will be used when it is less than the memory available to the system.
+
+    --memfree-poll:
+        Defines the interval, in seconds, at which available memory is checked while waiting for
+        the --memfree amount to become available. The default is 0.1 seconds.
+
+    --memfree-timeout:
+        Defines the maximum number of seconds to wait for the --memfree amount to become available.
+        Once reached, the running jobs will be terminated and parallel will exit with a failure.
 
     -n, --max-args:
         Groups up to a certain number of arguments together in the same
@@ -112,16 +306,98 @@ OPTIONS
         first argument, arguments three and four will become the second
         argument, and argument five will become the third argument.
 
+    --max-starts-per-second N:
+        Starts no more than N jobs per second across every job slot, such as to respect the
+        rate limit of an API. Up to N jobs may start at once, after which jobs are started
+        at the rate of N per second. N may be a fraction, such as `0.5`. Unlike --delay,
+        which waits a fixed time before every start, jobs start immediately while the rate
+        has not been exceeded.
+
+    --no-shell:
+        Never executes commands within a shell, even if the command contains shell
+        metacharacters. Commands will be split into arguments and executed directly.
+
+    --notify-cmd COMMAND:
+        Executes COMMAND within the shell once every job has finished, such as to send a
+        notification. A summary of the run is given by the PARALLEL_TOTAL, PARALLEL_SUCCEEDED,
+        PARALLEL_FAILED, PARALLEL_TIMED_OUT, and PARALLEL_RUNTIME variables.
+
     --num-cpu-cores:
         A convenience command that will print the number of CPU cores in the system.
 
+    --on-fail TEMPLATE:
+        Executes the command given by expanding the input tokens within TEMPLATE, such as
+        `notify-send failed {}`, for each job that fails after its retries. Inputs are quoted,
+        and the job's number, exit value, and signal are given by the PARALLEL_JOB,
+        PARALLEL_EXITVAL, and PARALLEL_SIGNAL variables.
+
+    --output TEMPLATE:
+        Writes the standard output of each job to the file named by expanding the input tokens
+        within TEMPLATE, such as `{/.}.log`, rather than printing it. Directories within the
+        path are created as needed.
+
     -p, --pipe:
         Instead of supplying arguments as arguments to child processes, apply
         them to the standard input of each child process.
 
+    --queue URL:
+        Shares a queue of inputs between any number of instances, which may be on other machines.
+        When inputs are given, they are added to the queue instead of being executed. Otherwise,
+        inputs are claimed from the queue one at a time and executed, until the queue is empty.
+        A URL of `redis://[[user]:password@]host[:port]/key` is the list `key` on a Redis server,
+        and a JSON record of each job, as written by --results-json, is pushed onto the list
+        `key:results`. Inputs are moved onto the list `key:processing` while their jobs are
+        running, which requires Redis 6.2 or later, so that the inputs of a worker which was
+        killed remain within that list, from which they may be moved back onto `key`. Any other
+        URL is an SQLite database, as given by --sqlmaster and --sqlworker.
+
     -q, --quote:
         Escapes the command argument supplied so that spaces, quotes, and slashes
-        are retained.
+        are retained. Each input that is substituted into the command will also be
+        quoted, so that inputs with spaces, quotes, or `$` are passed to the command
+        as a single argument.
+
+    --repeat N:
+        Executes each input N times, as N consecutive jobs. Combined with --summary, the
+        mean and standard deviation of the runtimes of each input will also be printed, to
+        benchmark each of the commands built from the inputs.
+
+    --require-output REGEX:
+        Considers a job to have failed unless a line of its standard output or error matches
+        the regular expression, even if it exited successfully. Such jobs are recorded with
+        an exit value of 1, and will be retried, logged, and reported as failures.
+
+    --results-json DIR:
+        Writes the metadata of each job to DIR/SEQ/job.json once the job has finished, where SEQ
+        is the job's sequence number. Each record is a JSON object holding the input, command,
+        exit value, signal, start time, runtime, peak memory, CPU times, and host of the job.
+
+    --retries N:
+        Retries a job with the same input up to N times while it fails. The outputs of every
+        attempt are printed once the job has finished.
+
+    --retry-backoff FACTOR:
+        Multiplies the delay between retries by FACTOR, such as `2x`, with each successive retry.
+        The backoff starts from the --retry-delay value, or one second.
+
+    --retry-delay SECONDS:
+        Waits for the given number of seconds, such as `5s`, before retrying a failed job.
+
+    --retry-failed:
+        Executes the commands of jobs that failed, as recorded within the file given to --joblog,
+        rather than obtaining inputs from the command line. Results are appended to the joblog.
+
+    --reverse:
+        Reverses the order of the inputs before any job has been dispatched, after they have been
+        sorted by --sort, if it was given. Can not be used with streamed inputs.
+
+    --seed N:
+        Seeds the random delays given by a --delay range with N, so that the delays of each
+        job slot will be the same each time that the run is repeated.
+
+    --seqreplace:
+        Defines the string that will be replaced with the job number, instead of {#}, so
+        that commands which contain {#} as a literal may still be used.
 
     -s, --silent, --quiet:
         Disables printing the standard output of running processes.
@@ -130,22 +406,146 @@ OPTIONS
         Grants ability to utilize the parallel command as an interpreter via
         calling it within a shebang line.
 
+    --shebang-wrap:
+        Executes the script containing the shebang line with the given interpreter in
+        parallel, supplying each argument that was given to the script as an input. If no
+        arguments were given, inputs will be read from standard input.
+
+            #!/usr/bin/parallel --shebang-wrap /usr/bin/python3
+
+    --shell:
+        Defines the shell that commands will be executed within when a shell is required,
+        such as `bash`. The value `env` will use the shell defined by the SHELL environment
+        variable, and the value `none` is equivalent to --no-shell. By default, ion, dash,
+        and then sh will be searched for.
+
     --shellquote:
         Prints commands that will be executed, with the commands quoted.
 
-    --tmpdir:
-        Defines the directory to use for temporary files.
-
-    --timeout:
+    --skip-comments, --skip-comments=PREFIX:
+        Skips blank lines, and lines which begin with PREFIX, or `#` if no prefix was given, when
+        reading inputs from files, from standard input, or from a stream, so that lists of inputs
+        may be annotated. Leading whitespace before the prefix is ignored. Lines of `::::` files
+        which begin with `#` are always skipped.
+
+    --slotreplace:
+        Defines the string that will be replaced with the slot number, instead of {%}.
+
+    --sort, --sort=ORDER:
+        Sorts the inputs before any job has been dispatched, so that jobs are processed in a
+        deterministic order when inputs come from an unordered source, such as `find`. The ORDER
+        may be `lexical`, the default, or `numeric`, which compares the number at the beginning
+        of each input, sorting inputs without a number lexically after the others. Can not be
+        used with streamed inputs.
+
+    --sqlmaster DB:
+        Adds the inputs to a queue within the SQLite database at DB, which is created if it does
+        not exist, instead of executing them. The inputs are executed by --sqlworker, which may
+        be given by any number of instances that share the database. DB is a path, which may be
+        given as `sqlite3:///path/to/db`. The queue is the `parallel` table, where the state of
+        each input, and the host, command, start time, runtime, exit value, and signal of its job
+        are recorded. Requires parallel to be built with the `sqlite` feature.
+
+    --sqlworker DB:
+        Claims inputs from the queue within DB, which were added by --sqlmaster, and executes the
+        command for each of them, recording the result of each job within the queue. Inputs are
+        claimed one at a time, as they are needed, and the run ends once every input has been
+        claimed. No other inputs may be given. The inputs of a worker which was killed remain in
+        the `running` state, and are not claimed again. Once no workers are running, they may be
+        returned to the queue with `UPDATE parallel SET state = 'pending' WHERE state = 'running'`.
+
+    --stall-timeout:
+        If a command does not write to its standard output or error for the specified number
+        of seconds, it will be killed with a SIGKILL, even if it has not reached the --timeout.
+
+    --status-file FILE:
+        Rewrites FILE every two seconds with a JSON object describing the progress of the run:
+        the total number of jobs, the numbers that have completed, succeeded, failed, timed out,
+        and are running, the elapsed time, and the estimated time remaining, in seconds.
+
+    --stream:
+        When reading inputs from standard input, jobs will begin executing while the standard
+        input is still being read, rather than after all inputs have been read. The total
+        number of jobs is unknown until the standard input is closed, so {N} tokens may not
+        be used in this mode.
+
+    --summary:
+        Once every job has finished, prints the outcomes of the jobs, the number of jobs
+        completed per second, the minimum, maximum, mean, and median runtimes of jobs, the
+        CPU time of the jobs against the wall time of the run, the job with the largest peak
+        resident memory, and the slowest jobs.
+
+    --sync:
+        Syncs the processed file and the job log to the disk after each entry is written, so
+        that they may be trusted to resume from after a power loss, at the cost of a write to
+        the disk for each job. Otherwise, entries of the processed file are buffered, and are
+        written when the run completes, or when it is interrupted.
+
+    --systemd-scope, --systemd-scope=PROPERTY,...:
+        Executes each job within a transient systemd scope unit with `systemd-run --scope --user`,
+        providing per-job resource accounting. Properties may be given for each scope, such as
+        `--systemd-scope=MemoryMax=1G,CPUQuota=50%`.
+
+    --tmpdir DIR:
+        Defines the directory to use for temporary files. By default, this is `parallel` within
+        the directory given by the TMPDIR variable, or `/tmp/parallel` if it is not set. Each run
+        writes its files within its own directory of the tempdir, which is named after its process
+        ID, or after the path of its checkpoint if --checkpoint was given. If the tempdir runs out
+        of space while jobs are running, the outputs of jobs will continue to be written within
+        `$XDG_CACHE_HOME/parallel`, or `~/.cache/parallel`, with a warning.
+
+    --timeout SECONDS, --timeout TEMPLATE:
         If a command runs for longer than a specified number of seconds, it will be
-        killed with a SIGKILL.
+        killed with a SIGKILL. The timeout may instead be a template of tokens, such as `{}`,
+        which is expanded from the input of each job, so that each job may be given its own
+        timeout. With --colsep, `{N}` is the Nth column of the job's input, such as
+        `--colsep , --timeout '{2}'`. Jobs whose timeout is not a number are not timed out.
+
+    --timestamp, --timestamp=FORMAT:
+        Prefixes each line of the standard output and error of jobs with the wall-clock time
+        at which it was read from the job, followed by a space. By default, the time is an ISO
+        8601 timestamp with milliseconds, such as `2017-07-14T04:40:00.250+02:00`, or it may be
+        given a strftime format, such as `--timestamp=%H:%M:%S`. Outputs are not timestamped
+        with --ungroup, as they are not captured.
+
+    --total-jobs N:
+        Declares that N jobs are expected when inputs are streamed from standard input,
+        whose total is otherwise not known until every input has been read. The ETA, and
+        the {##} token, will then be computed from N. The {##} token can not be used with
+        streamed inputs otherwise.
+
+    --trace-file:
+        Writes a JSON object to the designated file for each phase of each job, which are the
+        fetching of its input, the building and spawning of its command, the running of the
+        command, and the replaying of its outputs, along with the time spent within the phase.
+        The total and mean time spent within each phase is written once all jobs have been
+        processed. A value of `-` denotes the standard error.
+
+    -u, --ungroup:
+        The standard output and error of each job are inherited from parallel, so that they are
+        written directly as the job writes them, rather than being captured and written in order
+        once each job has completed. Outputs of jobs that run at the same time may be interleaved.
+        Can not be used with --output, --error-output, --color, --fail-on-output, --require-output,
+        --halt-on-output, or --stall-timeout, which require the outputs to be captured.
 
     -v, --verbose:
-        Print information about running processes.
+        Print information about running processes, including the peak resident memory and the
+        user and system CPU time of each job once it has completed.
 
     --version:
         Print version information.
 
+    --workdir DIR:
+        Executes each job within DIR, which is a template of tokens that is expanded from the
+        input of each job, such as `--workdir {} make ::: */` to execute make within every
+        directory. Jobs whose directory does not exist fail without being executed.
+
+ENVIRONMENT
+    PARALLEL:
+        Options which are parsed before the options given on the command line, so that
+        defaults such as `-j 75% --eta` may be set for every run. Options are separated by
+        whitespace, and may be quoted with single or double quotes.
+
 EXAMPLES
     # Command followed by inputs
     parallel -vun 'ffmpeg -i "{}" -c:a libopus -b:a 128k "{.}.opus"' ::: $(find -type f -name "*.flac")
@@ -202,7 +602,10 @@ HOW IT WORKS
         C. Messages from processes are sorted and printed in the order that
            inputs were given, as if each command was executed serially.
 
-        D. When processes complete, they are written to the processed file.
+        D. When processes complete, they are written to the processed file, as a record
+           of the job number and its input separated by a tab. Records are written to
+           the processed.partial file, which replaces the processed file once the run
+           has completed, or was interrupted.
 
         E. Once all processes have been completed, the program exits.
 
@@ -1,71 +1,313 @@
 /// Contains all functionality pertaining to parsing, tokenizing, and generating input arguments.
 pub mod errors;
-mod jobs;
+mod filter;
+pub mod jobs;
 mod man;
+mod options;
 mod redirection;
 
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::fs::{self, create_dir_all};
-use std::io::{self, BufRead, BufReader, BufWriter, Write};
-use std::num::ParseIntError;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::num::{ParseFloatError, ParseIntError};
 use std::path::{Path, PathBuf};
 use std::process::exit;
 use std::time::Duration;
 
-use arrayvec::ArrayVec;
 use permutate::Permutator;
+use execute::{JoblogTemplate, Timestamp};
+use execute::command::Records;
+use execute::container;
+use filepaths;
+use halt::{self, Halt, When};
+use regex::bytes::Regex;
+use ionice;
+use disk_buffer::BUFFER_SIZE;
+use logging::Level;
+use raw;
+use input_iterator::{InputStream, UNKNOWN_TOTAL};
+use queue::WorkQueue;
 use tokenizer::Token;
 use num_cpus;
 use self::errors::ParseErr;
+use self::filter::Filter;
 
 // Re-export key items from internal modules.
 pub use self::errors::FileErr;
+pub use self::filter::Filters;
+pub use self::options::{normalize as normalize_options, OptionErr};
 
-#[derive(PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 enum Mode { Arguments, Command, Inputs, InputsAppend, Files, FilesAppend }
 
-pub const INPUTS_ARE_COMMANDS: u16 = 1;
-pub const PIPE_IS_ENABLED:     u16 = 2;
-pub const SHELL_ENABLED:       u16 = 4;
-pub const QUIET_MODE:          u16 = 8;
-pub const VERBOSE_MODE:        u16 = 16;
-pub const DASH_EXISTS:         u16 = 32;
-pub const DRY_RUN:             u16 = 64;
-pub const SHELL_QUOTE:         u16 = 128;
-pub const ETA:                 u16 = 256;
-pub const JOBLOG:              u16 = 512;
-pub const JOBLOG_8601:         u16 = 1024;
-pub const ION_EXISTS:          u16 = 2048;
+/// The order that inputs are sorted in by the `--sort` parameter.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Sort { Lexical, Numeric }
+
+/// The markers which begin each list of inputs, or of files containing inputs, which may be changed with
+/// the `--arg-sep` and `--arg-file-sep` parameters. A marker followed by `+` appends to the previous list.
+struct Separators {
+    inputs: String,
+    files:  String,
+}
+
+impl Default for Separators {
+    fn default() -> Separators { Separators { inputs: ":::".to_owned(), files: "::::".to_owned() } }
+}
+
+impl Separators {
+    /// Returns the mode that the argument switches to, if the argument is a separator.
+    fn mode(&self, argument: &str) -> Option<Mode> {
+        let (marker, append) = if argument.ends_with('+') {
+            (&argument[..argument.len()-1], true)
+        } else {
+            (argument, false)
+        };
+
+        match (marker == self.inputs, marker == self.files, append) {
+            (true, _, false) => Some(Mode::Inputs),
+            (true, _, true)  => Some(Mode::InputsAppend),
+            (_, true, false) => Some(Mode::Files),
+            (_, true, true)  => Some(Mode::FilesAppend),
+            _                => None
+        }
+    }
+}
+
+/// The options which are either enabled or disabled, as given by the parameters, or as determined
+/// by the environment in which commands will be executed.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Config {
+    /// The inputs are themselves commands to execute, as no command was given.
+    pub inputs_are_commands: bool,
+    /// Inputs are written to the standard input of each command, as given by `--pipe`.
+    pub pipe:                bool,
+    /// Commands are executed within a shell.
+    pub shell_enabled:       bool,
+    /// The standard output and error of each command are discarded, as given by `--quiet`.
+    pub quiet:               bool,
+    /// Each command is printed as it is started, as given by `--verbose`.
+    pub verbose:             bool,
+    /// Commands are executed within `dash`, which was found to exist.
+    pub dash_exists:         bool,
+    /// Commands are printed instead of executed, as given by `--dry-run`.
+    pub dry_run:             bool,
+    /// Commands are printed with their arguments shell quoted, as given by `--shellquote`.
+    pub shell_quote:         bool,
+    /// The estimated time of completion is displayed, as given by `--eta`.
+    pub eta:                 bool,
+    /// Each job is recorded in a log, as given by `--joblog`.
+    pub joblog:              bool,
+    /// Times in the job log are written as ISO 8601 timestamps, as given by `--joblog-8601`.
+    pub joblog_8601:         bool,
+    /// Commands are executed within `ion`, which was found to exist.
+    pub ion_exists:          bool,
+    /// The delay between jobs grows as jobs fail, as given by `--delay-backoff`.
+    pub delay_backoff:       bool,
+    /// Only the failed jobs of a previous job log are executed, as given by `--retry-failed`.
+    pub retry_failed:        bool,
+    /// Commands are never executed within a shell, as given by `--no-shell`.
+    pub no_shell:            bool,
+    /// Inputs are quoted before they are substituted into commands, as given by `--quote`.
+    pub quote_inputs:        bool,
+    /// Each input that is a path is mounted into the container, as given by `--container-mount`.
+    pub container_mount:     bool,
+    /// Job events are written to the system log, as given by `--log-syslog`.
+    pub log_syslog:          bool,
+    /// The outputs of each job are colored, as given by `--color`.
+    pub color:               bool,
+    /// Inputs are grouped into records by a column, as given by `--group-by`.
+    pub group_by:            bool,
+    /// A summary of the jobs is printed once they have completed, as given by `--summary`.
+    pub summary:             bool,
+    /// Inputs are skipped unless they satisfy each `--filter` expression.
+    pub filter:              bool,
+    /// Duplicate inputs are skipped, as given by `--dedupe`.
+    pub dedupe:              bool,
+    /// Inputs are reordered, as given by `--sort` or `--reverse`.
+    pub sort:                bool,
+    /// The outputs of each command are inherited, rather than grouped by job, as given by `--ungroup`.
+    pub ungroup:             bool,
+    /// The processed file and the job log are synced to the disk after each entry, as given by `--sync`.
+    pub sync:                bool,
+    /// The files of the run are kept within the tempdir once the run has completed, as given by `--keep-temp`.
+    pub keep_temp:           bool,
+    /// The job and slot tokens within inputs that are commands are expanded, as given by `--expand-tokens`.
+    pub expand_tokens:       bool,
+}
+
+/// Separates the records of each input when records have been grouped with the `--group-by` parameter.
+pub const RECORD_SEPARATOR: char = '\x1e';
 
 /// `Args` is a collection of critical options and arguments that were collected at
 /// startup of the application.
 pub struct Args {
-    pub flags:     u16,
+    pub config:    Config,
     pub ncores:    usize,
+    pub jobs_file: Option<PathBuf>,
     pub ninputs:   usize,
+    pub total_jobs: Option<usize>,
+    pub max_args:  usize,
+    pub max_starts: Option<f64>,
+    pub nrecords:  usize,
+    /// The size of the buffers used for inputs and outputs on the disk, as given by `--buffer-size`.
+    pub buffer_size: usize,
     pub memory:    u64,
+    pub mem_poll:  Duration,
+    pub mem_timeout: Duration,
     pub delay:     Duration,
+    pub jitter:    Duration,
     pub timeout:   Duration,
-    pub arguments: ArrayVec<[Token; 128]>,
+    /// The template of the timeout of each job, if the `--timeout` parameter contains tokens.
+    pub timeout_template: Option<String>,
+    pub stall_timeout: Duration,
+    /// Each line of the outputs of jobs is prefixed with the time that it was read, as given by `--timestamp`.
+    pub timestamp: Option<Timestamp>,
+    pub retries:   usize,
+    pub repeat:    usize,
+    pub retry_delay: Duration,
+    pub retry_backoff: f64,
+    pub arguments: Vec<Token>,
     pub joblog:    Option<String>,
-    pub tempdir:   Option<PathBuf>,
+    pub joblog_template: Option<JoblogTemplate>,
+    pub checkpoint: Option<String>,
+    pub status_file: Option<PathBuf>,
+    pub events:    Option<String>,
+    /// The level and file that diagnostics are written to, as given by `--log-level` and `--log-file`.
+    pub log_level: Option<Level>,
+    pub log_file:  Option<String>,
+    pub trace:     Option<String>,
+    /// The directory that the metadata of each job will be written within, as given by `--results-json`.
+    pub results:   Option<String>,
+    pub output:    Option<String>,
+    pub on_fail:   Option<String>,
+    /// The template of the directory that each job will be executed within, as given by `--workdir`.
+    pub workdir:   Option<String>,
+    pub notify:    Option<String>,
+    pub error_output: Option<String>,
+    pub shell:     Option<String>,
+    pub dry_run:   Option<String>,
+    pub halt:      Option<Halt>,
+    pub halt_on_output: Option<(When, Regex)>,
+    pub fail_on_output: Option<Regex>,
+    pub require_output: Option<Regex>,
+    pub group_by:  Option<usize>,
+    pub colsep:    Option<String>,
+    pub ionice:    Option<u16>,
+    pub wrapper:   Vec<String>,
+    pub seed:      Option<u64>,
+    pub seqreplace:  Option<String>,
+    pub slotreplace: Option<String>,
+    pub stream:    Option<InputStream>,
+    /// The queue that the inputs will be added to, instead of being executed, as given by `--sqlmaster`, or by
+    /// `--queue` when inputs are given.
+    pub master:    Option<String>,
+    /// The queue that inputs are claimed from, and that results are recorded within, as given by `--sqlworker`,
+    /// or by `--queue` when no inputs are given.
+    pub queue:     Option<WorkQueue>,
 }
 
 impl Args {
     pub fn new() -> Args {
         Args {
             ncores:    num_cpus::get(),
-            flags:     0,
-            arguments: ArrayVec::new(),
+            jobs_file: None,
+            config:    Config::default(),
+            arguments: Vec::new(),
             ninputs:   0,
+            total_jobs: None,
+            max_args:  0,
+            max_starts: None,
+            nrecords:  0,
+            buffer_size: BUFFER_SIZE,
             memory:    0,
+            mem_poll:  Duration::from_millis(100),
+            mem_timeout: Duration::from_millis(0),
             delay:     Duration::from_millis(0),
+            jitter:    Duration::from_millis(0),
             timeout:   Duration::from_millis(0),
+            timeout_template: None,
+            stall_timeout: Duration::from_millis(0),
+            timestamp: None,
+            retries:   0,
+            repeat:    1,
+            retry_delay: Duration::from_millis(0),
+            retry_backoff: 1.0,
             joblog:    None,
-            tempdir:   None,
+            joblog_template: None,
+            checkpoint: None,
+            status_file: None,
+            events:    None,
+            log_level: None,
+            log_file:  None,
+            trace:     None,
+            results:   None,
+            output:    None,
+            on_fail:   None,
+            workdir:   None,
+            notify:    None,
+            error_output: None,
+            shell:     None,
+            dry_run:   None,
+            halt:      None,
+            halt_on_output: None,
+            fail_on_output: None,
+            require_output: None,
+            group_by:  None,
+            colsep:    None,
+            ionice:    None,
+            wrapper:   Vec::new(),
+            seed:      None,
+            seqreplace:  None,
+            slotreplace: None,
+            stream:    None,
+            master:    None,
+            queue:     None,
         }
     }
 
+    /// Describes the range of records within each input, for the `{seq-start}` and `{seq-end}` tokens.
+    /// Records that were grouped by the `--group-by` parameter, skipped by the `--filter` and `--dedupe`
+    /// parameters, or reordered by the `--sort` and `--reverse` parameters, are not consecutive, so each
+    /// input is then considered to be a single record.
+    pub fn records(&self) -> Records {
+        if self.config.group_by || self.config.filter || self.config.dedupe || self.config.sort || self.max_args < 2 {
+            Records { per_input: 1, total: self.distinct_inputs(), repeat: self.repeat }
+        } else {
+            Records { per_input: self.max_args, total: self.nrecords, repeat: self.repeat }
+        }
+    }
+
+    /// The total number of jobs that is displayed, and that the ETA is computed from. When inputs are
+    /// streamed, the total is not known in advance, and may instead be given by the `--total-jobs` parameter.
+    pub fn expected_total(&self) -> usize {
+        if self.ninputs == UNKNOWN_TOTAL { self.total_jobs.unwrap_or(UNKNOWN_TOTAL) } else { self.ninputs }
+    }
+
+    /// The number of inputs, before each input has been repeated by the `--repeat` parameter.
+    pub fn distinct_inputs(&self) -> usize {
+        if self.ninputs == UNKNOWN_TOTAL { UNKNOWN_TOTAL } else { self.ninputs / self.repeat }
+    }
+
+    /// Sets the number of jobs to run in parallel, where zero selects the number of CPU cores. If
+    /// the number was read from a file, zero will instead pause jobs until the file is changed.
+    fn set_jobs(&mut self, jobs: usize, file: Option<PathBuf>) {
+        if jobs != 0 || file.is_some() { self.ncores = jobs; }
+        self.jobs_file = file;
+    }
+
+    /// Inputs which are streamed can not be grouped, deduplicated, or sorted, as every record must be read first.
+    /// Their total is also unknown, so the `{##}` token of the command requires the `--total-jobs` parameter.
+    fn reject_streamed(&self, comm: &str) -> Result<(), ParseErr> {
+        if self.group_by.is_some() { return Err(ParseErr::GroupByStream); }
+        if self.config.dedupe { return Err(ParseErr::DedupeStream); }
+        if self.config.sort { return Err(ParseErr::SortStream); }
+        if self.total_jobs.is_none() && comm.contains("{##}") { return Err(ParseErr::JobTotalStream); }
+        Ok(())
+    }
+
     /// Performs all the work related to parsing program arguments
     pub fn parse(&mut self, comm: &mut String, arguments: &[String], base_path: &mut PathBuf)
         -> Result<usize, ParseErr>
@@ -80,19 +322,49 @@ impl Args {
         let mut number_of_arguments = 0;
         // If the `--quote` parameter was passed, this will quote the command argument.
         let mut quote_enabled = false;
+        // If the `--stream` parameter was passed, jobs will start while the standard input is read.
+        let mut stream = false;
+        // If the `--follow` parameter was passed, lines appended to the `::::` file will also become inputs.
+        let mut follow = false;
+        let mut followed: Option<&str> = None;
+        // If the `--input-fifo` parameter was passed, inputs will be read from a named pipe until the end marker.
+        let mut input_fifo: Option<&str> = None;
+        let mut fifo_eof = String::from("EOF");
+        // If the `--sqlworker` parameter was passed, inputs will be claimed from the queue within this database.
+        let mut sqlworker: Option<&str> = None;
+        // If the `--queue` parameter was passed, inputs will be added to this queue, or claimed from it.
+        let mut queue: Option<&str> = None;
+        // If the `--skip-comments` parameter was passed, blank lines and lines beginning with the prefix are skipped.
+        let mut comments: Option<String> = None;
+        // If the `--sort` or `--reverse` parameters were passed, inputs will be reordered before they are executed.
+        let mut sort: Option<Sort> = None;
+        let mut reverse = false;
+        // If the `--filter` parameter was passed, only the inputs which satisfy every filter will become jobs.
+        let mut filters: Vec<Filter> = Vec::new();
+        // If the `--commands-file` parameter was passed, its commands will be executed as the jobs.
+        let mut commands_file: Option<String> = None;
+        // If the `--commands-delimiter` parameter was passed, commands may span multiple lines.
+        let mut commands_delimiter: Option<String> = None;
+        // The markers which separate the lists of inputs, which may be changed by `--arg-sep` and `--arg-file-sep`.
+        let mut separators = Separators::default();
+
+        // Without any options, the default tempdir is used, and inputs are written within the run's directory.
+        if arguments.len() <= 1 { *base_path = filepaths::run_dir(base_path, None); }
 
         // If no arguments were passed, we can assume that the standard input will be parsing commands.
         // Otherwise, we will parse all the arguments and take actions based on these inputs.
-        if env::args().len() > 1 {
+        if arguments.len() > 1 {
             // The first argument defines which `mode` to shift into and which argument `index` to start from.
-            let (mut mode, mut index) = match arguments[1].as_str() {
-                ":::"  | ":::+"  => (Mode::Inputs, 2),
-                "::::" | "::::+" => (Mode::Files, 2),
-                _                => (Mode::Arguments, 1)
+            let (mut mode, mut index) = match separators.mode(&arguments[1]) {
+                Some(Mode::Inputs) | Some(Mode::InputsAppend) => (Mode::Inputs, 2),
+                Some(_) => (Mode::Files, 2),
+                None    => (Mode::Arguments, 1)
             };
 
             // If the `--shebang` parameter was passed, this will be set to `true`.
             let mut shebang = false;
+            // Once `--` has been given, the arguments that follow will not be parsed as options.
+            let mut options_ended = false;
 
             if let Mode::Arguments = mode {
                 // Parse arguments until the command has been found.
@@ -104,12 +376,12 @@ impl Args {
 
                     // If the first character is a '-' then it will be processed as an argument.
                     // We can guarantee that there will always be at least one character.
-                    if char_iter.next().unwrap() == b'-' {
+                    if !options_ended && char_iter.next().unwrap() == b'-' {
                         // If the second character exists, everything's OK.
                         let character = char_iter.next().ok_or_else(|| ParseErr::InvalidArgument(index-1))?;
                         if character == b'j' {
-                            let val = parse_jobs(argument, arguments.get(index), &mut index)?;
-                            if val != 0 { self.ncores = val; }
+                            let (val, file) = parse_jobs(argument, arguments.get(index), &mut index)?;
+                            self.set_jobs(val, file);
                         } else if character == b'n' {
                             max_args = parse_max_args(argument, arguments.get(index), &mut index)?;
                         } else if character != b'-' {
@@ -119,10 +391,11 @@ impl Args {
                                         println!("{}", man::MAN_PAGE);
                                         exit(0);
                                     },
-                                    b'p' => self.flags |= PIPE_IS_ENABLED,
-                                    b'q' => quote_enabled = true,
-                                    b's' => self.flags |= QUIET_MODE,
-                                    b'v' => self.flags |= VERBOSE_MODE,
+                                    b'p' => self.config.pipe = true,
+                                    b'q' => { quote_enabled = true; self.config.quote_inputs = true; },
+                                    b's' => self.config.quiet = true,
+                                    b'u' => self.config.ungroup = true,
+                                    b'v' => self.config.verbose = true,
                                     _ => {
                                         let stderr = io::stderr();
                                         let _ = writeln!(stderr.lock(), "parallel: unsupported argument: '-{}'", character as char);
@@ -132,86 +405,417 @@ impl Args {
                         } else {
                             // NOTE: Long mode versions of arguments
                             match &argument[2..] {
+                                // The command follows, even if it begins with a `-`.
+                                "" => options_ended = true,
+                                "arg-file-sep" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::ArgFileSepNoValue)?;
+                                    separators.files = val.to_owned();
+                                    index += 1;
+                                },
+                                "arg-sep" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::ArgSepNoValue)?;
+                                    separators.inputs = val.to_owned();
+                                    index += 1;
+                                },
+                                "buffer-size" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::BufferSizeNoValue)?;
+                                    self.buffer_size = match parse_memory(val) {
+                                        Ok(size) if size != 0 => size as usize,
+                                        _ => return Err(ParseErr::BufferSizeInvalid(index))
+                                    };
+                                    index += 1;
+                                },
+                                "checkpoint" => {
+                                    let file = arguments.get(index).ok_or(ParseErr::CheckpointNoValue)?;
+                                    self.checkpoint = Some(file.to_owned());
+                                    index += 1;
+                                },
+                                "colsep" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::ColsepNoValue)?;
+                                    self.colsep = Some(val.to_owned());
+                                    index += 1;
+                                },
+                                "color" => self.config.color = true,
+                                "commands-delimiter" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::CommandsDelimiterNoValue)?;
+                                    commands_delimiter = Some(val.to_owned());
+                                    index += 1;
+                                },
+                                "commands-file" => {
+                                    let file = arguments.get(index).ok_or(ParseErr::CommandsFileNoValue)?;
+                                    commands_file = Some(file.to_owned());
+                                    index += 1;
+                                },
+                                "container" => {
+                                    let image = arguments.get(index).ok_or(ParseErr::ContainerNoValue)?;
+                                    self.wrapper = container::command(image);
+                                    index += 1;
+                                },
+                                "container-mount" => self.config.container_mount = true,
+                                "dedupe" => self.config.dedupe = true,
                                 "delay" => {
                                     let val = arguments.get(index).ok_or(ParseErr::DelayNoValue)?;
-                                    let seconds = val.parse::<f64>().map_err(|_| ParseErr::DelayNaN(index))?;
-                                    self.delay = Duration::from_millis((seconds * 1000f64) as u64);
+                                    let (delay, jitter) = parse_delay(val).map_err(|_| ParseErr::DelayNaN(index))?;
+                                    self.delay = delay;
+                                    self.jitter = jitter;
+                                    index += 1;
+                                },
+                                "delay-backoff" => self.config.delay_backoff = true,
+                                "dry-run" => self.config.dry_run = true,
+                                "error-output" => {
+                                    let template = arguments.get(index).ok_or(ParseErr::ErrorOutputNoValue)?;
+                                    self.error_output = Some(template.to_owned());
+                                    index += 1;
+                                },
+                                "eta" => { self.config.eta = true; self.config.quiet = true; },
+                                "expand-tokens" => self.config.expand_tokens = true,
+                                "fail-on-output" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::FailOnOutputNoValue)?;
+                                    let pattern = Regex::new(val).map_err(|why| ParseErr::FailOnOutputInvalid(why.to_string()))?;
+                                    self.fail_on_output = Some(pattern);
+                                    index += 1;
+                                },
+                                "fifo-eof" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::FifoEofNoValue)?;
+                                    fifo_eof = val.to_owned();
+                                    index += 1;
+                                },
+                                "filter" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::FilterNoValue)?;
+                                    filters.push(Filter::parse(val).map_err(ParseErr::FilterInvalid)?);
+                                    self.config.filter = true;
+                                    index += 1;
+                                },
+                                "follow" => follow = true,
+                                "group-by" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::GroupByNoValue)?;
+                                    let column = val.parse::<usize>().map_err(|_| ParseErr::GroupByNaN(index))?;
+                                    if column == 0 { return Err(ParseErr::GroupByNaN(index)); }
+                                    self.group_by = Some(column);
+                                    self.config.group_by = true;
+                                    self.config.pipe = true;
+                                    index += 1;
+                                },
+                                "halt" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::HaltNoValue)?;
+                                    self.halt = Halt::parse(val).map_err(|_| ParseErr::HaltInvalid(index))?;
+                                    index += 1;
+                                },
+                                "halt-on-output" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::HaltOnOutputNoValue)?;
+                                    self.halt_on_output = Some(halt::parse_output(val).map_err(ParseErr::HaltOnOutputInvalid)?);
                                     index += 1;
                                 },
-                                "dry-run" => self.flags |= DRY_RUN,
-                                "eta" => self.flags |= ETA + QUIET_MODE,
                                 "help" => {
                                     println!("{}", man::MAN_PAGE);
                                     exit(0);
                                 },
+                                "input-fifo" => {
+                                    input_fifo = Some(arguments.get(index).ok_or(ParseErr::InputFifoNoValue)?);
+                                    index += 1;
+                                },
+                                "ionice" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::IoniceNoValue)?;
+                                    self.ionice = Some(ionice::parse(val).map_err(|_| ParseErr::IoniceInvalid(index))?);
+                                    index += 1;
+                                },
                                 "joblog" => {
                                     let file = arguments.get(index).ok_or(ParseErr::JoblogNoValue)?;
                                     self.joblog = Some(file.to_owned());
                                     index += 1;
-                                    self.flags |= JOBLOG;
+                                    self.config.joblog = true;
+                                },
+                                "joblog-8601" => self.config.joblog_8601 = true,
+                                "joblog-template" => {
+                                    let template = arguments.get(index).ok_or(ParseErr::JoblogTemplateNoValue)?;
+                                    self.joblog_template = Some(JoblogTemplate::parse(template)
+                                        .map_err(ParseErr::JoblogTemplateInvalid)?);
+                                    index += 1;
                                 },
-                                "joblog-8601" => self.flags |= JOBLOG_8601,
+                                "json" => self.events = Some(String::from("-")),
                                 "jobs" => {
-                                    let val = jobs::parse(arguments.get(index).ok_or(ParseErr::JobsNoValue)?)?;
-                                    if val != 0 { self.ncores = val; }
+                                    let (val, file) = jobs::parse_value(arguments.get(index).ok_or(ParseErr::JobsNoValue)?)?;
+                                    self.set_jobs(val, file);
+                                    index += 1;
+                                },
+                                "keep-temp" => self.config.keep_temp = true,
+                                "no-shell" => self.config.no_shell = true,
+                                "notify-cmd" => {
+                                    let command = arguments.get(index).ok_or(ParseErr::NotifyNoValue)?;
+                                    self.notify = Some(command.to_owned());
                                     index += 1;
                                 },
                                 "num-cpu-cores" => {
                                     println!("{}", num_cpus::get());
                                     exit(0);
                                 },
+                                "log-events" => {
+                                    let file = arguments.get(index).ok_or(ParseErr::EventsNoValue)?;
+                                    self.events = Some(file.to_owned());
+                                    index += 1;
+                                },
+                                "log-file" => {
+                                    let file = arguments.get(index).ok_or(ParseErr::LogFileNoValue)?;
+                                    self.log_file = Some(file.to_owned());
+                                    index += 1;
+                                },
+                                "log-level" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::LogLevelNoValue)?;
+                                    self.log_level = Some(Level::parse(val).ok_or(ParseErr::LogLevelInvalid(index))?);
+                                    index += 1;
+                                },
+                                "log-syslog" => self.config.log_syslog = true,
                                 "max-args" => {
                                     let val = arguments.get(index).ok_or(ParseErr::MaxArgsNoValue)?;
                                     max_args = val.parse::<usize>().map_err(|_| ParseErr::MaxArgsNaN(index))?;
                                     index += 1;
                                 },
-                                "mem-free" => {
+                                "max-starts-per-second" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::MaxStartsNoValue)?;
+                                    self.max_starts = match val.parse::<f64>() {
+                                        Ok(rate) if rate > 0f64 => Some(rate),
+                                        _ => return Err(ParseErr::MaxStartsNaN(index))
+                                    };
+                                    index += 1;
+                                },
+                                "mem-free" | "memfree" => {
                                     let val = arguments.get(index).ok_or(ParseErr::MemNoValue)?;
                                     self.memory = parse_memory(val).map_err(|_| ParseErr::MemInvalid(index))?;
                                     index += 1;
                                 },
-                                "pipe" => self.flags |= PIPE_IS_ENABLED,
-                                "quiet" | "silent" => self.flags |= QUIET_MODE,
-                                "quote" => quote_enabled = true,
-                                "shellquote" => self.flags |= DRY_RUN + SHELL_QUOTE,
+                                "memfree-poll" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::MemPollNoValue)?;
+                                    let seconds = val.parse::<f64>().map_err(|_| ParseErr::MemPollNaN(index))?;
+                                    self.mem_poll = Duration::from_millis((seconds * 1000f64) as u64);
+                                    index += 1;
+                                },
+                                "memfree-timeout" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::MemTimeoutNoValue)?;
+                                    let seconds = val.parse::<f64>().map_err(|_| ParseErr::MemTimeoutNaN(index))?;
+                                    self.mem_timeout = Duration::from_millis((seconds * 1000f64) as u64);
+                                    index += 1;
+                                },
+                                "on-fail" => {
+                                    let command = arguments.get(index).ok_or(ParseErr::OnFailNoValue)?;
+                                    self.on_fail = Some(command.to_owned());
+                                    index += 1;
+                                },
+                                "output" => {
+                                    let template = arguments.get(index).ok_or(ParseErr::OutputNoValue)?;
+                                    self.output = Some(template.to_owned());
+                                    index += 1;
+                                },
+                                "pipe" => self.config.pipe = true,
+                                "quiet" | "silent" => self.config.quiet = true,
+                                "queue" => {
+                                    let url = arguments.get(index).ok_or(ParseErr::QueueNoValue)?;
+                                    if !cfg!(feature = "sqlite") && !url.starts_with("redis://") {
+                                        return Err(ParseErr::SqlDisabled);
+                                    }
+                                    queue = Some(url);
+                                    index += 1;
+                                },
+                                "quote" => { quote_enabled = true; self.config.quote_inputs = true; },
+                                "repeat" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::RepeatNoValue)?;
+                                    self.repeat = match val.parse::<usize>() {
+                                        Ok(repeat) if repeat != 0 => repeat,
+                                        _ => return Err(ParseErr::RepeatNaN(index))
+                                    };
+                                    index += 1;
+                                },
+                                "reverse" => { reverse = true; self.config.sort = true; },
+                                "require-output" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::RequireOutputNoValue)?;
+                                    let pattern = Regex::new(val).map_err(|why| ParseErr::RequireOutputInvalid(why.to_string()))?;
+                                    self.require_output = Some(pattern);
+                                    index += 1;
+                                },
+                                "results-json" => {
+                                    let dir = arguments.get(index).ok_or(ParseErr::ResultsJsonNoValue)?;
+                                    self.results = Some(dir.to_owned());
+                                    index += 1;
+                                },
+                                "retries" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::RetriesNoValue)?;
+                                    self.retries = val.parse::<usize>().map_err(|_| ParseErr::RetriesNaN(index))?;
+                                    index += 1;
+                                },
+                                "retry-backoff" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::RetryBackoffNoValue)?;
+                                    // The factor may be written as a multiplier, such as `2x`.
+                                    self.retry_backoff = match val.trim_end_matches('x').parse::<f64>() {
+                                        Ok(factor) if factor >= 1.0 => factor,
+                                        _ => return Err(ParseErr::RetryBackoffNaN(index))
+                                    };
+                                    index += 1;
+                                },
+                                "retry-delay" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::RetryDelayNoValue)?;
+                                    // The delay may be written with a unit of seconds, such as `5s`.
+                                    let seconds = val.trim_end_matches('s').parse::<f64>()
+                                        .map_err(|_| ParseErr::RetryDelayNaN(index))?;
+                                    self.retry_delay = Duration::from_millis((seconds * 1000f64) as u64);
+                                    index += 1;
+                                },
+                                "retry-failed" => self.config.retry_failed = true,
+                                "shell" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::ShellNoValue)?;
+                                    match val.as_str() {
+                                        "none" => self.config.no_shell = true,
+                                        "env" => {
+                                            let shell = env::var("SHELL").map_err(|_| ParseErr::ShellNotSet)?;
+                                            self.shell = Some(shell);
+                                        },
+                                        _ => self.shell = Some(val.to_owned())
+                                    }
+                                    index += 1;
+                                },
+                                "seed" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::SeedNoValue)?;
+                                    self.seed = Some(val.parse::<u64>().map_err(|_| ParseErr::SeedNaN(index))?);
+                                    index += 1;
+                                },
+                                "seqreplace" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::SeqReplaceNoValue)?;
+                                    self.seqreplace = Some(val.to_owned());
+                                    index += 1;
+                                },
+                                "shellquote" => { self.config.dry_run = true; self.config.shell_quote = true; },
+                                "slotreplace" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::SlotReplaceNoValue)?;
+                                    self.slotreplace = Some(val.to_owned());
+                                    index += 1;
+                                },
+                                "sort" => { sort = Some(Sort::Lexical); self.config.sort = true; },
+                                "sqlmaster" => {
+                                    let url = arguments.get(index).ok_or(ParseErr::SqlMasterNoValue)?;
+                                    if !cfg!(feature = "sqlite") { return Err(ParseErr::SqlDisabled); }
+                                    self.master = Some(url.to_owned());
+                                    index += 1;
+                                },
+                                "sqlworker" => {
+                                    let url = arguments.get(index).ok_or(ParseErr::SqlWorkerNoValue)?;
+                                    if !cfg!(feature = "sqlite") { return Err(ParseErr::SqlDisabled); }
+                                    sqlworker = Some(url);
+                                    index += 1;
+                                },
+                                "stall-timeout" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::StallTimeoutNoValue)?;
+                                    let seconds = val.parse::<f64>().map_err(|_| ParseErr::StallTimeoutNaN(index))?;
+                                    self.stall_timeout = Duration::from_millis((seconds * 1000f64) as u64);
+                                    index += 1;
+                                },
+                                "status-file" => {
+                                    let file = arguments.get(index).ok_or(ParseErr::StatusFileNoValue)?;
+                                    self.status_file = Some(PathBuf::from(file));
+                                    index += 1;
+                                },
+                                "stream" => stream = true,
+                                "summary" => self.config.summary = true,
+                                "sync" => self.config.sync = true,
                                 "timeout" => {
                                     let val = arguments.get(index).ok_or(ParseErr::TimeoutNoValue)?;
-                                    let seconds = val.parse::<f64>().map_err(|_| ParseErr::TimeoutNaN(index))?;
-                                    self.timeout = Duration::from_millis((seconds * 1000f64) as u64);
+                                    // A timeout which contains tokens is expanded from the input of each job.
+                                    if val.contains('{') {
+                                        self.timeout_template = Some(val.to_owned());
+                                    } else {
+                                        let seconds = val.parse::<f64>().map_err(|_| ParseErr::TimeoutNaN(index))?;
+                                        self.timeout = Duration::from_millis((seconds * 1000f64) as u64);
+                                    }
+                                    index += 1;
+                                },
+                                "total-jobs" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::TotalJobsNoValue)?;
+                                    self.total_jobs = Some(val.parse::<usize>().map_err(|_| ParseErr::TotalJobsNaN(index))?);
+                                    index += 1;
+                                },
+                                "trace-file" => {
+                                    let file = arguments.get(index).ok_or(ParseErr::TraceFileNoValue)?;
+                                    self.trace = Some(file.to_owned());
                                     index += 1;
                                 },
-                                "verbose" => self.flags |= VERBOSE_MODE,
+                                "verbose" => self.config.verbose = true,
                                 "version" => {
                                     println!("MIT/Rust Parallel {}", env!("CARGO_PKG_VERSION"));
                                     exit(0);
                                 },
                                 "tmpdir" | "tempdir" => {
-                                    *base_path = PathBuf::from(arguments.get(index).ok_or(ParseErr::WorkDirNoValue)?);
+                                    // The tempdir is created once inputs are written to it.
+                                    *base_path = PathBuf::from(arguments.get(index).ok_or(ParseErr::TmpdirNoValue)?);
                                     index += 1;
-
-                                    // Create the base directory if it does not exist
-                                    if let Err(why) = create_dir_all(base_path.as_path()) {
-                                        let stderr = io::stderr();
-                                        let stderr = &mut stderr.lock();
-                                        let _ = writeln!(stderr, "parallel: unable to create tempdir {:?}: {}", base_path.as_path(), why);
-                                        exit(1);
-                                    }
                                 }
-                                _ if &argument[2..9] == "shebang" => {
+                                "ungroup" => self.config.ungroup = true,
+                                "workdir" => {
+                                    let dir = arguments.get(index).ok_or(ParseErr::WorkDirNoValue)?;
+                                    self.workdir = Some(dir.to_owned());
+                                    index += 1;
+                                },
+                                _ if argument[2..].starts_with("dry-run=") => {
+                                    self.dry_run = Some(argument[10..].to_owned());
+                                    self.config.dry_run = true;
+                                },
+                                "skip-comments" => comments = Some(String::from("#")),
+                                _ if argument[2..].starts_with("skip-comments=") => {
+                                    if argument.len() == 16 { return Err(ParseErr::SkipCommentsNoValue); }
+                                    comments = Some(argument[16..].to_owned());
+                                },
+                                "timestamp" => self.timestamp = Some(Timestamp::Iso8601),
+                                _ if argument[2..].starts_with("timestamp=") => {
+                                    self.timestamp = Some(Timestamp::parse(&argument[12..])
+                                        .map_err(ParseErr::TimestampInvalid)?);
+                                },
+                                _ if argument[2..].starts_with("sort=") => {
+                                    sort = match &argument[7..] {
+                                        "lexical" => Some(Sort::Lexical),
+                                        "numeric" => Some(Sort::Numeric),
+                                        order     => return Err(ParseErr::SortInvalid(order.to_owned()))
+                                    };
+                                    self.config.sort = true;
+                                },
+                                _ if argument[2..].split('=').next() == Some("systemd-scope") => {
+                                    // Each job will be executed within a transient systemd scope unit,
+                                    // with each comma-separated property applied to the unit.
+                                    self.wrapper = ["systemd-run", "--scope", "--user", "--quiet"].iter()
+                                        .map(|&x| x.to_owned()).collect();
+                                    if argument[2..].starts_with("systemd-scope=") {
+                                        for property in argument[16..].split(',').filter(|x| !x.is_empty()) {
+                                            self.wrapper.push(["--property=", property].concat());
+                                        }
+                                    }
+                                    self.wrapper.push(String::from("--"));
+                                },
+                                _ if argument[2..].starts_with("shebang-wrap") => {
+                                    // The kernel supplies every argument in the shebang line as a single
+                                    // argument, so the interpreter may follow within the same argument.
+                                    let mut interpreter = argument[14..].trim().to_owned();
+                                    if interpreter.is_empty() {
+                                        interpreter = arguments.get(index).ok_or(ParseErr::ShebangWrapNoValue)?.to_owned();
+                                        index += 1;
+                                    }
+
+                                    // The script containing the shebang is executed by the interpreter,
+                                    // and the arguments that follow the script are its inputs.
+                                    let script = arguments.get(index).ok_or(ParseErr::ShebangWrapNoValue)?;
+                                    index += 1;
+                                    comm.push_str(&interpreter);
+                                    comm.push(' ');
+                                    comm.push_str(script);
+                                    mode = Mode::Inputs;
+                                    break
+                                },
+                                _ if argument == "--shebang" || argument.starts_with("--shebang ") => {
                                     shebang = true;
-                                    comm.push_str(&argument[10..]);
+                                    comm.push_str(argument.get(10..).unwrap_or(""));
                                     break
                                 },
-                                _ => {
-                                    let stderr = io::stderr();
-                                    let _ = writeln!(stderr.lock(), "parallel: unsupported argument: '{}'", argument);
-                                }
+                                _ => return Err(ParseErr::InvalidArgument(index-1))
                             }
                         }
                     } else {
-                        match argument.as_str() {
-                            ":::"  => mode = Mode::Inputs,
-                            "::::" => mode = Mode::Files,
+                        match separators.mode(argument) {
+                            Some(Mode::Inputs) => mode = Mode::Inputs,
+                            Some(Mode::Files)  => mode = Mode::Files,
                             _ => {
                                 // The command has been supplied, and argument parsing is over.
                                 if quote_enabled {
@@ -227,27 +831,55 @@ impl Args {
                 }
             }
 
+            // Now that the tempdir and the checkpoint are known, inputs will be written within the run's directory.
+            *base_path = filepaths::run_dir(base_path, self.checkpoint.as_ref().map(|x| x.as_str()));
+
+            // When retrying failed jobs, the commands that were logged will be executed instead.
+            if self.config.retry_failed { comm.clear(); }
+
             // If no command was supplied, then inputs are the commands
             if comm.is_empty() {
-                self.flags |= INPUTS_ARE_COMMANDS;
+                self.config.inputs_are_commands = true;
             } else {
                 // Ensure that the command has each possible quote terminated
                 if !quote_enabled { check_command(comm.as_str())?; }
             }
 
-            if let Some(path) = redirection::input_was_redirected() {
+            // Outputs which are inherited by the jobs can not be captured, so they can not be redirected or scanned.
+            if self.config.ungroup && (self.output.is_some() || self.error_output.is_some() || self.config.color
+                || self.fail_on_output.is_some() || self.require_output.is_some() || self.halt_on_output.is_some()
+                || self.stall_timeout != Duration::from_millis(0))
+            {
+                return Err(ParseErr::UngroupCaptured);
+            }
+
+            if let Some(ref path) = commands_file {
+                // The commands within the file are the jobs, so no other command or inputs may be given.
+                if mode != Mode::Arguments { return Err(ParseErr::CommandsFileArguments); }
+                commands_parse(&mut current_inputs, path, commands_delimiter.as_ref().map(|x| x.as_str()))?;
+            } else if self.config.retry_failed {
+                let path = self.joblog.as_ref().ok_or(ParseErr::JoblogNoValue)?;
+                // Only the default columns of the job log can be parsed to find the jobs that failed.
+                if self.joblog_template.is_some() { return Err(ParseErr::JoblogTemplateRetry); }
+                joblog_parse(&mut current_inputs, path)?;
+                if current_inputs.is_empty() {
+                    let stderr = io::stderr();
+                    let _ = writeln!(stderr.lock(), "parallel: no failed jobs were found in {}", path);
+                    exit(0);
+                }
+            } else if let Some(path) = redirection::input_was_redirected() {
                 file_parse(&mut current_inputs, path.to_str().ok_or_else(|| ParseErr::RedirFile(path.clone()))?,
-                    self.flags & INPUTS_ARE_COMMANDS != 0)?;
+                    self.config.inputs_are_commands, comments.as_ref().map(|x| x.as_str()))?;
             } else if let Mode::Command = mode {
                 while let Some(argument) = arguments.get(index) {
                     index += 1;
-                    match argument.as_str() {
+                    match separators.mode(argument) {
                         // Arguments after `:::` are input values.
-                        ":::" | ":::+"   => mode = Mode::Inputs,
+                        Some(Mode::Inputs) | Some(Mode::InputsAppend) => mode = Mode::Inputs,
                         // Arguments after `::::` are files with inputs.
-                        "::::" | "::::+" => mode = Mode::Files,
+                        Some(_) => mode = Mode::Files,
                         // All other arguments are command arguments.
-                        _ => {
+                        None => {
                             comm.push(' ');
                             if quote_enabled {
                                 comm.push_str(&quote_inputs(argument));
@@ -262,7 +894,7 @@ impl Args {
 
                 // If no command was supplied, then inputs are the commands
                 if comm.is_empty() {
-                    self.flags |= INPUTS_ARE_COMMANDS;
+                    self.config.inputs_are_commands = true;
                 } else {
                     // Ensure that the command has each possible quote terminated
                     if !quote_enabled { check_command(comm.as_str())?; }
@@ -270,41 +902,168 @@ impl Args {
 
                 if shebang {
                     file_parse(&mut current_inputs, &arguments.last().unwrap(),
-                        self.flags & INPUTS_ARE_COMMANDS != 0)?;
+                        self.config.inputs_are_commands, comments.as_ref().map(|x| x.as_str()))?;
+                } else if follow {
+                    followed = Some(followed_file(arguments, index, mode)?);
                 } else {
-                    parse_inputs(arguments, index, &mut current_inputs, &mut lists, &mut mode,
-                        self.flags & INPUTS_ARE_COMMANDS != 0)?;
+                    parse_inputs(arguments, index, &mut current_inputs, &mut lists, &mut mode, &separators,
+                        self.config.inputs_are_commands, comments.as_ref().map(|x| x.as_str()))?;
                 }
+            } else if follow {
+                followed = Some(followed_file(arguments, index, mode)?);
             } else {
-                parse_inputs(arguments, index, &mut current_inputs, &mut lists, &mut mode,
-                    self.flags & INPUTS_ARE_COMMANDS != 0)?;
+                parse_inputs(arguments, index, &mut current_inputs, &mut lists, &mut mode, &separators,
+                    self.config.inputs_are_commands, comments.as_ref().map(|x| x.as_str()))?;
             }
 
-            number_of_arguments = write_inputs_to_disk(lists, current_inputs, max_args, base_path.clone())?;
+            if let Some(path) = followed {
+                self.reject_streamed(comm)?;
+                let quote = self.config.inputs_are_commands && quote_enabled;
+                let stream = InputStream::follow(Path::new(path), max_args, stream_line(quote, comments));
+                let stream = stream.map_err(|why| FileErr::Open(PathBuf::from(path), why))?;
+                self.stream = Some(stream.filtered(Filters::new(filters, self.colsep.clone())));
+                self.max_args = max_args;
+                self.nrecords = UNKNOWN_TOTAL;
+                return Ok(UNKNOWN_TOTAL);
+            }
+
+            let (inputs, records) = write_inputs_to_disk(lists, current_inputs, max_args, base_path.clone(),
+                self.buffer_size)?;
+            number_of_arguments = inputs;
+            self.nrecords = records;
         } else if let Some(path) = redirection::input_was_redirected() {
             // Read inputs as commands
             let path = path.to_str().ok_or_else(|| ParseErr::RedirFile(path.clone()))?;
-            file_parse(&mut current_inputs, path, true)?;
-            number_of_arguments = write_inputs_to_disk(lists, current_inputs, max_args, base_path.clone())?;
+            file_parse(&mut current_inputs, path, true, comments.as_ref().map(|x| x.as_str()))?;
+            let (inputs, records) = write_inputs_to_disk(lists, current_inputs, max_args, base_path.clone(),
+                self.buffer_size)?;
+            number_of_arguments = inputs;
+            self.nrecords = records;
         }
 
         if number_of_arguments == 0 {
             // If no command was supplied, then inputs are the commands
             if comm.is_empty() {
-                self.flags |= INPUTS_ARE_COMMANDS;
+                self.config.inputs_are_commands = true;
             } else {
                 // Ensure that the command has each possible quote terminated
                 if !quote_enabled { check_command(comm.as_str())?; }
             }
 
-            number_of_arguments = write_stdin_to_disk(max_args, base_path.clone(),
-                self.flags & INPUTS_ARE_COMMANDS != 0, quote_enabled)?;
+            if queue.is_some() && (sqlworker.is_some() || self.master.is_some()) {
+                return Err(ParseErr::QueueArguments);
+            }
+
+            if let Some(url) = sqlworker.or(queue) {
+                self.reject_streamed(comm)?;
+                if self.master.is_some() { return Err(ParseErr::SqlWorkerArguments); }
+                let queue = WorkQueue::open(url).map_err(|why| FileErr::Open(PathBuf::from(url), why))?;
+                let queue = queue.repeat(self.repeat);
+                self.stream = Some(InputStream::queue(queue.clone()));
+                self.queue = Some(queue);
+                self.nrecords = UNKNOWN_TOTAL;
+                return Ok(UNKNOWN_TOTAL);
+            }
+
+            if let Some(path) = input_fifo {
+                self.reject_streamed(comm)?;
+                let quote = self.config.inputs_are_commands && quote_enabled;
+                let stream = InputStream::fifo(Path::new(path), fifo_eof, max_args, stream_line(quote, comments));
+                let stream = stream.map_err(|why| FileErr::Open(PathBuf::from(path), why))?;
+                self.stream = Some(stream.filtered(Filters::new(filters, self.colsep.clone())));
+                self.max_args = max_args;
+                self.nrecords = UNKNOWN_TOTAL;
+                return Ok(UNKNOWN_TOTAL);
+            }
+
+            if stream {
+                self.reject_streamed(comm)?;
+                let stderr = io::stderr();
+                let _ = stderr.lock().write(b"parallel: streaming inputs from standard input\n");
+                let quote = self.config.inputs_are_commands && quote_enabled;
+                let stream = InputStream::spawn(max_args, stream_line(quote, comments));
+                self.stream = Some(stream.filtered(Filters::new(filters, self.colsep.clone())));
+                self.max_args = max_args;
+                self.nrecords = UNKNOWN_TOTAL;
+                return Ok(UNKNOWN_TOTAL);
+            }
+
+            let (inputs, records) = write_stdin_to_disk(max_args, base_path.clone(),
+                self.config.inputs_are_commands, quote_enabled, comments.as_ref().map(|x| x.as_str()),
+                self.buffer_size)?;
+            number_of_arguments = inputs;
+            self.nrecords = records;
         }
 
         if number_of_arguments == 0 { return Err(ParseErr::NoArguments); }
+        if input_fifo.is_some() { return Err(ParseErr::InputFifoArguments); }
+        if sqlworker.is_some() { return Err(ParseErr::SqlWorkerArguments); }
+        if let Some(url) = queue {
+            if self.master.is_some() { return Err(ParseErr::QueueArguments); }
+            self.master = Some(url.to_owned());
+        }
+
+        self.max_args = max_args;
+        if !filters.is_empty() {
+            let filters = Filters::new(filters, self.colsep.clone());
+            number_of_arguments = retain_inputs(base_path.clone(), |input| filters.keep(input))?;
+        }
+
+        if self.config.dedupe {
+            // Inputs are compared without their surrounding whitespace, and the first occurrence is kept.
+            let mut seen = HashSet::new();
+            let inputs = retain_inputs(base_path.clone(), |input| seen.insert(input.trim().to_owned()))?;
+            let stderr = io::stderr();
+            let _ = writeln!(stderr.lock(), "parallel: skipped {} duplicate inputs", number_of_arguments - inputs);
+            number_of_arguments = inputs;
+        }
+
+        if self.config.sort { sort_inputs(base_path.clone(), sort, reverse)?; }
+
+        if let Some(column) = self.group_by {
+            number_of_arguments = group_inputs(base_path.clone(), column, self.colsep.as_ref().map(|x| x.as_str()))?;
+        }
+
+        // Each input will be given to as many jobs as it is repeated by the `--repeat` parameter.
+        Ok(number_of_arguments * self.repeat)
+    }
+}
+
+/// Inserts the options within the `PARALLEL` environment variable after the name of the program, so
+/// that they will be parsed as defaults before the options that were given on the command line.
+pub fn insert_default_options(arguments: &mut Vec<String>) {
+    if let Ok(options) = env::var("PARALLEL") {
+        let position = if arguments.is_empty() { 0 } else { 1 };
+        let options = split_options(&options);
+        arguments.splice(position..position, options);
+    }
+}
 
-        Ok(number_of_arguments)
+/// Splits options on whitespace, where single and double quotes may be used to include whitespace
+/// within an option, and a backslash escapes the following character outside of quotes.
+fn split_options(options: &str) -> Vec<String> {
+    let mut output = Vec::new();
+    let mut option = String::new();
+    let (mut single, mut double, mut started) = (false, false, false);
+    let mut characters = options.chars();
+    while let Some(character) = characters.next() {
+        match character {
+            '\\' if !single && !double => if let Some(escaped) = characters.next() { option.push(escaped); },
+            '\'' if !double => single = !single,
+            '"'  if !single => double = !double,
+            _ if character.is_whitespace() && !single && !double => {
+                if started { output.push(option.clone()); }
+                option.clear();
+                started = false;
+                continue
+            },
+            _ => option.push(character)
+        }
+        started = true;
     }
+
+    if started { output.push(option); }
+    output
 }
 
 /// Ensures that the command supplied is properly terminated
@@ -323,6 +1082,24 @@ fn check_command(input: &str) -> Result<(), ParseErr> {
     if double || single { Err(ParseErr::NonTerminated(String::from(input))) } else { Ok(()) }
 }
 
+/// Returns `true` if the line should be skipped because the `--skip-comments` parameter was given with
+/// the `prefix`, and the line is blank, or begins with the prefix after any leading whitespace.
+fn is_comment(line: &str, prefix: Option<&str>) -> bool {
+    prefix.map_or(false, |prefix| {
+        let line = line.trim_start();
+        line.is_empty() || line.starts_with(prefix)
+    })
+}
+
+/// Creates the transformation that is applied to each line read by an `InputStream`. Lines which are
+/// comments become empty, so that they will be skipped by the stream.
+fn stream_line(quote: bool, comments: Option<String>) -> impl Fn(String) -> String + Send + 'static {
+    move |line| {
+        if is_comment(&line, comments.as_ref().map(|x| x.as_str())) { return String::new() }
+        if quote { quote_command(&line) } else { line }
+    }
+}
+
 // Performs the same operation as `quote_inputs`, but doesn't escape the first word found
 fn quote_command(input: &str) -> String {
     let mut output = Vec::with_capacity(input.len());
@@ -359,10 +1136,20 @@ fn quote_inputs(input: &str) -> String {
     unsafe { String::from_utf8_unchecked(output) }
 }
 
-/// Write all arguments from standard input to the disk, recording the number of arguments that were read.
+/// Creates the tempdir if it does not exist, exiting if it could not be created.
+fn create_tempdir(path: &Path) {
+    if let Err(why) = create_dir_all(path) {
+        let stderr = io::stderr();
+        let _ = writeln!(stderr.lock(), "parallel: unable to create tempdir {:?}: {}", path, why);
+        exit(1);
+    }
+}
+
+/// Write all arguments from standard input to the disk, returning the number of inputs and records that were read.
 fn write_stdin_to_disk(max_args: usize, mut unprocessed_path: PathBuf, inputs_are_commands: bool,
-    quote_enabled: bool) -> Result<usize, ParseErr>
+    quote_enabled: bool, comments: Option<&str>, buffer_size: usize) -> Result<(usize, usize), ParseErr>
 {
+    create_tempdir(&unprocessed_path);
     // Write a message to standard error that inputs are being read from standard input
     let stderr = io::stderr();
     let mut stderr = stderr.lock();
@@ -371,8 +1158,9 @@ fn write_stdin_to_disk(max_args: usize, mut unprocessed_path: PathBuf, inputs_ar
     unprocessed_path.push("unprocessed");
     let disk_buffer = fs::OpenOptions::new().truncate(true).write(true).create(true).open(&unprocessed_path)
         .map_err(|why| ParseErr::File(FileErr::Open(unprocessed_path.clone(), why)))?;
-    let mut disk_buffer = BufWriter::new(disk_buffer);
+    let mut disk_buffer = BufWriter::with_capacity(buffer_size, disk_buffer);
     let mut number_of_arguments = 0;
+    let mut number_of_records = 0;
 
     // If inputs are commands, then inputs should be command escaped, else inputs escaped.
     let parse_line: Box<Fn(io::Result<String>) -> io::Result<String>> =
@@ -386,10 +1174,13 @@ fn write_stdin_to_disk(max_args: usize, mut unprocessed_path: PathBuf, inputs_ar
     };
 
     let stdin = io::stdin();
+    let lines = raw::lines(BufReader::new(stdin.lock()))
+        .filter(|line| line.as_ref().map_or(true, |line| !is_comment(line, comments)));
     if max_args < 2 {
-        for line in BufReader::new(stdin.lock()).lines() {
+        for line in lines {
             if let Ok(line) = parse_line(line) {
                 if line.is_empty() { continue }
+                number_of_records += 1;
                 disk_buffer.write(line.as_bytes()).and_then(|_| disk_buffer.write(b"\n"))
                     .map_err(|why| FileErr::Write(unprocessed_path.clone(), why))?;
                 number_of_arguments += 1;
@@ -397,9 +1188,10 @@ fn write_stdin_to_disk(max_args: usize, mut unprocessed_path: PathBuf, inputs_ar
         }
     } else {
         let mut max_args_index = max_args;
-        for line in BufReader::new(stdin.lock()).lines() {
+        for line in lines {
             if let Ok(line) = parse_line(line) {
                 if line.is_empty() { continue }
+                number_of_records += 1;
                 if max_args_index == max_args {
                     max_args_index -= 1;
                     number_of_arguments += 1;
@@ -425,18 +1217,20 @@ fn write_stdin_to_disk(max_args: usize, mut unprocessed_path: PathBuf, inputs_ar
         }
     }
 
-    Ok(number_of_arguments)
+    Ok((number_of_arguments, number_of_records))
 }
 
-/// Write all input arguments buffered in memory to the disk, recording the number of arguments that were read.
+/// Write all input arguments buffered in memory to the disk, returning the number of inputs and records that were written.
 fn write_inputs_to_disk(lists: Vec<Vec<String>>, current_inputs: Vec<String>, max_args: usize,
-    mut unprocessed_path: PathBuf) -> Result<usize, ParseErr>
+    mut unprocessed_path: PathBuf, buffer_size: usize) -> Result<(usize, usize), ParseErr>
 {
+    create_tempdir(&unprocessed_path);
     unprocessed_path.push("unprocessed");
     let disk_buffer = fs::OpenOptions::new().truncate(true).write(true).create(true).open(&unprocessed_path)
         .map_err(|why| ParseErr::File(FileErr::Open(unprocessed_path.to_owned(), why)))?;
-    let mut disk_buffer = BufWriter::new(disk_buffer);
+    let mut disk_buffer = BufWriter::with_capacity(buffer_size, disk_buffer);
     let mut number_of_arguments = 0;
+    let mut number_of_records = current_inputs.len();
 
     if lists.len() > 1 {
         // Convert the Vec<Vec<String>> into a Vec<Vec<&str>>
@@ -452,6 +1246,7 @@ fn write_inputs_to_disk(lists: Vec<Vec<String>>, current_inputs: Vec<String>, ma
 
         // Generate the first permutation's buffer
         let mut permutation_buffer = permutator.next().unwrap();
+        number_of_records = 1;
         {
             let mut iter = permutation_buffer.iter();
             disk_buffer.write(iter.next().unwrap().as_bytes())
@@ -467,6 +1262,7 @@ fn write_inputs_to_disk(lists: Vec<Vec<String>>, current_inputs: Vec<String>, ma
         if max_args < 2 {
             disk_buffer.write(b"\n").map_err(|why| FileErr::Write(unprocessed_path.clone(), why))?;
             while permutator.next_with_buffer(&mut permutation_buffer) {
+                number_of_records += 1;
                 let mut iter = permutation_buffer.iter();
                 disk_buffer.write(iter.next().unwrap().as_bytes())
                     .map_err(|why| FileErr::Write(unprocessed_path.clone(), why))?;
@@ -481,6 +1277,7 @@ fn write_inputs_to_disk(lists: Vec<Vec<String>>, current_inputs: Vec<String>, ma
         } else {
             let mut max_args_index = max_args - 1;
             while permutator.next_with_buffer(&mut permutation_buffer) {
+                number_of_records += 1;
                 let mut iter = permutation_buffer.iter();
                 if max_args_index == max_args {
                     max_args_index -= 1;
@@ -543,12 +1340,21 @@ fn write_inputs_to_disk(lists: Vec<Vec<String>>, current_inputs: Vec<String>, ma
                 .map_err(|why| FileErr::Write(unprocessed_path.clone(), why))?;
         }
     }
-    Ok(number_of_arguments)
+    Ok((number_of_arguments, number_of_records))
 }
 
 /// Collects all the provided inputs that were passed as command line arguments into the program.
+/// Obtains the file that will be followed by the `--follow` parameter, which must be the only input list.
+fn followed_file(arguments: &[String], index: usize, mode: Mode) -> Result<&str, ParseErr> {
+    match arguments.get(index) {
+        Some(path) if mode == Mode::Files && arguments.len() == index + 1 => Ok(path),
+        _ => Err(ParseErr::FollowFile)
+    }
+}
+
 fn parse_inputs(arguments: &[String], mut index: usize, current_inputs: &mut Vec<String>,
-    lists: &mut Vec<Vec<String>>, mode: &mut Mode, inputs_are_commands: bool) -> Result<(), ParseErr>
+    lists: &mut Vec<Vec<String>>, mode: &mut Mode, separators: &Separators, inputs_are_commands: bool,
+    comments: Option<&str>) -> Result<(), ParseErr>
 {
     let mut append_list = &mut Vec::new();
 
@@ -576,23 +1382,23 @@ fn parse_inputs(arguments: &[String], mut index: usize, current_inputs: &mut Vec
     // Parse each and every input argument supplied to the program.
     while let Some(argument) = arguments.get(index) {
         index += 1;
-        match argument.as_str() {
+        match separators.mode(argument) {
             // `:::` denotes that the next set of inputs will be added to a new list.
-            ":::"   => switch_mode!(Mode::Inputs),
+            Some(Mode::Inputs)       => switch_mode!(Mode::Inputs),
             // `:::+` denotes that the next set of inputs will be added to the current list.
-            ":::+"  => switch_mode!(append Mode::InputsAppend),
+            Some(Mode::InputsAppend) => switch_mode!(append Mode::InputsAppend),
             // `::::` denotes that the next set of inputs will be added to a new list.
-            "::::"  => switch_mode!(Mode::Files),
-            // `:::+` denotes that the next set of inputs will be added to the current list.
-            "::::+" => switch_mode!(append Mode::FilesAppend),
+            Some(Mode::Files)        => switch_mode!(Mode::Files),
+            // `::::+` denotes that the next set of inputs will be added to the current list.
+            Some(_)                  => switch_mode!(append Mode::FilesAppend),
             // All other arguments will be added to the current list.
-            _ => match *mode {
+            None => match *mode {
                 Mode::Inputs if inputs_are_commands       => current_inputs.push(quote_command(&argument)),
                 Mode::InputsAppend if inputs_are_commands => append_list.push(quote_command(&argument)),
                 Mode::Inputs       => current_inputs.push(argument.clone()),
                 Mode::InputsAppend => append_list.push(argument.clone()),
-                Mode::Files        => file_parse(current_inputs, argument, inputs_are_commands)?,
-                Mode::FilesAppend  => file_parse(append_list, argument, inputs_are_commands)?,
+                Mode::Files        => file_parse(current_inputs, argument, inputs_are_commands, comments)?,
+                Mode::FilesAppend  => file_parse(append_list, argument, inputs_are_commands, comments)?,
                 _                  => unreachable!()
             }
         }
@@ -624,15 +1430,147 @@ fn parse_max_args(argument: &str, next_argument: Option<&String>,index: &mut usi
 }
 
 /// Merges an `append` list to the `original` list, draining the `append` list in the process.
-/// Excess arguments will be truncated, and therefore lost.
+/// If the lists differ in length, the shorter list is cycled to the length of the longer list.
 fn merge_lists(original: &mut Vec<String>, append: &mut Vec<String>) {
-    if original.len() > append.len() {
-        original.truncate(append.len());
+    if append.is_empty() { return }
+    if original.is_empty() { original.append(append); return }
+    if original.len() != append.len() {
+        let stderr = io::stderr();
+        let _ = writeln!(stderr.lock(), "parallel: linked lists have mismatched lengths ({} and {}): the shorter list will be cycled",
+            original.len(), append.len());
+    }
+
+    // The shorter of the two lists will be cycled so that no inputs are dropped.
+    if original.len() < append.len() {
+        let length = original.len();
+        for index in length..append.len() {
+            let input = original[index % length].clone();
+            original.push(input);
+        }
     }
-    for (input, element) in original.iter_mut().zip(append.drain(..)) {
+
+    let length = append.len();
+    for (id, input) in original.iter_mut().enumerate() {
         input.push(' ');
-        input.push_str(&element);
+        input.push_str(&append[id % length]);
+    }
+    append.clear();
+}
+
+/// Removes the inputs within the unprocessed file for which `keep` returns `false`, such as those that do not
+/// satisfy the `--filter` expressions, before any job has been dispatched. Returns the number of inputs that remain.
+fn retain_inputs<F: FnMut(&str) -> bool>(mut unprocessed_path: PathBuf, mut keep: F) -> Result<usize, ParseErr> {
+    unprocessed_path.push("unprocessed");
+    let contents = fs::read_to_string(&unprocessed_path)
+        .map_err(|why| ParseErr::File(FileErr::Read(unprocessed_path.clone(), why)))?;
+
+    let disk_buffer = fs::OpenOptions::new().truncate(true).write(true).open(&unprocessed_path)
+        .map_err(|why| ParseErr::File(FileErr::Open(unprocessed_path.clone(), why)))?;
+    let mut disk_buffer = BufWriter::new(disk_buffer);
+    let mut number_of_arguments = 0;
+    for input in contents.lines().filter(|input| keep(input)) {
+        disk_buffer.write(input.as_bytes()).and_then(|_| disk_buffer.write(b"\n"))
+            .map_err(|why| FileErr::Write(unprocessed_path.clone(), why))?;
+        number_of_arguments += 1;
+    }
+    disk_buffer.flush().map_err(|why| FileErr::Write(unprocessed_path.clone(), why))?;
+
+    Ok(number_of_arguments)
+}
+
+/// Reorders the inputs within the unprocessed file, sorting them in the given order, if any, and then
+/// reversing them if `reverse` is set. Sorting is stable, so inputs which compare equal keep their order.
+fn sort_inputs(mut unprocessed_path: PathBuf, sort: Option<Sort>, reverse: bool) -> Result<(), ParseErr> {
+    unprocessed_path.push("unprocessed");
+    let contents = fs::read_to_string(&unprocessed_path)
+        .map_err(|why| ParseErr::File(FileErr::Read(unprocessed_path.clone(), why)))?;
+
+    let mut inputs = contents.lines().collect::<Vec<&str>>();
+    match sort {
+        Some(Sort::Lexical) => inputs.sort(),
+        // Inputs which do not begin with a number are sorted lexically after those that do.
+        Some(Sort::Numeric) => inputs.sort_by(|a, b| match (leading_number(a), leading_number(b)) {
+            (Some(x), Some(y)) => x.partial_cmp(&y).unwrap_or(Ordering::Equal),
+            (Some(_), None)    => Ordering::Less,
+            (None, Some(_))    => Ordering::Greater,
+            (None, None)       => a.cmp(b)
+        }),
+        None => ()
+    }
+    if reverse { inputs.reverse(); }
+
+    let disk_buffer = fs::OpenOptions::new().truncate(true).write(true).open(&unprocessed_path)
+        .map_err(|why| ParseErr::File(FileErr::Open(unprocessed_path.clone(), why)))?;
+    let mut disk_buffer = BufWriter::new(disk_buffer);
+    for input in inputs {
+        disk_buffer.write(input.as_bytes()).and_then(|_| disk_buffer.write(b"\n"))
+            .map_err(|why| FileErr::Write(unprocessed_path.clone(), why))?;
+    }
+    disk_buffer.flush().map_err(|why| FileErr::Write(unprocessed_path.clone(), why))?;
+    Ok(())
+}
+
+/// Parses the number at the beginning of an input, ignoring leading whitespace, so that `10.txt` sorts after `9.txt`.
+fn leading_number(input: &str) -> Option<f64> {
+    let input = input.trim_start();
+    let mut end = if input.starts_with('-') || input.starts_with('+') { 1 } else { 0 };
+    let mut decimal = false;
+    for character in input[end..].bytes() {
+        match character {
+            b'0'..=b'9' => (),
+            b'.' if !decimal => decimal = true,
+            _ => break
+        }
+        end += 1;
+    }
+    input[..end].parse::<f64>().ok()
+}
+
+/// Regroups the inputs within the unprocessed file, so that the records which have the same key in the
+/// given column will become a single input, with each record separated by the `RECORD_SEPARATOR`.
+/// Groups are ordered by the first appearance of their key. Returns the number of groups.
+fn group_inputs(mut unprocessed_path: PathBuf, column: usize, colsep: Option<&str>) -> Result<usize, ParseErr> {
+    unprocessed_path.push("unprocessed");
+    let contents = fs::read_to_string(&unprocessed_path)
+        .map_err(|why| ParseErr::File(FileErr::Read(unprocessed_path.clone(), why)))?;
+
+    let mut indices: HashMap<&str, usize> = HashMap::new();
+    let mut groups: Vec<Vec<&str>> = Vec::new();
+    for record in contents.lines() {
+        let index = *indices.entry(group_key(record, column, colsep)).or_insert_with(|| {
+            groups.push(Vec::new());
+            groups.len() - 1
+        });
+        groups[index].push(record);
     }
+
+    let disk_buffer = fs::OpenOptions::new().truncate(true).write(true).open(&unprocessed_path)
+        .map_err(|why| ParseErr::File(FileErr::Open(unprocessed_path.clone(), why)))?;
+    let mut disk_buffer = BufWriter::new(disk_buffer);
+    let mut separator = [0u8; 4];
+    let separator = RECORD_SEPARATOR.encode_utf8(&mut separator).as_bytes();
+    for group in &groups {
+        for (id, record) in group.iter().enumerate() {
+            if id != 0 {
+                disk_buffer.write(separator).map_err(|why| FileErr::Write(unprocessed_path.clone(), why))?;
+            }
+            disk_buffer.write(record.as_bytes()).map_err(|why| FileErr::Write(unprocessed_path.clone(), why))?;
+        }
+        disk_buffer.write(b"\n").map_err(|why| FileErr::Write(unprocessed_path.clone(), why))?;
+    }
+    disk_buffer.flush().map_err(|why| FileErr::Write(unprocessed_path.clone(), why))?;
+
+    Ok(groups.len())
+}
+
+/// Obtains the value of the given column of a record, where columns are separated by `colsep`, or by
+/// whitespace if no separator was given. Records which lack the column have an empty key.
+pub fn group_key<'a>(record: &'a str, column: usize, colsep: Option<&str>) -> &'a str {
+    let value = match colsep {
+        Some(colsep) => record.split(colsep).nth(column - 1),
+        None => record.split_whitespace().nth(column - 1)
+    };
+    value.unwrap_or("")
 }
 
 /// When the `--memfree` option has been selected, this will attempt to parse the unit's value, multiplying
@@ -654,27 +1592,133 @@ fn parse_memory(input: &str) -> Result<u64, ParseIntError> {
     Ok(result)
 }
 
+/// Parses the `--delay` value, which may either be a number of seconds (`1.5`), or a range of
+/// seconds (`0.5-2`). Returns the minimum delay, and the amount of random jitter to add to it.
+fn parse_delay(input: &str) -> Result<(Duration, Duration), ParseFloatError> {
+    let to_millis = |seconds: f64| (seconds * 1000f64) as u64;
+    match input.find('-') {
+        Some(pos) if pos != 0 => {
+            let min = to_millis(input[..pos].parse::<f64>()?);
+            let max = to_millis(input[pos+1..].parse::<f64>()?);
+            let (min, max) = if min > max { (max, min) } else { (min, max) };
+            Ok((Duration::from_millis(min), Duration::from_millis(max - min)))
+        },
+        _ => Ok((Duration::from_millis(to_millis(input.parse::<f64>()?)), Duration::from_millis(0)))
+    }
+}
+
 /// Parses the jobs value, and optionally increments the index if necessary.
-fn parse_jobs(argument: &str, next_argument: Option<&String>, index: &mut usize) -> Result<usize, ParseErr> {
-    let ncores = if argument.len() > 2 {
-        jobs::parse(&argument[2..])?
+fn parse_jobs(argument: &str, next_argument: Option<&String>, index: &mut usize)
+    -> Result<(usize, Option<PathBuf>), ParseErr>
+{
+    if argument.len() > 2 {
+        jobs::parse_value(&argument[2..])
     } else {
         *index += 1;
-        jobs::parse(next_argument.ok_or(ParseErr::JobsNoValue)?)?
+        jobs::parse_value(next_argument.ok_or(ParseErr::JobsNoValue)?)
+    }
+}
+
+/// Opens a job log that was created with the `--joblog` parameter, and adds the command of each job
+/// which exited with a non-zero exit value, or was killed by a signal, to the `inputs` list.
+fn joblog_parse<P: AsRef<Path>>(inputs: &mut Vec<String>, path: P) -> Result<(), ParseErr> {
+    let path = path.as_ref();
+    let file = fs::File::open(path).map_err(|err| ParseErr::File(FileErr::Open(path.to_owned(), err)))?;
+    let mut lines = raw::lines(BufReader::new(file));
+
+    // The start time column will span two fields if the ISO 8601 format was used.
+    let start_fields = match lines.next() {
+        Some(Ok(header)) => if header.contains("StartTime(ISO-8601)") { 2 } else { 1 },
+        Some(Err(why))   => return Err(ParseErr::File(FileErr::Read(path.to_owned(), why))),
+        None             => return Ok(())
     };
 
-    Ok(ncores)
+    let mut jobs: Vec<(String, bool)> = Vec::new();
+    let mut indices: HashMap<String, usize> = HashMap::new();
+
+    for line in lines {
+        let line = line.map_err(|why| ParseErr::File(FileErr::Read(path.to_owned(), why)))?;
+        let mut fields = JoblogFields { line: &line };
+        // Skip the sequence, start time, and runtime columns.
+        for _ in 0..start_fields + 2 { let _ = fields.next(); }
+        let exit_val = fields.next().and_then(|x| x.parse::<i32>().ok());
+        let signal   = fields.next().and_then(|x| x.parse::<i32>().ok());
+        let command  = fields.line.trim_start();
+
+        if let (Some(exit_val), Some(signal)) = (exit_val, signal) {
+            if command.is_empty() { continue }
+            let failed = exit_val != 0 || signal != 0;
+            // Jobs that were retried are logged again, so only the latest entry of a command is used.
+            match indices.get(command).cloned() {
+                Some(index) => jobs[index].1 = failed,
+                None => {
+                    indices.insert(command.to_owned(), jobs.len());
+                    jobs.push((command.to_owned(), failed));
+                }
+            }
+        }
+    }
+
+    inputs.extend(jobs.into_iter().filter(|&(_, failed)| failed).map(|(command, _)| command));
+    Ok(())
+}
+
+/// Splits whitespace-separated fields from the beginning of a job log entry, retaining the remainder
+/// of the line so that the command may be obtained verbatim.
+struct JoblogFields<'a> {
+    line: &'a str,
+}
+
+impl<'a> Iterator for JoblogFields<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let line = self.line.trim_start();
+        if line.is_empty() { return None }
+        let end = line.find(' ').unwrap_or(line.len());
+        self.line = &line[end..];
+        Some(&line[..end])
+    }
+}
+
+/// Reads the commands that will be executed from the file given by the `--commands-file` parameter.
+/// Each line is a command, unless a `delimiter` was given, in which case each command consists of the
+/// lines up to the next line that matches the delimiter. The lines of a command are separated by the
+/// `RECORD_SEPARATOR`, so that each command will be stored as a single input.
+fn commands_parse<P: AsRef<Path>>(inputs: &mut Vec<String>, path: P, delimiter: Option<&str>) -> Result<(), ParseErr> {
+    let path = path.as_ref();
+    let file = fs::File::open(path).map_err(|err| ParseErr::File(FileErr::Open(path.to_owned(), err)))?;
+    let mut command = String::new();
+    for line in raw::lines(BufReader::new(file)) {
+        let line = line.map_err(|why| ParseErr::File(FileErr::Read(path.to_owned(), why)))?;
+        match delimiter {
+            Some(delimiter) if line.trim_end() == delimiter => {
+                if !command.trim().is_empty() { inputs.push(command.clone()); }
+                command.clear();
+            },
+            Some(_) => {
+                if !command.is_empty() { command.push(RECORD_SEPARATOR); }
+                command.push_str(&line);
+            },
+            None => if !line.trim().is_empty() && !line.starts_with('#') { inputs.push(line); }
+        }
+    }
+
+    // The last command does not need to be followed by the delimiter.
+    if !command.trim().is_empty() { inputs.push(command); }
+    Ok(())
 }
 
-/// Attempts to open an input argument and adds each line to the `inputs` list.
-fn file_parse<P: AsRef<Path>>(inputs: &mut Vec<String>, path: P, inputs_are_commands: bool)
-    -> Result<(), ParseErr>
+/// Attempts to open an input argument and adds each line to the `inputs` list. Empty lines, and lines
+/// beginning with `#`, such as the interpreter line of a shebang script, are always skipped.
+fn file_parse<P: AsRef<Path>>(inputs: &mut Vec<String>, path: P, inputs_are_commands: bool,
+    comments: Option<&str>) -> Result<(), ParseErr>
 {
     let path = path.as_ref();
     let file = fs::File::open(path).map_err(|err| ParseErr::File(FileErr::Open(path.to_owned(), err)))?;
-    for line in BufReader::new(file).lines() {
+    for line in raw::lines(BufReader::new(file)) {
         if let Ok(line) = line {
-            if !line.is_empty() && !line.starts_with("#") {
+            if !line.is_empty() && !line.starts_with("#") && !is_comment(&line, comments) {
                 if inputs_are_commands {
                     inputs.push(quote_command(&line));
                 } else {
@@ -685,3 +1729,126 @@ fn file_parse<P: AsRef<Path>>(inputs: &mut Vec<String>, path: P, inputs_are_comm
     }
     Ok(())
 }
+
+#[test]
+fn commands_file_parsing() {
+    let path = env::temp_dir().join(format!("parallel_commands_{}", ::std::process::id()));
+    fs::write(&path, "echo one\n# comment\n\necho two\n--\nfor x in a b; do\n    echo $x\ndone\n").unwrap();
+
+    let mut inputs = Vec::new();
+    commands_parse(&mut inputs, &path, None).unwrap();
+    assert_eq!(inputs.len(), 6);
+    assert_eq!(inputs[1], "echo two");
+
+    let mut inputs = Vec::new();
+    commands_parse(&mut inputs, &path, Some("--")).unwrap();
+    let _ = fs::remove_file(&path);
+    assert_eq!(inputs, vec![
+        "echo one\x1e# comment\x1e\x1eecho two".to_owned(),
+        "for x in a b; do\x1e    echo $x\x1edone".to_owned(),
+    ]);
+}
+
+#[test]
+fn default_options_splitting() {
+    assert_eq!(split_options("  -j 75%\t--eta "), vec!["-j", "75%", "--eta"]);
+    assert_eq!(split_options("--tmpdir '/tmp/my dir' --colsep \"\\t\" a\\ b ''"),
+        vec!["--tmpdir", "/tmp/my dir", "--colsep", "\\t", "a b", ""]);
+    assert!(split_options("").is_empty());
+}
+
+#[test]
+fn separator_modes() {
+    let separators = Separators { inputs: "--in".to_owned(), files: "--files".to_owned() };
+    assert_eq!(separators.mode("--in"), Some(Mode::Inputs));
+    assert_eq!(separators.mode("--in+"), Some(Mode::InputsAppend));
+    assert_eq!(separators.mode("--files+"), Some(Mode::FilesAppend));
+    assert_eq!(separators.mode(":::"), None);
+    assert_eq!(Separators::default().mode("::::"), Some(Mode::Files));
+}
+
+#[test]
+fn delay_parsing() {
+    assert_eq!((Duration::from_millis(1500), Duration::from_millis(0)), parse_delay("1.5").unwrap());
+    assert_eq!((Duration::from_millis(500), Duration::from_millis(1500)), parse_delay("0.5-2").unwrap());
+    assert_eq!((Duration::from_millis(500), Duration::from_millis(1500)), parse_delay("2-0.5").unwrap());
+    assert!(parse_delay("1-a").is_err());
+}
+
+#[test]
+fn linked_lists_cycle() {
+    let mut original = vec!["1".to_owned(), "2".to_owned(), "3".to_owned()];
+    let mut append = vec!["A".to_owned()];
+    merge_lists(&mut original, &mut append);
+    assert_eq!(original, vec!["1 A".to_owned(), "2 A".to_owned(), "3 A".to_owned()]);
+
+    let mut original = vec!["1".to_owned()];
+    let mut append = vec!["A".to_owned(), "B".to_owned()];
+    merge_lists(&mut original, &mut append);
+    assert_eq!(original, vec!["1 A".to_owned(), "1 B".to_owned()]);
+}
+
+#[test]
+fn record_grouping() {
+    assert_eq!(group_key("a\tb\tc", 2, Some("\t")), "b");
+    assert_eq!(group_key("a,b", 3, Some(",")), "");
+    assert_eq!(group_key("  a   b", 2, None), "b");
+}
+
+#[test]
+fn expected_total() {
+    let mut args = Args::new();
+    args.ninputs = UNKNOWN_TOTAL;
+    assert_eq!(args.expected_total(), UNKNOWN_TOTAL);
+    args.total_jobs = Some(3);
+    assert_eq!(args.expected_total(), 3);
+    args.ninputs = 5;
+    assert_eq!(args.expected_total(), 5);
+}
+
+#[test]
+fn skip_comments() {
+    let path = env::temp_dir().join(format!("parallel_comments_{}", ::std::process::id()));
+    fs::write(&path, "one\n# comment\n\n   \n  // note\ntwo\n").unwrap();
+
+    let mut inputs = Vec::new();
+    file_parse(&mut inputs, &path, false, None).unwrap();
+    assert_eq!(inputs, vec!["one", "   ", "  // note", "two"]);
+
+    let mut inputs = Vec::new();
+    file_parse(&mut inputs, &path, false, Some("//")).unwrap();
+    assert_eq!(inputs, vec!["one", "two"]);
+    let _ = fs::remove_file(&path);
+
+    assert!(is_comment("\t# note", Some("#")) && !is_comment("a # note", Some("#")));
+    assert!(!is_comment("", None));
+}
+
+#[test]
+fn dedupe_inputs() {
+    let base = env::temp_dir().join(format!("parallel_dedupe_{}", ::std::process::id()));
+    create_dir_all(&base).unwrap();
+    fs::write(base.join("unprocessed"), "a\nb\n a \nc\nb\n").unwrap();
+
+    let mut seen = HashSet::new();
+    assert_eq!(retain_inputs(base.clone(), |input| seen.insert(input.trim().to_owned())).unwrap(), 3);
+    assert_eq!(fs::read_to_string(base.join("unprocessed")).unwrap(), "a\nb\nc\n");
+    let _ = fs::remove_dir_all(&base);
+}
+
+#[test]
+fn sorted_inputs() {
+    let base = env::temp_dir().join(format!("parallel_sort_{}", ::std::process::id()));
+    create_dir_all(&base).unwrap();
+    let sorted = |sort, reverse| {
+        fs::write(base.join("unprocessed"), "10.txt\nb\n9.txt\n-1.5\na\n").unwrap();
+        sort_inputs(base.clone(), sort, reverse).unwrap();
+        fs::read_to_string(base.join("unprocessed")).unwrap()
+    };
+
+    assert_eq!(sorted(Some(Sort::Lexical), false), "-1.5\n10.txt\n9.txt\na\nb\n");
+    assert_eq!(sorted(Some(Sort::Numeric), false), "-1.5\n9.txt\n10.txt\na\nb\n");
+    assert_eq!(sorted(Some(Sort::Numeric), true), "b\na\n10.txt\n9.txt\n-1.5\n");
+    assert_eq!(sorted(None, true), "a\n-1.5\n9.txt\nb\n10.txt\n");
+    let _ = fs::remove_dir_all(&base);
+}
@@ -0,0 +1,316 @@
+use std::fmt;
+
+/// Determines whether an option is followed by a value.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Value {
+    /// The option does not take a value.
+    None,
+    /// The value is the following argument, or follows an `=`.
+    Required,
+    /// The value may only be given after an `=`, such as `--dry-run=FILE`.
+    Attached,
+}
+
+/// Every long option, sorted by name, along with whether the option takes a value.
+const LONG_OPTIONS: &'static [(&'static str, Value)] = &[
+    ("arg-file-sep",       Value::Required),
+    ("arg-sep",            Value::Required),
+    ("buffer-size",        Value::Required),
+    ("checkpoint",         Value::Required),
+    ("color",              Value::None),
+    ("colsep",             Value::Required),
+    ("commands-delimiter", Value::Required),
+    ("commands-file",      Value::Required),
+    ("container",          Value::Required),
+    ("container-mount",    Value::None),
+    ("dedupe",             Value::None),
+    ("delay",              Value::Required),
+    ("delay-backoff",      Value::None),
+    ("dry-run",            Value::Attached),
+    ("error-output",       Value::Required),
+    ("eta",                Value::None),
+    ("expand-tokens",      Value::None),
+    ("fail-on-output",     Value::Required),
+    ("fifo-eof",           Value::Required),
+    ("filter",             Value::Required),
+    ("follow",             Value::None),
+    ("group-by",           Value::Required),
+    ("halt",               Value::Required),
+    ("halt-on-output",     Value::Required),
+    ("help",               Value::None),
+    ("input-fifo",         Value::Required),
+    ("ionice",             Value::Required),
+    ("joblog",             Value::Required),
+    ("joblog-8601",        Value::None),
+    ("joblog-template",    Value::Required),
+    ("jobs",               Value::Required),
+    ("json",               Value::None),
+    ("keep-temp",          Value::None),
+    ("log-events",         Value::Required),
+    ("log-file",           Value::Required),
+    ("log-level",          Value::Required),
+    ("log-syslog",         Value::None),
+    ("max-args",           Value::Required),
+    ("max-starts-per-second", Value::Required),
+    ("mem-free",           Value::Required),
+    ("memfree",            Value::Required),
+    ("memfree-poll",       Value::Required),
+    ("memfree-timeout",    Value::Required),
+    ("no-shell",           Value::None),
+    ("notify-cmd",         Value::Required),
+    ("num-cpu-cores",      Value::None),
+    ("on-fail",            Value::Required),
+    ("output",             Value::Required),
+    ("pipe",               Value::None),
+    ("queue",              Value::Required),
+    ("quiet",              Value::None),
+    ("quote",              Value::None),
+    ("repeat",             Value::Required),
+    ("require-output",     Value::Required),
+    ("results-json",       Value::Required),
+    ("retries",            Value::Required),
+    ("retry-backoff",      Value::Required),
+    ("retry-delay",        Value::Required),
+    ("retry-failed",       Value::None),
+    ("reverse",            Value::None),
+    ("seed",               Value::Required),
+    ("seqreplace",         Value::Required),
+    ("shell",              Value::Required),
+    ("shellquote",         Value::None),
+    ("silent",             Value::None),
+    ("skip-comments",      Value::Attached),
+    ("slotreplace",        Value::Required),
+    ("sort",               Value::Attached),
+    ("sqlmaster",          Value::Required),
+    ("sqlworker",          Value::Required),
+    ("stall-timeout",      Value::Required),
+    ("status-file",        Value::Required),
+    ("stream",             Value::None),
+    ("summary",            Value::None),
+    ("sync",               Value::None),
+    ("systemd-scope",      Value::Attached),
+    ("tempdir",            Value::Required),
+    ("timeout",            Value::Required),
+    ("timestamp",          Value::Attached),
+    ("tmpdir",             Value::Required),
+    ("total-jobs",         Value::Required),
+    ("trace-file",         Value::Required),
+    ("ungroup",            Value::None),
+    ("verbose",            Value::None),
+    ("version",            Value::None),
+    ("workdir",            Value::Required),
+];
+
+/// Short options which take a value, which may be attached, such as `-j4`, or the following argument.
+/// Every other short option is a flag, which may be grouped, such as `-vsp`.
+const SHORT_VALUES: &'static [u8] = b"jn";
+
+/// The errors that may occur when options are normalized.
+#[derive(Debug, PartialEq)]
+pub enum OptionErr {
+    /// A long option was abbreviated to a prefix which is shared by several options.
+    Ambiguous(String, Vec<&'static str>),
+    /// A value was given with `=` to an option which does not take a value.
+    UnexpectedValue(String),
+}
+
+impl fmt::Display for OptionErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            OptionErr::Ambiguous(ref option, ref candidates) => {
+                write!(f, "option '--{}' is ambiguous; possibilities:", option)?;
+                for candidate in candidates { write!(f, " '--{}'", candidate)?; }
+                Ok(())
+            },
+            OptionErr::UnexpectedValue(ref option) => write!(f, "option '--{}' does not take a value", option)
+        }
+    }
+}
+
+/// Finds the long option with the given name, or the only option which begins with the given name.
+/// Options that are not known are returned as `None`, so that they may be reported by the parser.
+fn find_long(name: &str) -> Result<Option<(&'static str, Value)>, OptionErr> {
+    if let Some(&option) = LONG_OPTIONS.iter().find(|&&(option, _)| option == name) {
+        return Ok(Some(option));
+    }
+
+    let candidates = LONG_OPTIONS.iter().filter(|&&(option, _)| option.starts_with(name)).collect::<Vec<_>>();
+    match candidates.len() {
+        0 => Ok(None),
+        1 => Ok(Some(*candidates[0])),
+        _ => Err(OptionErr::Ambiguous(name.to_owned(), candidates.iter().map(|&&(option, _)| option).collect()))
+    }
+}
+
+/// The number of single-character insertions, deletions, substitutions, and transpositions of adjacent
+/// characters that would turn `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let (a, b) = (a.chars().collect::<Vec<char>>(), b.chars().collect::<Vec<char>>());
+    // Only the distances of the previous two rows are required to compute the next row.
+    let mut rows = vec![(0..b.len() + 1).collect::<Vec<usize>>(); 3];
+    for i in 1..a.len() + 1 {
+        rows[2][0] = i;
+        for j in 1..b.len() + 1 {
+            let cost = if a[i-1] == b[j-1] { 0 } else { 1 };
+            let mut distance = (rows[1][j-1] + cost).min(rows[1][j] + 1).min(rows[2][j-1] + 1);
+            if i > 1 && j > 1 && a[i-1] == b[j-2] && a[i-2] == b[j-1] {
+                distance = distance.min(rows[0][j-2] + 1);
+            }
+            rows[2][j] = distance;
+        }
+        rows.rotate_left(1);
+    }
+    rows[1][b.len()]
+}
+
+/// Finds the long option which is closest to the given unknown option, such as `--verbose` for
+/// `--verbsoe`, if an option is close enough to have been what was meant.
+pub fn suggest(option: &str) -> Option<&'static str> {
+    let name = option.trim_start_matches('-');
+    let name = name.split('=').next().unwrap_or(name);
+    if name.is_empty() { return None }
+
+    // Longer names may contain more typos before another option is likely to have been meant.
+    let limit = (name.chars().count() / 3).max(1).min(3);
+    LONG_OPTIONS.iter()
+        .map(|&(option, _)| (edit_distance(name, option), option))
+        .filter(|&(distance, _)| distance <= limit)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, option)| option)
+}
+
+/// Rewrites the options preceding the command into the form that the parser expects: long options are
+/// given by their full names, values given with `=` become the following argument, and grouped short
+/// options are separated, so that `--timeo=8 -vj4` becomes `--timeout 8 -v -j 4`. The program name, and every
+/// argument following the options, is copied as it was given.
+pub fn normalize(arguments: &[String]) -> Result<Vec<String>, OptionErr> {
+    let mut output = Vec::with_capacity(arguments.len());
+    let mut arguments = arguments.iter();
+    output.extend(arguments.next().cloned());
+
+    // Separators that were changed with `--arg-sep` and `--arg-file-sep` may begin with a `-`.
+    let mut separators: Vec<String> = Vec::new();
+
+    while let Some(argument) = arguments.next() {
+        let is_separator = separators.iter().any(|sep| argument == sep || argument == &[sep.as_str(), "+"].concat());
+        // Options end at the command, at `--`, or at the `--shebang` options, which are followed by the command.
+        if !argument.starts_with('-') || argument == "-" || argument == "--" || argument.starts_with("--shebang")
+            || is_separator
+        {
+            output.push(argument.clone());
+            break
+        }
+
+        if argument.starts_with("--") {
+            let (name, value) = match argument.find('=') {
+                Some(pos) => (&argument[2..pos], Some(&argument[pos+1..])),
+                None      => (&argument[2..], None)
+            };
+
+            match find_long(name)? {
+                Some((option, Value::None)) => {
+                    if value.is_some() { return Err(OptionErr::UnexpectedValue(option.to_owned())); }
+                    output.push(["--", option].concat());
+                },
+                Some((option, Value::Required)) => {
+                    output.push(["--", option].concat());
+                    // The value will be reported as missing by the parser if it was not given.
+                    let value = match value {
+                        Some(value) => Some(value.to_owned()),
+                        None        => arguments.next().cloned()
+                    };
+                    if let Some(value) = value {
+                        if option == "arg-sep" || option == "arg-file-sep" { separators.push(value.clone()); }
+                        output.push(value);
+                    }
+                },
+                Some((option, Value::Attached)) => match value {
+                    Some(value) => output.push(["--", option, "=", value].concat()),
+                    None        => output.push(["--", option].concat())
+                },
+                None => output.push(argument.clone())
+            }
+        } else {
+            for (id, character) in argument.bytes().enumerate().skip(1) {
+                if SHORT_VALUES.contains(&character) {
+                    output.push(format!("-{}", character as char));
+                    if id + 1 < argument.len() {
+                        output.push(argument[id+1..].to_owned());
+                    } else if let Some(value) = arguments.next() {
+                        output.push(value.clone());
+                    }
+                    break
+                }
+
+                // Unknown short options are also separated, so that they will be reported by the parser.
+                if !character.is_ascii() {
+                    output.push(["-", &argument[id..]].concat());
+                    break
+                }
+                output.push(format!("-{}", character as char));
+            }
+        }
+    }
+
+    output.extend(arguments.cloned());
+    Ok(output)
+}
+
+#[cfg(test)]
+fn normalized(arguments: &[&str]) -> Result<Vec<String>, OptionErr> {
+    let arguments = arguments.iter().map(|&x| x.to_owned()).collect::<Vec<_>>();
+    normalize(&arguments)
+}
+
+#[test]
+fn long_options_sorted() {
+    assert!(LONG_OPTIONS.windows(2).all(|pair| pair[0].0 < pair[1].0));
+}
+
+#[test]
+fn long_option_values() {
+    assert_eq!(normalized(&["parallel", "--jobs=8", "--joblog", "-", "echo", "--eta"]).unwrap(),
+        vec!["parallel", "--jobs", "8", "--joblog", "-", "echo", "--eta"]);
+    assert_eq!(normalized(&["parallel", "--dry-run=out", "--systemd-scope", ":::", "--eta"]).unwrap(),
+        vec!["parallel", "--dry-run=out", "--systemd-scope", ":::", "--eta"]);
+    assert_eq!(normalized(&["parallel", "--eta=1"]), Err(OptionErr::UnexpectedValue("eta".to_owned())));
+}
+
+#[test]
+fn long_option_abbreviations() {
+    assert_eq!(normalized(&["parallel", "--verb", "--stal=5", "--temp", "/tmp", "--unknown"]).unwrap(),
+        vec!["parallel", "--verbose", "--stall-timeout", "5", "--tempdir", "/tmp", "--unknown"]);
+    assert_eq!(normalized(&["parallel", "--joblog", "log"]).unwrap(), vec!["parallel", "--joblog", "log"]);
+    assert_eq!(normalized(&["parallel", "--timeo=8", "-vj4"]).unwrap(),
+        vec!["parallel", "--timeout", "8", "-v", "-j", "4"]);
+    match normalized(&["parallel", "--s"]) {
+        Err(OptionErr::Ambiguous(ref option, ref candidates)) => {
+            assert_eq!(option, "s");
+            assert!(candidates.contains(&"shell") && candidates.contains(&"stream"));
+        },
+        _ => panic!("expected an ambiguous option error")
+    }
+}
+
+#[test]
+fn option_suggestions() {
+    assert_eq!(suggest("--verbsoe"), Some("verbose"));
+    assert_eq!(suggest("--dryrun=out"), Some("dry-run"));
+    assert_eq!(suggest("--jbos"), Some("jobs"));
+    assert_eq!(suggest("--frobnicate"), None);
+    assert_eq!(suggest("--"), None);
+}
+
+#[test]
+fn short_option_groups() {
+    assert_eq!(normalized(&["parallel", "-vsp", "-vj4", "-n", "2", "echo", "-v"]).unwrap(),
+        vec!["parallel", "-v", "-s", "-p", "-v", "-j", "4", "-n", "2", "echo", "-v"]);
+    assert_eq!(normalized(&["parallel", "-qj", "50%", "--", "-v", ":::", "a"]).unwrap(),
+        vec!["parallel", "-q", "-j", "50%", "--", "-v", ":::", "a"]);
+}
+
+#[test]
+fn custom_separators() {
+    assert_eq!(normalized(&["parallel", "--arg-sep=-i", "-i", "a", "-i+", "b"]).unwrap(),
+        vec!["parallel", "--arg-sep", "-i", "-i", "a", "-i+", "b"]);
+}
@@ -0,0 +1,77 @@
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// The state of a run that is periodically recorded with the `--checkpoint` parameter, so that a
+/// run which was killed may be restarted from where it left off.
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct Checkpoint {
+    /// The number of inputs whose jobs have completed, and whose outputs have been printed.
+    pub completed: usize,
+    /// The number of jobs that have failed.
+    pub failed:    usize,
+    /// A hash of the command template that the jobs were executed with.
+    pub command:   u64,
+}
+
+/// Hashes the command template with the FNV-1a algorithm, whose hashes, unlike those of the standard
+/// library's hasher, are guaranteed to remain the same between builds of the program.
+pub fn hash_command(command: &str) -> u64 {
+    command.bytes().fold(0xcbf29ce484222325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+impl Checkpoint {
+    /// Loads the checkpoint at the given path, returning a default checkpoint if it does not exist.
+    pub fn load(path: &Path) -> io::Result<Checkpoint> {
+        let mut contents = String::new();
+        match File::open(path) {
+            Ok(mut file) => { file.read_to_string(&mut contents)?; },
+            Err(ref why) if why.kind() == io::ErrorKind::NotFound => return Ok(Checkpoint::default()),
+            Err(why) => return Err(why)
+        }
+
+        Checkpoint::parse(&contents)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid checkpoint"))
+    }
+
+    /// Writes the checkpoint to a temporary file which then replaces the checkpoint, so that the
+    /// checkpoint will remain intact if the run is killed while it is being written.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut temporary = PathBuf::from(path);
+        temporary.set_extension("tmp");
+        {
+            let mut file = File::create(&temporary)?;
+            write!(file, "completed={}\nfailed={}\ncommand={:016x}\n", self.completed, self.failed, self.command)?;
+            file.sync_all()?;
+        }
+        fs::rename(&temporary, path)
+    }
+
+    fn parse(contents: &str) -> Option<Checkpoint> {
+        let mut checkpoint = Checkpoint::default();
+        for line in contents.lines().filter(|line| !line.is_empty()) {
+            let pos = line.find('=')?;
+            let value = &line[pos+1..];
+            match &line[..pos] {
+                "completed" => checkpoint.completed = value.parse().ok()?,
+                "failed"    => checkpoint.failed = value.parse().ok()?,
+                "command"   => checkpoint.command = u64::from_str_radix(value, 16).ok()?,
+                _           => return None
+            }
+        }
+        Some(checkpoint)
+    }
+}
+
+#[test]
+fn checkpoint_parsing() {
+    assert_eq!(Checkpoint::parse("completed=12\nfailed=1\ncommand=00000000000000ff\n"), Some(Checkpoint {
+        completed: 12, failed: 1, command: 255
+    }));
+    assert_eq!(Checkpoint::parse(""), Some(Checkpoint::default()));
+    assert_eq!(Checkpoint::parse("completed=x\n"), None);
+    assert_eq!(Checkpoint::parse("started=1\n"), None);
+    assert_eq!(hash_command(""), 0xcbf29ce484222325);
+    assert_eq!(hash_command("echo {}"), hash_command("echo {}"));
+    assert!(hash_command("echo {}") != hash_command("echo {.}"));
+}
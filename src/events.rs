@@ -0,0 +1,96 @@
+use std::fs::File;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use time::get_time;
+
+/// Emits one JSON object per line for each event in the lifecycle of a job, so that wrappers may
+/// track the progress of a run without needing to parse the verbose output.
+#[derive(Clone)]
+pub struct EventLog {
+    output: Arc<Mutex<Box<dyn Write + Send>>>,
+    failed: Arc<AtomicUsize>,
+}
+
+impl EventLog {
+    /// Opens the event log at the given path, where a path of `-` denotes the standard error.
+    pub fn open(path: &str) -> io::Result<EventLog> {
+        let output: Box<dyn Write + Send> = if path == "-" {
+            Box::new(io::stderr())
+        } else {
+            Box::new(File::create(path)?)
+        };
+
+        Ok(EventLog {
+            output: Arc::new(Mutex::new(output)),
+            failed: Arc::new(AtomicUsize::new(0)),
+        })
+    }
+
+    /// Records that the given job has been started.
+    pub fn job_started(&self, job: usize, input: &str) {
+        self.write(&format!("{{\"event\":\"started\",\"time\":{},\"job\":{},\"input\":\"{}\"}}\n",
+            timestamp(), job, escape(input)));
+    }
+
+    /// Records that the given job has finished, which will be marked as failed if the job exited
+    /// with a non-zero exit status or was killed by a signal.
+    pub fn job_finished(&self, job: usize, input: &str, exit_value: i32, signal: i32, runtime: u64) {
+        let event = if exit_value == 0 && signal == 0 {
+            "finished"
+        } else {
+            self.failed.fetch_add(1, Ordering::SeqCst);
+            "failed"
+        };
+
+        self.write(&format!("{{\"event\":\"{}\",\"time\":{},\"job\":{},\"input\":\"{}\",\"exit\":{},\
+            \"signal\":{},\"runtime\":{}.{:03}}}\n", event, timestamp(), job, escape(input), exit_value,
+            signal, runtime / 1_000_000_000, (runtime % 1_000_000_000) / 1_000_000));
+    }
+
+    /// Records a summary of the run once all jobs have been processed.
+    pub fn summary(&self, jobs: usize, runtime: u64) {
+        self.write(&format!("{{\"event\":\"summary\",\"time\":{},\"jobs\":{},\"failed\":{},\"runtime\":{}.{:03}}}\n",
+            timestamp(), jobs, self.failed.load(Ordering::SeqCst), runtime / 1_000_000_000,
+            (runtime % 1_000_000_000) / 1_000_000));
+    }
+
+    fn write(&self, event: &str) {
+        let mut output = self.output.lock().unwrap();
+        let _ = output.write_all(event.as_bytes()).and_then(|_| output.flush());
+    }
+}
+
+/// The current time in seconds since the UNIX epoch, with three decimal places.
+fn timestamp() -> String {
+    let now = get_time();
+    format!("{}.{:03}", now.sec, now.nsec / 1_000_000)
+}
+
+/// Escapes a string so that it may be embedded within a JSON string.
+pub fn escape(input: &str) -> String {
+    let mut output = String::with_capacity(input.len());
+    for character in input.chars() {
+        match character {
+            '"'  => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            c if (c as u32) < 0x20 => output.push_str(&format!("\\u{:04x}", c as u32)),
+            c => output.push(c)
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_escape() {
+        assert_eq!(escape("echo \"a\\b\"\n"), "echo \\\"a\\\\b\\\"\\n");
+        assert_eq!(escape("\u{1}"), "\\u0001");
+    }
+}
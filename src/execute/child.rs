@@ -1,31 +1,142 @@
-use arguments::QUIET_MODE;
-use std::process::Child;
-use std::sync::mpsc::Sender;
-use std::time::Duration;
-use wait_timeout::ChildExt;
+use arguments::Config;
+use running;
+use std::io;
+use std::process::{Child, ExitStatus};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
 use time::{get_time, Timespec};
 use super::signals;
-use super::pipe::disk::output as pipe_output;
-use super::pipe::disk::State;
+use super::pipe::disk::{output as pipe_output, output_watched, Activity, Capture, Redirect};
+use super::scan::{Matches, Patterns};
+
+/// How often the watchdog of a job checks whether the job has stalled.
+const WATCHDOG_INTERVAL: u64 = 50;
+
+/// The exit value that is recorded for a job which exited successfully, but whose outputs did not satisfy
+/// the `--fail-on-output` or `--require-output` patterns.
+const OUTPUT_FAILURE: i32 = 1;
+
+/// The resources that were consumed by a job, which are obtained when the job is reaped.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Usage {
+    /// The peak resident set size of the job, in kilobytes.
+    pub max_rss: u64,
+    /// The CPU time that the job spent in user mode, in nanoseconds.
+    pub user:    u64,
+    /// The CPU time that the job spent in kernel mode, in nanoseconds.
+    pub system:  u64,
+}
 
 /// Receives a `Child` and handles the child according. If a `timeout` is specified then the process will be killed
 /// if it exceeds the `timeout` value. Job stats are also gathered in case the `--joblog` parameter was supplied.
-pub fn handle_child(mut child: Child, output: &Sender<State>, flags: u16, job_id: usize, input: String,
-    has_timeout: bool, timeout: Duration, base: &str, buffer: &mut [u8]) -> (Timespec, Timespec, i32, i32)
+/// The outputs of the job will be written to the `capture`, or to the files given by the `redirect`, and scanned
+/// for the `patterns`, which may fail a job that exited successfully. The last value of the result is `true` if
+/// the job was killed for exceeding the `timeout` or the `stall_timeout`.
+pub fn handle_child(mut child: Child, config: Config, job_id: usize, has_timeout: bool, timeout: Duration,
+    stall_timeout: Duration, redirect: &Redirect, patterns: &Patterns, capture: &mut Capture)
+    -> (Timespec, Timespec, i32, i32, Usage, bool)
 {
     let start_time = get_time();
-    if has_timeout && child.wait_timeout(timeout).unwrap().is_none() {
-        let _ = child.kill();
-        pipe_output(&mut child, job_id, input, output, flags & QUIET_MODE != 0, base, buffer);
-        (start_time, get_time(), -1, 15)
+    if has_timeout || stall_timeout != Duration::from_millis(0) {
+        watch_child(child, config, job_id, has_timeout, timeout, stall_timeout, redirect, patterns, capture, start_time)
     } else {
-        pipe_output(&mut child, job_id, input, output, flags & QUIET_MODE != 0, base, buffer);
-        match child.wait() {
-            Ok(status) => match status.code() {
-                Some(exit) => (start_time, get_time(), exit, 0),
-                None       => (start_time, get_time(), -1, signals::get(status))
+        // Inherited outputs are written by the job itself, so there is nothing to capture.
+        let matches = if config.ungroup {
+            Matches::default()
+        } else {
+            pipe_output(&mut child, config.quiet, redirect, capture, patterns, job_id)
+        };
+        match reap(&mut child) {
+            Ok((status, usage)) => match status.code() {
+                Some(0) if patterns.failed(matches) => (start_time, get_time(), OUTPUT_FAILURE, 0, usage, false),
+                Some(exit) => (start_time, get_time(), exit, 0, usage, false),
+                None       => (start_time, get_time(), -1, signals::get(status), usage, false)
             },
-            Err(_) => (start_time, get_time(), -1, 0),
+            Err(_) => (start_time, get_time(), -1, 0, Usage::default(), false),
         }
     }
 }
+
+/// Handles a child which must not exceed the `timeout`, or whose outputs must not stall for longer than the
+/// `stall_timeout`. A watchdog thread kills the job if it exceeds the `timeout`, or if it has not written to
+/// its standard output or error within the `stall_timeout`, while the outputs of the job are being read.
+fn watch_child(mut child: Child, config: Config, job_id: usize, has_timeout: bool, timeout: Duration,
+    stall_timeout: Duration, redirect: &Redirect, patterns: &Patterns, capture: &mut Capture, start_time: Timespec)
+    -> (Timespec, Timespec, i32, i32, Usage, bool)
+{
+    let has_stall_timeout = stall_timeout != Duration::from_millis(0);
+    let activity = Arc::new(Activity::new());
+    let exited = Arc::new(AtomicBool::new(false));
+    let watchdog = {
+        let (activity, exited) = (activity.clone(), exited.clone());
+        let pid = child.id();
+        let started = Instant::now();
+        thread::spawn(move || {
+            while !exited.load(Ordering::SeqCst) {
+                if (has_stall_timeout && activity.idle() >= stall_timeout) || (has_timeout && started.elapsed() >= timeout) {
+                    // The job's process group is killed, so that processes spawned by the job are also killed.
+                    running::kill(pid);
+                    return true
+                }
+                thread::sleep(Duration::from_millis(WATCHDOG_INTERVAL));
+            }
+            false
+        })
+    };
+
+    let matches = if config.ungroup {
+        Matches::default()
+    } else {
+        output_watched(&mut child, config.quiet, redirect, capture, &activity, patterns, job_id)
+    };
+    let status = reap(&mut child);
+    exited.store(true, Ordering::SeqCst);
+    let killed = watchdog.join().unwrap_or(false);
+
+    match status {
+        Ok((status, usage)) => {
+            // The job may have exited by itself before the watchdog killed it. A job which was killed on Windows
+            // exits with a code, as Windows does not have signals.
+            let timed_out = killed && (cfg!(not(unix)) || status.code().is_none());
+            match status.code() {
+                Some(0) if patterns.failed(matches) => (start_time, get_time(), OUTPUT_FAILURE, 0, usage, timed_out),
+                Some(exit) => (start_time, get_time(), exit, 0, usage, timed_out),
+                None if timed_out => (start_time, get_time(), -1, signals::TIMEOUT, usage, true),
+                None       => (start_time, get_time(), -1, signals::get(status), usage, false)
+            }
+        },
+        Err(_) => (start_time, get_time(), -1, 0, Usage::default(), killed),
+    }
+}
+
+/// Waits for the job to exit, and obtains the resources that were consumed by the job from `wait4`.
+#[cfg(unix)]
+fn reap(child: &mut Child) -> io::Result<(ExitStatus, Usage)> {
+    use libc;
+    use std::mem;
+    use std::os::unix::process::ExitStatusExt;
+
+    let mut status = 0;
+    let mut usage: libc::rusage = unsafe { mem::zeroed() };
+    while unsafe { libc::wait4(child.id() as libc::pid_t, &mut status, 0, &mut usage) } == -1 {
+        let error = io::Error::last_os_error();
+        if error.kind() != io::ErrorKind::Interrupted { return Err(error) }
+    }
+
+    let nanoseconds = |time: libc::timeval| time.tv_sec as u64 * 1_000_000_000 + time.tv_usec as u64 * 1000;
+    // The peak resident set size is measured in bytes on macOS, and in kilobytes elsewhere.
+    let max_rss = if cfg!(target_os = "macos") { usage.ru_maxrss as u64 / 1024 } else { usage.ru_maxrss as u64 };
+    Ok((ExitStatus::from_raw(status), Usage {
+        max_rss: max_rss,
+        user:    nanoseconds(usage.ru_utime),
+        system:  nanoseconds(usage.ru_stime),
+    }))
+}
+
+/// The resources consumed by jobs are not obtained on other platforms.
+#[cfg(not(unix))]
+fn reap(child: &mut Child) -> io::Result<(ExitStatus, Usage)> {
+    child.wait().map(|status| (status, Usage::default()))
+}
@@ -1,9 +1,16 @@
+use std::cmp;
 use std::convert::AsRef;
 use std::ffi::OsStr;
 use std::io::{self, Write};
+use std::mem;
+use std::path::Path;
 use std::process::{Child, Command, Stdio};
 use std::str;
-use arguments;
+use std::time::Duration;
+use arguments::{self, group_key, Config};
+use ionice;
+use raw;
+use running;
 use tokenizer::*;
 use super::argument_splitter::ArgumentSplitter;
 
@@ -12,19 +19,230 @@ pub enum CommandErr {
 }
 
 /// If no placeholder tokens are in use, then the input will be appended at the end of the the command.
-pub fn append_argument(arguments: &mut String, command_template: &[Token], input: &str) {
-    // Check to see if any placeholder tokens are in use.
-    let placeholder_exists = command_template.iter().any(|x| match *x {
-        Token::BaseAndExt | Token::Basename | Token::Dirname | Token::Job | Token::Placeholder |
-        Token::RemoveExtension | Token::RemoveSuffix(_) | Token::Slot => true,
-        _ => false,
-    });
-
+pub fn append_argument(arguments: &mut String, command_template: &[Token], input: &str, config: Config) {
     // If no placeholder tokens are in use, the user probably wants to infer one.
-    if !placeholder_exists {
+    if !placeholder_exists(command_template) {
         arguments.push(' ');
+        if config.quote_inputs {
+            push_quoted(arguments, input);
+        } else {
+            arguments.push_str(input);
+        }
+    }
+}
+
+/// Checks to see if any placeholder tokens are in use.
+fn placeholder_exists(command_template: &[Token]) -> bool {
+    command_template.iter().any(|x| match *x {
+        Token::BaseAndExt | Token::BaseAndExts | Token::Basename | Token::Case(..) | Token::Dirname |
+        Token::Extension | Token::Job | Token::JobTotal | Token::Placeholder | Token::RealPath |
+        Token::RemoveExtension | Token::RemoveExtensions | Token::RemoveSuffix(_) | Token::SeqEnd |
+        Token::SeqStart | Token::Slot => true,
+        _ => false,
+    })
+}
+
+/// Splits a command template into words, removing the quotes and escapes within it as the shell
+/// would, while values that are substituted into the template are never split.
+#[derive(Default)]
+struct Words {
+    argv:     Vec<String>,
+    current:  String,
+    has_word: bool,
+    single:   bool,
+    double:   bool,
+    escape:   bool,
+}
+
+impl Words {
+    fn split(&mut self, template: &str) {
+        let mut characters = template.chars().peekable();
+        while let Some(character) = characters.next() {
+            if self.escape {
+                self.escape = false;
+                self.has_word = true;
+                self.current.push(character);
+                continue
+            }
+
+            match character {
+                // Backslashes are path separators on Windows.
+                '\\' if cfg!(windows) || self.single => self.current.push(character),
+                // Within double quotes, a backslash only escapes characters which are special to the shell.
+                '\\' if self.double => match characters.peek() {
+                    Some(&'"') | Some(&'\\') | Some(&'$') | Some(&'`') => self.escape = true,
+                    _ => self.current.push(character)
+                },
+                '\\' => self.escape = true,
+                '\'' if !self.double => {
+                    self.single = !self.single;
+                    self.has_word = true;
+                },
+                '"' if !self.single => {
+                    self.double = !self.double;
+                    self.has_word = true;
+                },
+                ' ' | '\t' | '\n' if !self.single && !self.double => if self.has_word {
+                    self.argv.push(mem::replace(&mut self.current, String::new()));
+                    self.has_word = false;
+                },
+                _ => {
+                    self.has_word = true;
+                    self.current.push(character);
+                }
+            }
+        }
+    }
+
+    fn push(&mut self, value: &str) {
+        self.has_word |= !value.is_empty();
+        self.current.push_str(value);
+    }
+
+    fn finish(mut self) -> Vec<String> {
+        if self.has_word { self.argv.push(self.current); }
+        self.argv
+    }
+}
+
+/// Appends the input to the arguments, quoted so that the shell will interpret it as a single
+/// argument. Inputs which only contain characters that are never special to the shell are left as is.
+#[cfg(not(windows))]
+fn push_quoted(arguments: &mut String, input: &str) {
+    let is_safe = |x: u8| (x as char).is_alphanumeric() || b"-_./,:=+%".contains(&x);
+    if !input.is_empty() && input.bytes().all(is_safe) {
         arguments.push_str(input);
+        return
     }
+
+    arguments.push('\'');
+    for character in input.chars() {
+        if character == '\'' { arguments.push_str("'\\''"); } else { arguments.push(character); }
+    }
+    arguments.push('\'');
+}
+
+/// On Windows, `cmd` arguments are quoted with double quotes, where a double quote is escaped by doubling it.
+#[cfg(windows)]
+fn push_quoted(arguments: &mut String, input: &str) {
+    arguments.push('"');
+    for character in input.chars() {
+        if character == '"' { arguments.push_str("\"\""); } else { arguments.push(character); }
+    }
+    arguments.push('"');
+}
+
+/// Replaces the job and slot replacement strings, which are `{#}` and `{%}` by default, and the `{##}` token
+/// within a command that was supplied as an input, writing the expanded command into `output`. Returns
+/// `false` if no tokens were found.
+pub fn expand_job_tokens(output: &mut String, input: &str, replace: &Replacements, slot: &str, job: &str,
+    job_total: &str) -> bool
+{
+    let matches = |token: &str| !token.is_empty() && input.contains(token);
+    if !matches(replace.seq) && !matches(replace.slot) && !input.contains("{##}") { return false }
+    output.clear();
+    let mut remaining = input;
+    while !remaining.is_empty() {
+        // As when tokenizing a command, the replacement strings take precedence over the `{##}` token.
+        let (value, length) = if !replace.seq.is_empty() && remaining.starts_with(replace.seq) {
+            (job, replace.seq.len())
+        } else if !replace.slot.is_empty() && remaining.starts_with(replace.slot) {
+            (slot, replace.slot.len())
+        } else if remaining.starts_with("{##}") {
+            (job_total, 4)
+        } else {
+            let length = remaining.chars().next().map_or(1, char::len_utf8);
+            (&remaining[..length], length)
+        };
+        output.push_str(value);
+        remaining = &remaining[length..];
+    }
+    true
+}
+
+/// Determines the range of records that each input contains, as inputs may contain several records
+/// when they are grouped with the `--max-args` parameter.
+#[derive(Clone, Copy, Debug)]
+pub struct Records {
+    /// The number of records within each input.
+    pub per_input: usize,
+    /// The total number of records, which may be unknown when inputs are streamed.
+    pub total:     usize,
+    /// The number of consecutive jobs that are given each input, as given by the `--repeat` parameter.
+    pub repeat:    usize,
+}
+
+impl Records {
+    /// Returns the numbers of the first and last records within the input of the given job ID.
+    pub fn range(&self, job_id: usize) -> (usize, usize) {
+        let start = job_id / self.repeat * self.per_input + 1;
+        (start, cmp::min(start + self.per_input - 1, self.total))
+    }
+}
+
+/// The timeout of each job, as given by the `--timeout` parameter, which may be obtained from the input of each
+/// job, so that the inputs which are expected to run for longer may be given longer timeouts.
+#[derive(Clone, Copy, Debug)]
+pub enum JobTimeout {
+    /// Every job has the same timeout, where a timeout of zero denotes that jobs are not timed out.
+    Fixed(Duration),
+    /// The timeout of each job, in seconds, is a column of its input, as separated by the `--colsep` parameter.
+    Column(usize, &'static str),
+    /// The timeout of each job, in seconds, is expanded from a template of tokens.
+    Template(&'static [Token]),
+}
+
+impl JobTimeout {
+    /// Obtains the timeout of the given job, expanding its template into the `buffer`. A job whose timeout is
+    /// not a number of seconds will not be timed out.
+    pub fn get(&self, command: &ParallelCommand, buffer: &mut String) -> Duration {
+        let value = match *self {
+            JobTimeout::Fixed(timeout) => return timeout,
+            JobTimeout::Column(column, colsep) => group_key(command.input, column, Some(colsep)),
+            JobTimeout::Template(template) => {
+                buffer.clear();
+                command.expand(template, buffer);
+                buffer.as_str()
+            }
+        };
+
+        match value.trim().parse::<f64>() {
+            Ok(seconds) if seconds > 0f64 => Duration::from_millis((seconds * 1000f64) as u64),
+            Ok(_) => Duration::from_millis(0),
+            Err(_) => {
+                let _ = writeln!(io::stderr(), "parallel: invalid timeout of '{}' for input '{}': the job will not be timed out",
+                    value, command.input);
+                Duration::from_millis(0)
+            }
+        }
+    }
+}
+
+/// The templates of the files that the standard output and error of each job will be written to, as
+/// given by the `--output` and `--error-output` parameters.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct OutputFiles {
+    pub stdout: Option<&'static [Token]>,
+    pub stderr: Option<&'static [Token]>,
+}
+
+impl OutputFiles {
+    /// Expands the templates for the given command into the `stdout` and `stderr` buffers, returning
+    /// the paths of the files that the outputs of the command's job will be written to.
+    pub fn paths<'b>(&self, command: &ParallelCommand, stdout: &'b mut String, stderr: &'b mut String)
+        -> (Option<&'b Path>, Option<&'b Path>)
+    {
+        (expand_path(command, self.stdout, stdout), expand_path(command, self.stderr, stderr))
+    }
+}
+
+/// Expands the template of a path for the given command into the `buffer`, if a template was given.
+pub fn expand_path<'b>(command: &ParallelCommand, template: Option<&[Token]>, buffer: &'b mut String) -> Option<&'b Path> {
+    template.map(move |template| {
+        buffer.clear();
+        command.expand(template, buffer);
+        Path::new(buffer.as_str())
+    })
 }
 
 /// A structure for generating commands to be executed.
@@ -32,26 +250,57 @@ pub struct ParallelCommand<'a> {
     pub slot_no:          &'a str,
     pub job_no:           &'a [u8],
     pub job_total:        &'a [u8],
+    pub seq_start:        &'a [u8],
+    pub seq_end:          &'a [u8],
     pub input:            &'a str,
-    pub flags:            u16,
+    pub config:           Config,
     pub command_template: &'a [Token],
+    pub shell:            Option<&'a str>,
+    pub wrapper:          &'a [String],
 }
 
 impl<'a> ParallelCommand<'a> {
-    /// Builds and execute commands based on given flags, supplied inputs and token arguments.
+    /// Builds and execute commands based on given config, supplied inputs and token arguments.
     pub fn exec(&self, arguments: &mut String) -> Result<Child, CommandErr> {
+        let argv = self.build(arguments);
+        self.spawn(arguments, argv, None)
+    }
+
+    /// Builds the command into `arguments`. If the command will not be executed within a shell, the
+    /// arguments of the command are also returned.
+    pub fn build(&self, arguments: &mut String) -> Option<Vec<String>> {
         self.build_arguments(arguments);
+        if !self.config.pipe {
+            append_argument(arguments, self.command_template, self.input, self.config);
+            if self.config.shell_enabled { return None }
+        }
+        Some(self.build_argv())
+    }
+
+    /// Spawns the command that was built by `build`, within the `workdir` if one was given.
+    pub fn spawn(&self, arguments: &str, argv: Option<Vec<String>>, workdir: Option<&Path>) -> Result<Child, CommandErr> {
+        let argv = match argv {
+            Some(argv) => argv,
+            None => return shell_output(arguments, self.shell, self.wrapper, self.config, workdir).map_err(CommandErr::IO)
+        };
 
-        if self.flags & arguments::PIPE_IS_ENABLED == 0 {
-            append_argument(arguments, self.command_template, self.input);
-            get_command_output(arguments.as_str(), self.flags).map_err(CommandErr::IO)
+        if !self.config.pipe {
+            argv_output(&argv, self.wrapper, self.config, workdir).map_err(CommandErr::IO)
         } else {
-            let mut child = get_command_output(arguments.as_str(), self.flags).map_err(CommandErr::IO)?;
+            let mut child = argv_output(&argv, self.wrapper, self.config, workdir).map_err(CommandErr::IO)?;
 
             {   // Grab a handle to the child's stdin and write the input argument to the child's stdin.
+                // If records were grouped with the `--group-by` parameter, each record is written on its own line.
                 let stdin = child.stdin.as_mut().unwrap();
-                stdin.write(self.input.as_bytes()).map_err(CommandErr::IO)?;
-                stdin.write(b"\n").map_err(CommandErr::IO)?;
+                if self.config.group_by {
+                    for record in self.input.split(arguments::RECORD_SEPARATOR) {
+                        stdin.write(&raw::encode(record)).map_err(CommandErr::IO)?;
+                        stdin.write(b"\n").map_err(CommandErr::IO)?;
+                    }
+                } else {
+                    stdin.write(&raw::encode(self.input)).map_err(CommandErr::IO)?;
+                    stdin.write(b"\n").map_err(CommandErr::IO)?;
+                }
             }
 
             // Drop the stdin of the child process to avoid having the application hang waiting for user input.
@@ -61,30 +310,91 @@ impl<'a> ParallelCommand<'a> {
         }
     }
 
+    /// Builds the arguments of a command that will be executed without a shell directly from the
+    /// `tokens` template and the current `input` value, so that inputs are never re-split.
+    pub fn build_argv(&self) -> Vec<String> {
+        let pipe      = self.config.pipe;
+        let mut words = Words::default();
+        for arg in self.command_template {
+            match *arg {
+                Token::Argument(ref arg)  => words.split(arg),
+                Token::Job                => words.push(str::from_utf8(self.job_no).unwrap_or("")),
+                Token::JobTotal           => words.push(str::from_utf8(self.job_total).unwrap_or("")),
+                Token::SeqEnd             => words.push(str::from_utf8(self.seq_end).unwrap_or("")),
+                Token::SeqStart           => words.push(str::from_utf8(self.seq_start).unwrap_or("")),
+                Token::Slot               => words.push(self.slot_no),
+                _ if pipe                 => (),
+                Token::Basename           => words.push(basename(self.input)),
+                Token::BaseAndExt         => words.push(basename(remove_extension(self.input))),
+                Token::BaseAndExts        => words.push(basename(remove_extensions(self.input))),
+                Token::BaseAndSuffix(pat) => words.push(basename(remove_pattern(self.input, pat))),
+                Token::Case(..)           => words.push(&arg.derive(self.input).unwrap_or_default()),
+                Token::Dirname            => words.push(dirname(self.input)),
+                Token::Extension          => words.push(extension(self.input)),
+                Token::Placeholder        => words.push(self.input),
+                Token::RealPath           => words.push(&realpath(self.input)),
+                Token::RemoveExtension    => words.push(remove_extension(self.input)),
+                Token::RemoveExtensions   => words.push(remove_extensions(self.input)),
+                Token::RemoveSuffix(pat)  => words.push(remove_pattern(self.input, pat)),
+            }
+        }
+
+        let mut argv = words.finish();
+        if !pipe && !placeholder_exists(self.command_template) {
+            argv.push(self.input.to_owned());
+        }
+        argv
+    }
+
+    /// Expands the tokens within the given template, such as the template of an output file, with
+    /// the current `input` value. Inputs are substituted verbatim, as the result is not a command.
+    pub fn expand(&self, template: &[Token], output: &mut String) {
+        let command = ParallelCommand { command_template: template, config: Config::default(), ..*self };
+        command.build_arguments(output);
+    }
+
     /// Builds arguments using the `tokens` template with the current `input` value.
     /// The arguments will be stored within a `Vec<String>`
     pub fn build_arguments(&self, arguments: &mut String) {
-        if self.flags & arguments::PIPE_IS_ENABLED != 0 {
+        if self.config.pipe {
             for arg in self.command_template {
                 match *arg {
                     Token::Argument(ref arg) => arguments.push_str(arg),
                     Token::Job               => for character in self.job_no { arguments.push(*character as char); },
+                    Token::JobTotal          => for character in self.job_total { arguments.push(*character as char); },
+                    Token::SeqEnd            => for character in self.seq_end { arguments.push(*character as char); },
+                    Token::SeqStart          => for character in self.seq_start { arguments.push(*character as char); },
                     Token::Slot              => arguments.push_str(self.slot_no),
                     _ => ()
                 }
             }
         } else {
+            // If the `--quote` parameter was passed, each substituted input will be quoted.
+            let push_input: fn(&mut String, &str) = if self.config.quote_inputs {
+                push_quoted
+            } else {
+                |arguments, input| arguments.push_str(input)
+            };
+
             for arg in self.command_template {
                 match *arg {
                     Token::Argument(ref arg)  => arguments.push_str(arg),
-                    Token::Basename           => arguments.push_str(basename(self.input)),
-                    Token::BaseAndExt         => arguments.push_str(basename(remove_extension(self.input))),
-                    Token::BaseAndSuffix(pat) => arguments.push_str(basename(remove_pattern(self.input, pat))),
-                    Token::Dirname            => arguments.push_str(dirname(self.input)),
+                    Token::Basename           => push_input(arguments, basename(self.input)),
+                    Token::BaseAndExt         => push_input(arguments, basename(remove_extension(self.input))),
+                    Token::BaseAndExts        => push_input(arguments, basename(remove_extensions(self.input))),
+                    Token::BaseAndSuffix(pat) => push_input(arguments, basename(remove_pattern(self.input, pat))),
+                    Token::Case(..)           => push_input(arguments, &arg.derive(self.input).unwrap_or_default()),
+                    Token::Dirname            => push_input(arguments, dirname(self.input)),
+                    Token::Extension          => push_input(arguments, extension(self.input)),
                     Token::Job                => for character in self.job_no { arguments.push(*character as char); },
-                    Token::Placeholder        => arguments.push_str(self.input),
-                    Token::RemoveExtension    => arguments.push_str(remove_extension(self.input)),
-                    Token::RemoveSuffix(pat)  => arguments.push_str(remove_pattern(self.input, pat)),
+                    Token::JobTotal           => for character in self.job_total { arguments.push(*character as char); },
+                    Token::Placeholder        => push_input(arguments, self.input),
+                    Token::RealPath           => push_input(arguments, &realpath(self.input)),
+                    Token::RemoveExtension    => push_input(arguments, remove_extension(self.input)),
+                    Token::RemoveExtensions   => push_input(arguments, remove_extensions(self.input)),
+                    Token::RemoveSuffix(pat)  => push_input(arguments, remove_pattern(self.input, pat)),
+                    Token::SeqEnd             => for character in self.seq_end { arguments.push(*character as char); },
+                    Token::SeqStart           => for character in self.seq_start { arguments.push(*character as char); },
                     Token::Slot               => arguments.push_str(self.slot_no)
                 }
             }
@@ -95,65 +405,179 @@ impl<'a> ParallelCommand<'a> {
 /// Handles shell execution and returns a handle to the underlying `Child` process.
 /// If the command requires to be executed in a shell, it will be executed within a shell.
 /// Otherwise, the arguments will be split and the command will run without a shell.
-pub fn get_command_output(command: &str, flags: u16) -> io::Result<Child> {
-    if flags & arguments::SHELL_ENABLED != 0 && flags & arguments::PIPE_IS_ENABLED == 0 {
-        shell_output(command, flags)
+/// If a `wrapper` was given, the command will be supplied as the arguments of the wrapper.
+pub fn get_command_output(command: &str, shell: Option<&str>, wrapper: &[String], config: Config,
+    workdir: Option<&Path>) -> io::Result<Child>
+{
+    if config.shell_enabled && !config.pipe {
+        shell_output(command, shell, wrapper, config, workdir)
     } else {
         // Collect each argument into a vector
         let arguments = ArgumentSplitter::new(command).collect::<Vec<&str>>();
-        match (arguments.len() == 1, flags & arguments::QUIET_MODE != 0, flags & arguments::PIPE_IS_ENABLED != 0) {
-            (true, true, false) => Command::new(&arguments[0])
-                .stdout(Stdio::null()).stderr(Stdio::piped())
-                .spawn(),
-            (true, true, true) => Command::new(&arguments[0])
-                .stdin(Stdio::piped()).stdout(Stdio::null()).stderr(Stdio::piped())
-                .spawn(),
-            (true, false, false) => Command::new(&arguments[0])
-                .stdout(Stdio::piped()).stderr(Stdio::piped())
-                .spawn(),
-            (true, false, true) => Command::new(&arguments[0])
-                .stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped())
-                .spawn(),
-            (false, true, false) => Command::new(&arguments[0]).args(&arguments[1..])
-                .stdout(Stdio::null()).stderr(Stdio::piped())
-                .spawn(),
-            (false, true, true) => Command::new(&arguments[0]).args(&arguments[1..])
-                .stdin(Stdio::piped()).stdout(Stdio::null()).stderr(Stdio::piped())
-                .spawn(),
-            (false, false, false) => Command::new(&arguments[0]).args(&arguments[1..])
-                .stdout(Stdio::piped()).stderr(Stdio::piped())
-                .spawn(),
-            (false, false, true) => Command::new(&arguments[0]).args(&arguments[1..])
-                .stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped())
-                .spawn(),
-        }
+        argv_output(&arguments, wrapper, config, workdir)
+    }
+}
+
+/// Creates a command for the given program, which will be executed by the `wrapper` command if one was given.
+fn wrapped_command<S: AsRef<OsStr>>(wrapper: &[String], program: S) -> Command {
+    match wrapper.split_first() {
+        Some((wrapper, arguments)) => {
+            let mut command = Command::new(wrapper);
+            command.args(arguments).arg(program);
+            command
+        },
+        None => Command::new(program)
+    }
+}
+
+/// Executes the command directly with the given arguments, without a shell. Arguments are encoded back
+/// into the bytes that their inputs were decoded from.
+fn argv_output<S: AsRef<str>>(argv: &[S], wrapper: &[String], config: Config, workdir: Option<&Path>)
+    -> io::Result<Child>
+{
+    match argv.split_first() {
+        Some((program, arguments)) => {
+            let mut command = wrapped_command(wrapper, raw::encode_os(program.as_ref()));
+            command.args(arguments.iter().map(|argument| raw::encode_os(argument.as_ref())));
+            spawn(command, config, workdir)
+        },
+        None => Err(io::Error::new(io::ErrorKind::InvalidInput, "no command was given"))
     }
 }
 
 /// Executes the command within a shell
-fn shell_output<S: AsRef<OsStr>>(args: S, flags: u16) -> io::Result<Child> {
-    let (cmd, flag) = if cfg!(windows) {
-        ("cmd".to_owned(), "/C")
-    } else if flags & arguments::ION_EXISTS != 0 {
-        ("ion".to_owned(), "-c")
-    } else if flags & arguments::DASH_EXISTS != 0  {
-        ("dash".to_owned(), "-c")
-    } else {
-        ("sh".to_owned(), "-c")
+fn shell_output(args: &str, shell: Option<&str>, wrapper: &[String], config: Config, workdir: Option<&Path>)
+    -> io::Result<Child>
+{
+    let args = raw::encode_os(args);
+    let command = match shell {
+        Some(shell) => {
+            let mut command = wrapped_command(wrapper, shell);
+            command.arg("-c").arg(&args);
+            command
+        },
+        None => shell_command(&args, wrapper, config)
     };
 
-    match (flags & arguments::QUIET_MODE != 0, flags & arguments::PIPE_IS_ENABLED != 0) {
-        (true, false) => Command::new(cmd).arg(flag).arg(args)
-            .stdout(Stdio::null()).stderr(Stdio::piped())
-            .spawn(),
-        (true, true) => Command::new(cmd).arg(flag).arg(args)
-            .stdin(Stdio::piped()).stdout(Stdio::null()).stderr(Stdio::piped())
-            .spawn(),
-        (false, false) => Command::new(cmd).arg(flag).arg(args)
-            .stdout(Stdio::piped()).stderr(Stdio::piped())
-            .spawn(),
-        (false, true) => Command::new(cmd).arg(flag).arg(args)
-            .stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped())
-            .spawn()
+    spawn(command, config, workdir)
+}
+
+/// Spawns the command, with the standard input piped if pipe mode is enabled, and otherwise empty,
+/// and the standard output discarded if quiet mode is enabled. In ungrouped mode,
+/// the outputs are inherited, so that they are written without being copied.
+/// The command is executed within the `workdir`, if one was given.
+fn spawn(mut command: Command, config: Config, workdir: Option<&Path>) -> io::Result<Child> {
+    if let Some(workdir) = workdir {
+        // Otherwise, a missing directory would be reported as though the program was not found.
+        if !workdir.is_dir() {
+            return Err(io::Error::new(io::ErrorKind::NotFound,
+                format!("working directory {:?} does not exist", workdir)));
+        }
+        command.current_dir(workdir);
     }
+    running::set_process_group(&mut command);
+    ionice::set_priority(&mut command);
+    // Jobs must never read parallel's own standard input, which may be a terminal, or the inputs themselves.
+    command.stdin(if config.pipe { Stdio::piped() } else { Stdio::null() });
+    if config.quiet {
+        command.stdout(Stdio::null());
+    } else if config.ungroup {
+        command.stdout(Stdio::inherit());
+    } else {
+        command.stdout(Stdio::piped());
+    }
+
+    if config.ungroup {
+        command.stderr(Stdio::inherit()).spawn()
+    } else {
+        command.stderr(Stdio::piped()).spawn()
+    }
+}
+
+/// Constructs the default shell command that the given command will be executed within.
+#[cfg(not(windows))]
+fn shell_command(args: &OsStr, wrapper: &[String], config: Config) -> Command {
+    let cmd = if config.ion_exists {
+        "ion"
+    } else if config.dash_exists  {
+        "dash"
+    } else {
+        "sh"
+    };
+
+    let mut command = wrapped_command(wrapper, cmd);
+    command.arg("-c").arg(args);
+    command
+}
+
+/// On Windows, the command is passed to `cmd /C` verbatim, because `cmd` does not follow the
+/// quoting rules that are used when arguments are escaped for typical Windows programs.
+#[cfg(windows)]
+fn shell_command(args: &OsStr, wrapper: &[String], _config: Config) -> Command {
+    use std::os::windows::process::CommandExt;
+    let mut command = wrapped_command(wrapper, "cmd");
+    command.arg("/C").raw_arg(args);
+    command
+}
+
+#[test]
+fn job_token_expansion() {
+    let mut output = String::new();
+    let replace = Replacements::default();
+    assert!(!expand_job_tokens(&mut output, "echo {}", &replace, "1", "2", "3"));
+    assert!(expand_job_tokens(&mut output, "echo {#}/{##} {%} {x}", &replace, "1", "2", "3"));
+    assert_eq!(output, "echo 2/3 1 {x}");
+
+    let replace = Replacements { seq: "SEQ", slot: "é" };
+    assert!(!expand_job_tokens(&mut output, "echo {#} {%}", &replace, "1", "2", "3"));
+    assert!(expand_job_tokens(&mut output, "echo SEQ/{##} é", &replace, "1", "2", "3"));
+    assert_eq!(output, "echo 2/3 1");
+}
+
+#[cfg(not(windows))]
+#[test]
+fn input_quoting() {
+    let mut arguments = String::new();
+    push_quoted(&mut arguments, "file.txt");
+    arguments.push(' ');
+    push_quoted(&mut arguments, "it's $HOME");
+    assert_eq!(arguments, "file.txt 'it'\\''s $HOME'");
+}
+
+#[cfg(not(windows))]
+#[test]
+fn argv_building() {
+    let template = [Token::Argument("printf \"%s %s\" 'a b'\\ c ".into()), Token::Placeholder,
+        Token::Argument(" x\"{}\"".into())];
+    let command = ParallelCommand {
+        slot_no: "1", job_no: b"1", job_total: b"1", seq_start: b"1", seq_end: b"1", input: "it's $HOME", config: Config::default(),
+        command_template: &template, shell: None, wrapper: &[],
+    };
+    assert_eq!(command.build_argv(), vec!["printf", "%s %s", "a b c", "it's $HOME", "x{}"]);
+}
+
+#[test]
+fn output_file_paths() {
+    static TEMPLATE: [Token; 3] = [Token::Argument(::std::borrow::Cow::Borrowed("out/")), Token::BaseAndExt,
+        Token::Argument(::std::borrow::Cow::Borrowed(".log"))];
+    let command = ParallelCommand {
+        slot_no: "1", job_no: b"2", job_total: b"3", seq_start: b"2", seq_end: b"2", input: "src/it's.rs",
+        config: Config { quote_inputs: true, ..Config::default() }, command_template: &[], shell: None, wrapper: &[],
+    };
+    let outputs = OutputFiles { stdout: Some(&TEMPLATE), stderr: None };
+    let (mut stdout, mut stderr) = (String::new(), String::new());
+    assert_eq!(outputs.paths(&command, &mut stdout, &mut stderr), (Some(Path::new("out/it's.log")), None));
+}
+
+#[test]
+fn job_timeouts() {
+    static TEMPLATE: [Token; 1] = [Token::Extension];
+    let command = ParallelCommand {
+        slot_no: "1", job_no: b"1", job_total: b"1", seq_start: b"1", seq_end: b"1", input: "slow,2.5,job.10",
+        config: Config::default(), command_template: &[], shell: None, wrapper: &[],
+    };
+    let mut buffer = String::new();
+    assert_eq!(JobTimeout::Fixed(Duration::from_secs(1)).get(&command, &mut buffer), Duration::from_secs(1));
+    assert_eq!(JobTimeout::Column(2, ",").get(&command, &mut buffer), Duration::from_millis(2500));
+    assert_eq!(JobTimeout::Template(&TEMPLATE).get(&command, &mut buffer), Duration::from_secs(10));
 }
@@ -0,0 +1,40 @@
+use std::path::Path;
+use shell;
+
+/// The directory within the container that each input is mounted within.
+const MOUNT_DIR: &str = "/parallel";
+
+/// Creates the command that each job will be executed within a container of the given image with,
+/// preferring podman over docker. The image is always the last argument of the command.
+pub fn command(image: &str) -> Vec<String> {
+    let engine = if shell::exists("podman") { "podman" } else { "docker" };
+    [engine, "run", "--rm", "--interactive", image].iter().map(|&x| x.to_owned()).collect()
+}
+
+/// If the input is a path that exists, the container command will be written to `job_wrapper` with the
+/// path bind-mounted into the container, and the path of the input within the container is returned.
+pub fn mount(wrapper: &[String], input: &str, job_wrapper: &mut Vec<String>) -> Option<String> {
+    let path = Path::new(input).canonicalize().ok()?;
+    let name = path.file_name()?.to_str()?.to_owned();
+    let container_path = [MOUNT_DIR, "/", &name].concat();
+
+    let (image, arguments) = wrapper.split_last()?;
+    job_wrapper.clear();
+    job_wrapper.extend(arguments.iter().cloned());
+    job_wrapper.push(String::from("--volume"));
+    job_wrapper.push([path.to_str()?, ":", &container_path].concat());
+    job_wrapper.push(image.clone());
+    Some(container_path)
+}
+
+#[test]
+fn container_mount() {
+    let wrapper = command("alpine");
+    let mut job_wrapper = Vec::new();
+    assert_eq!(mount(&wrapper, "/nonexistent/path", &mut job_wrapper), None);
+    assert_eq!(mount(&wrapper, "/", &mut job_wrapper), None);
+    let path = mount(&wrapper, "Cargo.toml", &mut job_wrapper).unwrap();
+    assert_eq!(path, "/parallel/Cargo.toml");
+    assert_eq!(job_wrapper.last().unwrap(), "alpine");
+    assert!(job_wrapper[job_wrapper.len()-2].ends_with("Cargo.toml:/parallel/Cargo.toml"));
+}
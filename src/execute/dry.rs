@@ -1,39 +1,86 @@
 use input_iterator::{InputIterator, InputIteratorErr};
 use tokenizer::Token;
-use arguments;
+use arguments::Config;
 use execute::command;
 use numtoa::NumToA;
+use raw;
 
-use std::io::{self, StdoutLock, Read, Write};
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::process::exit;
 
 /// Instead of executing commands in parallel, the commands that would be executed will be printed
 /// directly to the standard output of this application. This also applies to shell quoted arguments.
-pub fn dry_run<IO: Read>(flags: u16, inputs: InputIterator<IO>, arguments: &[Token]) {
-    let stdout             = io::stdout();
-    let stdout             = &mut stdout.lock();
+/// If a `script` path was supplied, the commands will instead be written to an executable shell script.
+pub fn dry_run<IO: Read>(config: Config, inputs: InputIterator<IO>, arguments: &[Token], records: command::Records,
+    script: Option<&str>)
+{
+    match script {
+        Some(path) => {
+            let result = File::create(path).and_then(|file| {
+                let mut file = BufWriter::new(file);
+                file.write_all(b"#!/bin/sh\nset -e\n")?;
+                write_commands(&mut file, config, true, inputs, arguments, records);
+                file.flush()?;
+                set_executable(path)
+            });
+
+            if let Err(why) = result {
+                let stderr = io::stderr();
+                let _ = writeln!(stderr.lock(), "parallel: unable to write dry run script {:?}: {}", path, why);
+                exit(1);
+            }
+        },
+        None => {
+            let stdout = io::stdout();
+            write_commands(&mut stdout.lock(), config, false, inputs, arguments, records);
+        }
+    }
+}
+
+#[cfg(unix)]
+fn set_executable(path: &str) -> io::Result<()> {
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o755))
+}
+
+#[cfg(not(unix))]
+fn set_executable(_path: &str) -> io::Result<()> { Ok(()) }
+
+/// Writes each command that would be executed to the given `output`, one command per line. When
+/// `script` is set, the commands are being written to a script, and piped inputs must be supplied to
+/// each command within the script, while substituted inputs are quoted, so that they are not interpreted
+/// by the shell which executes the script.
+fn write_commands<IO: Read, W: Write>(output: &mut W, mut config: Config, script: bool, inputs: InputIterator<IO>, arguments: &[Token],
+    records: command::Records)
+{
     let stderr             = io::stderr();
     let stderr             = &mut stderr.lock();
+    if script { config.quote_inputs = true; }
     let mut command_buffer = String::new();
     let slot               = "{SLOT_ID}";
-    let pipe               = flags & arguments::PIPE_IS_ENABLED != 0;
+    let pipe               = config.pipe;
     let mut id_buffer      = [0u8; 20];
     let mut total_buffer   = [0u8; 20];
-    let start_indice       = inputs.total_arguments.numtoa(10, &mut total_buffer);
+    let mut start_buffer   = [0u8; 20];
+    let mut end_buffer     = [0u8; 20];
+    let start_indice       = inputs.total().numtoa(10, &mut total_buffer);
     let job_total          = &total_buffer[start_indice..];
 
     // If `SHELL_QUOTE` is enabled then the quoted command will be printed, otherwise the command will be
     // printed unmodified. The correct function to execute will be assigned here in advance.
-    let pipe_action: Box<Fn(&mut StdoutLock, &str)> = if flags & arguments::SHELL_QUOTE != 0 {
-        Box::new(|stdout: &mut StdoutLock, input: &str| {
+    let pipe_action: Box<dyn Fn(&mut W, &str)> = if config.shell_quote {
+        Box::new(|output: &mut W, input: &str| {
             if let Some(new_arg) = shell_quote(input) {
-                let _ = stdout.write(new_arg.as_bytes());
+                let _ = output.write(&raw::encode(&new_arg));
             } else {
-                let _ = stdout.write(input.as_bytes());
+                let _ = output.write(&raw::encode(input));
             }
         })
     } else {
-        Box::new(|stdout: &mut StdoutLock, input: &str| {
-            let _ = stdout.write(input.as_bytes());
+        Box::new(|output: &mut W, input: &str| {
+            let _ = output.write(&raw::encode(input));
         })
     };
 
@@ -41,21 +88,31 @@ pub fn dry_run<IO: Read>(flags: u16, inputs: InputIterator<IO>, arguments: &[Tok
         match input {
             Ok(input) => {
                 let start_indice = job_id.numtoa(10, &mut id_buffer);
+                let (seq_start, seq_end) = records.range(job_id);
+                let seq_start = seq_start.numtoa(10, &mut start_buffer);
+                let seq_end = seq_end.numtoa(10, &mut end_buffer);
                 let command = command::ParallelCommand {
                     slot_no:          slot,
                     job_no:           &id_buffer[start_indice..],
                     job_total:        job_total,
+                    seq_start:        &start_buffer[seq_start..],
+                    seq_end:          &end_buffer[seq_end..],
                     input:            &input,
                     command_template: arguments,
-                    flags:            flags,
+                    config:           config,
+                    shell:            None,
+                    wrapper:          &[],
                 };
 
                 command.build_arguments(&mut command_buffer);
                 if !pipe {
-                    command::append_argument(&mut command_buffer, command.command_template, command.input);
+                    command::append_argument(&mut command_buffer, command.command_template, command.input, config);
+                } else if script {
+                    // Within a script, the input must be piped into the command's standard input.
+                    let _ = write!(output, "printf '%s\\n' '{}' | ", input.replace('\'', "'\\''"));
                 }
-                pipe_action(stdout, &command_buffer);
-                let _ = stdout.write(b"\n");
+                pipe_action(output, &command_buffer);
+                let _ = output.write(b"\n");
                 command_buffer.clear();
             },
             Err(why) => {
@@ -70,6 +127,7 @@ pub fn dry_run<IO: Read>(flags: u16, inputs: InputIterator<IO>, arguments: &[Tok
 }
 
 /// Simply escapes special characters, optionally returning a new `String` if changes occurred
+#[cfg(not(windows))]
 fn shell_quote(command: &str) -> Option<String> {
     // Determines if allocations will be necessary or not.
     let mut needs_escaping = false;
@@ -98,3 +156,24 @@ fn shell_quote(command: &str) -> Option<String> {
         None
     }
 }
+
+/// Escapes characters that are special to `cmd.exe` with the `^` character, optionally returning
+/// a new `String` if changes occurred.
+#[cfg(windows)]
+fn shell_quote(command: &str) -> Option<String> {
+    let is_special = |character: char| match character {
+        '^' | '&' | '|' | '<' | '>' | '(' | ')' | '%' | '!' | '"' => true,
+        _ => false
+    };
+
+    if command.chars().any(is_special) {
+        let mut output = String::with_capacity(command.len() * 2);
+        for character in command.chars() {
+            if is_special(character) { output.push('^'); }
+            output.push(character);
+        }
+        Some(output)
+    } else {
+        None
+    }
+}
@@ -1,17 +1,29 @@
-use arguments::{VERBOSE_MODE, JOBLOG};
-use execute::command::{self, CommandErr};
+use arguments::Config;
+use events::EventLog;
+use execute::command::{self, CommandErr, JobTimeout, OutputFiles, Records};
+use execute::container;
 use input_iterator::InputsLock;
+use notify;
 use numtoa::NumToA;
+use queue::WorkQueue;
+use results::ResultsDir;
+use summary;
+use syslog;
 use time::{self, Timespec};
+use trace::{Phase, TraceLog};
 use tokenizer::Token;
 use verbose;
-use super::pipe::disk::State;
+use super::pipe::disk::{Capture, Redirect, State};
 use super::job_log::JobLog;
-use super::child::handle_child;
+use super::child::{handle_child, Usage};
+use super::retry::Retries;
+use super::scan::Patterns;
+use super::timestamp::Timestamp;
+use super::window::ResultSender;
 
 use std::io::{self, Read, Write};
-use std::sync::mpsc::Sender;
-use std::time::Duration;
+use std::thread;
+use std::time::{Duration, Instant};
 
 /// Contains all the required data needed for executing commands in parallel.
 /// Commands will be generated based on a template of argument tokens combined
@@ -19,12 +31,29 @@ use std::time::Duration;
 pub struct ExecCommands<IO: Read> {
     pub slot:       usize,
     pub num_inputs: usize,
-    pub flags:      u16,
-    pub timeout:    Duration,
+    pub records:    Records,
+    pub retries:    Retries,
+    pub outputs:    OutputFiles,
+    pub on_fail:    Option<&'static [Token]>,
+    pub workdir:    Option<&'static [Token]>,
+    pub config:     Config,
+    pub timeout:    JobTimeout,
+    pub stall_timeout: Duration,
+    pub patterns:   Patterns,
     pub inputs:     InputsLock<IO>,
-    pub output_tx:  Sender<State>,
+    /// The input of the current job, which is kept so that the job may be recorded if the worker panics.
+    pub input:      String,
+    pub output_tx:  ResultSender,
     pub arguments:  &'static [Token],
     pub tempdir:    String,
+    pub buffer_size: usize,
+    pub timestamp:  Option<Timestamp>,
+    pub events:     Option<EventLog>,
+    pub results:    Option<ResultsDir>,
+    pub queue:      Option<WorkQueue>,
+    pub trace:      Option<TraceLog>,
+    pub shell:      Option<&'static str>,
+    pub wrapper:    &'static [String],
 }
 
 impl<IO: Read> ExecCommands<IO> {
@@ -34,51 +63,119 @@ impl<IO: Read> ExecCommands<IO> {
 
         let slot               = &self.slot.to_string();
         let mut command_buffer = &mut String::with_capacity(64);
-        let has_timeout        = self.timeout != Duration::from_millis(0);
-        let mut input          = String::with_capacity(64);
         let mut id_buffer      = [0u8; 20];
         let mut job_buffer     = [0u8; 20];
         let mut total_buffer   = [0u8; 20];
+        let mut start_buffer   = [0u8; 20];
+        let mut end_buffer     = [0u8; 20];
         let mut start_indice   = self.num_inputs.numtoa(10, &mut total_buffer);
         let job_total          = &total_buffer[start_indice..];
+        let mount              = self.config.container_mount && !self.wrapper.is_empty();
+        let mut job_wrapper    = Vec::new();
+        let mut stdout_path    = String::new();
+        let mut stderr_path    = String::new();
+        let mut timeout_buffer = String::new();
+        let mut workdir_path   = String::new();
+        let mut capture        = Capture::new(&self.tempdir, self.buffer_size, self.timestamp.clone());
 
+        // When the `--trace-file` parameter was passed, the phases of each job will be timed from this instant.
+        let mut phase_start    = Instant::now();
 
-        while let Some(job_id) = self.inputs.try_next(&mut input) {
-            if self.flags & VERBOSE_MODE != 0  {
-                verbose::processing_task(&stdout, job_id+1, self.num_inputs, &input);
+        while let Some(job_id) = self.inputs.try_next(&mut self.input) {
+            if let Some(ref trace) = self.trace { phase_start = trace.span(job_id+1, Phase::Fetch, phase_start); }
+
+            if self.config.verbose  {
+                verbose::processing_task(&stdout, job_id+1, self.num_inputs, &self.input);
             }
 
+            if let Some(ref events) = self.events { events.job_started(job_id+1, &self.input); }
+
+            // If the input is a path, it may be mounted into the container and substituted by its container path.
+            let container_input = if mount {
+                container::mount(self.wrapper, &self.input, &mut job_wrapper)
+            } else {
+                None
+            };
+            let (job_input, wrapper) = match container_input {
+                Some(ref path) => (path.as_str(), job_wrapper.as_slice()),
+                None           => (self.input.as_str(), self.wrapper)
+            };
+
             start_indice = (job_id+1).numtoa(10, &mut id_buffer);
+            let (seq_start, seq_end) = self.records.range(job_id);
+            let seq_start = seq_start.numtoa(10, &mut start_buffer);
+            let seq_end = seq_end.numtoa(10, &mut end_buffer);
             let command = command::ParallelCommand {
                 slot_no:          slot,
                 job_no:           &id_buffer[start_indice..],
                 job_total:        job_total,
-                input:            &input,
+                seq_start:        &start_buffer[seq_start..],
+                seq_end:          &end_buffer[seq_end..],
+                input:            job_input,
                 command_template: self.arguments,
-                flags:            self.flags
+                config:           self.config,
+                shell:            self.shell,
+                wrapper:          wrapper,
             };
 
-            command_buffer.clear();
-            let (start_time, end_time, exit_value, signal) = match command.exec(command_buffer) {
-                Ok(child) => {
-                    handle_child(child, &self.output_tx, self.flags, job_id, input.clone(), has_timeout, self.timeout,
-                        &self.tempdir, &mut job_buffer)
-                },
-                Err(cmd_err) => {
-                    let mut stderr = stderr.lock();
-                    let _ = stderr.write(b"parallel: command error: ");
-                    let message = match cmd_err {
-                        CommandErr::IO(error) => format!("I/O error: {}\n", error),
-                    };
-
-                    let _ = stderr.write(message.as_bytes());
-                    let message = format!("{}: {}: {}", job_id+1, command.input, message);
-                    let _ = self.output_tx.send(State::Error(job_id, message));
-                    (Timespec::new(0, 0), Timespec::new(0, 0), -1, 0)
+            // The outputs may be written to files that are named after the original input.
+            let job = command::ParallelCommand { input: &self.input, ..command };
+            let (stdout_file, stderr_file) = self.outputs.paths(&job, &mut stdout_path, &mut stderr_path);
+            let timeout = self.timeout.get(&job, &mut timeout_buffer);
+            let has_timeout = timeout != Duration::from_millis(0);
+            let workdir = command::expand_path(&job, self.workdir, &mut workdir_path);
+
+            capture.begin(job_id, &mut job_buffer);
+            let mut retry = 0;
+            let (start_time, end_time, exit_value, signal, usage, timed_out) = loop {
+                command_buffer.clear();
+                let argv = command.build(command_buffer);
+                if let Some(ref trace) = self.trace { phase_start = trace.span(job_id+1, Phase::Build, phase_start); }
+                match command.spawn(command_buffer, argv, workdir) {
+                    Ok(child) => {
+                        if let Some(ref trace) = self.trace { phase_start = trace.span(job_id+1, Phase::Spawn, phase_start); }
+                        let pid = child.id();
+                        self.inputs.started(pid);
+                        let redirect = Redirect { append: retry != 0, stdout: stdout_file, stderr: stderr_file };
+                        let result = handle_child(child, self.config, job_id, has_timeout, timeout,
+                            self.stall_timeout, &redirect, &self.patterns, &mut capture);
+                        self.inputs.exited(pid);
+                        if let Some(ref trace) = self.trace { trace.span(job_id+1, Phase::Run, phase_start); }
+
+                        // A failed job will be retried with the same input until its retries are exhausted.
+                        if (result.2 != 0 || result.3 != 0) && retry < self.retries.retries {
+                            retry += 1;
+                            let delay = self.retries.delay(retry);
+                            log!(Debug, "job {} failed with exit value {} and signal {}; retry {} of {} in {:?}",
+                                job_id+1, result.2, result.3, retry, self.retries.retries, delay);
+                            thread::sleep(delay);
+                            phase_start = Instant::now();
+                            continue
+                        }
+
+                        let _ = self.output_tx.send(State::Completed(job_id, self.input.clone(), capture.finish()));
+                        self.inputs.reported = true;
+                        break result
+                    },
+                    Err(cmd_err) => {
+                        let mut stderr = stderr.lock();
+                        let _ = stderr.write(b"parallel: command error: ");
+                        let message = match cmd_err {
+                            CommandErr::IO(error) => format!("I/O error: {}\n", error),
+                        };
+
+                        let _ = stderr.write(message.as_bytes());
+                        let message = format!("{}: {}: {}", job_id+1, command.input, message);
+                        let _ = self.output_tx.send(State::Error(job_id, message));
+                        self.inputs.reported = true;
+                        break (Timespec::new(0, 0), Timespec::new(0, 0), -1, 0, Usage::default(), false)
+                    }
                 }
             };
 
-            if self.flags & JOBLOG != 0 {
+            self.inputs.report(exit_value, signal);
+
+            if self.config.joblog {
                 let runtime: time::Duration = end_time - start_time;
                 let _ = self.output_tx.send(State::JobLog(JobLog {
                     job_id:     job_id,
@@ -86,14 +183,48 @@ impl<IO: Read> ExecCommands<IO> {
                     runtime:    runtime.num_nanoseconds().unwrap_or(0) as u64,
                     exit_value: exit_value,
                     signal:     signal,
-                    flags:      self.flags,
+                    usage:      usage,
+                    config:     self.config,
                     command:    command_buffer.clone(),
                 }));
+                self.inputs.logged = true;
+            }
+
+            let runtime = (end_time - start_time).num_nanoseconds().unwrap_or(0) as u64;
+            summary::job_finished(job_id+1, exit_value, signal, timed_out, runtime, usage, command_buffer);
+            if let (Some(template), true) = (self.on_fail, exit_value != 0 || signal != 0) {
+                notify::job_failed(template, &job, exit_value, signal);
             }
 
-            if self.flags & VERBOSE_MODE != 0 {
-                verbose::task_complete(&stdout, job_id, self.num_inputs, &input);
+            if let Some(ref events) = self.events {
+                events.job_finished(job_id+1, &self.input, exit_value, signal, runtime);
             }
+
+            if self.config.log_syslog {
+                syslog::job_finished(job_id+1, exit_value, signal, runtime, command_buffer);
+            }
+
+            if let Some(ref results) = self.results {
+                if let Err(why) = results.job_finished(job_id+1, &self.input, command_buffer, exit_value, signal,
+                    start_time, runtime, usage)
+                {
+                    let _ = writeln!(stderr.lock(), "parallel: unable to write the results of job {}: {}", job_id+1, why);
+                }
+            }
+
+            if let Some(ref queue) = self.queue {
+                if let Err(why) = queue.job_finished(job_id, &self.input, command_buffer, exit_value, signal,
+                    start_time, runtime, usage)
+                {
+                    let _ = writeln!(stderr.lock(), "parallel: unable to record job {} in the queue: {}", job_id+1, why);
+                }
+            }
+
+            if self.config.verbose {
+                verbose::task_complete(&stdout, job_id, self.num_inputs, &self.input, usage);
+            }
+
+            phase_start = Instant::now();
         }
     }
 }
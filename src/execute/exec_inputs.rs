@@ -1,64 +1,174 @@
-use arguments::{self, JOBLOG};
-use execute::command;
+use arguments::{self, Config};
+use events::EventLog;
+use execute::command::{self, JobTimeout, OutputFiles};
 use input_iterator::InputsLock;
+use notify;
+use queue::WorkQueue;
+use results::ResultsDir;
 use shell;
+use summary;
+use syslog;
 use time::Timespec;
+use numtoa::NumToA;
+use tokenizer::{Replacements, Token};
+use trace::{Phase, TraceLog};
 use verbose;
 use super::job_log::JobLog;
-use super::pipe::disk::State;
-use super::child::handle_child;
+use super::pipe::disk::{Capture, Redirect, State};
+use super::child::{handle_child, Usage};
+use super::retry::Retries;
+use super::scan::Patterns;
+use super::timestamp::Timestamp;
+use super::window::ResultSender;
 
-use std::u16;
-use std::time::Duration;
+use std::borrow::Cow;
+use std::str;
+use std::time::{Duration, Instant};
 use std::io::{self, Read, Write};
-use std::sync::mpsc::Sender;
+use std::thread;
 
 /// Contains all the required data needed for executing commands in parallel.
 /// The inputs will be executed as commands themselves.
 pub struct ExecInputs<IO: Read> {
+    pub slot:       usize,
     pub num_inputs: usize,
-    pub timeout:    Duration,
+    pub retries:    Retries,
+    pub outputs:    OutputFiles,
+    pub on_fail:    Option<&'static [Token]>,
+    pub workdir:    Option<&'static [Token]>,
+    pub timeout:    JobTimeout,
+    pub stall_timeout: Duration,
+    pub patterns:   Patterns,
     pub inputs:     InputsLock<IO>,
-    pub output_tx:  Sender<State>,
+    /// The input of the current job, which is kept so that the job may be recorded if the worker panics.
+    pub input:      String,
+    pub output_tx:  ResultSender,
     pub tempdir:    String,
+    pub buffer_size: usize,
+    pub timestamp:  Option<Timestamp>,
+    pub events:     Option<EventLog>,
+    pub results:    Option<ResultsDir>,
+    pub queue:      Option<WorkQueue>,
+    pub trace:      Option<TraceLog>,
+    pub replace:    Replacements,
+    pub shell:      Option<&'static str>,
+    pub wrapper:    &'static [String],
 }
 
 impl<IO: Read> ExecInputs<IO> {
-    pub fn run(&mut self, mut flags: u16) {
+    pub fn run(&mut self, mut config: Config) {
         let stdout = io::stdout();
         let stderr = io::stderr();
 
-        let has_timeout   = self.timeout != Duration::from_millis(0);
-        let mut input     = String::with_capacity(64);
-        let mut id_buffer = [0u8; 20];
+        let slot             = &self.slot.to_string();
+        let job_total        = &self.num_inputs.to_string();
+        let mut expanded     = String::with_capacity(64);
+        let mut id_buffer    = [0u8; 20];
+        let mut job_buffer   = [0u8; 20];
+        let mut stdout_path  = String::new();
+        let mut stderr_path  = String::new();
+        let mut timeout_buffer = String::new();
+        let mut workdir_path = String::new();
+        let mut capture      = Capture::new(&self.tempdir, self.buffer_size, self.timestamp.clone());
 
-        while let Some(job_id) = self.inputs.try_next(&mut input) {
-            if flags & arguments::VERBOSE_MODE != 0 {
-                verbose::processing_task(&stdout, job_id+1, self.num_inputs, &input);
+        // When the `--trace-file` parameter was passed, the phases of each job will be timed from this instant.
+        let mut phase_start  = Instant::now();
+
+        while let Some(job_id) = self.inputs.try_next(&mut self.input) {
+            if let Some(ref trace) = self.trace { phase_start = trace.span(job_id+1, Phase::Fetch, phase_start); }
+
+            if config.verbose {
+                verbose::processing_task(&stdout, job_id+1, self.num_inputs, &self.input);
             }
 
+            if let Some(ref events) = self.events { events.job_started(job_id+1, &self.input); }
+
+            // With `--expand-tokens`, the job and slot replacement strings within the input will be replaced
+            // within the command that is executed, while the input itself is recorded as it was given.
+            let start_indice = (job_id+1).numtoa(10, &mut job_buffer);
+            let job_no = str::from_utf8(&job_buffer[start_indice..]).unwrap();
+            let command = if config.expand_tokens
+                && command::expand_job_tokens(&mut expanded, &self.input, &self.replace, slot, job_no, job_total)
+            {
+                expanded.as_str()
+            } else {
+                self.input.as_str()
+            };
+
+            // The outputs may be written to files that are named after the input.
+            let job = command::ParallelCommand {
+                slot_no:          slot,
+                job_no:           job_no.as_bytes(),
+                job_total:        job_total.as_bytes(),
+                seq_start:        job_no.as_bytes(),
+                seq_end:          job_no.as_bytes(),
+                input:            &self.input,
+                config:           Config::default(),
+                command_template: &[],
+                shell:            None,
+                wrapper:          &[],
+            };
+            let (stdout_file, stderr_file) = self.outputs.paths(&job, &mut stdout_path, &mut stderr_path);
+            let timeout = self.timeout.get(&job, &mut timeout_buffer);
+            let has_timeout = timeout != Duration::from_millis(0);
+            let workdir = command::expand_path(&job, self.workdir, &mut workdir_path);
+
+            // The lines of multi-line commands from the `--commands-file` are separated by the `RECORD_SEPARATOR`.
+            let command_line = if command.contains(arguments::RECORD_SEPARATOR) {
+                Cow::Owned(command.replace(arguments::RECORD_SEPARATOR, "\n"))
+            } else {
+                Cow::Borrowed(command)
+            };
+
             // Checks the current command to determine if a shell will be required.
-            if shell::required(shell::Kind::Input(&input)) {
-                flags |= arguments::SHELL_ENABLED;
+            if !config.no_shell && shell::required(shell::Kind::Input(&command_line)) {
+                config.shell_enabled = true;
             } else {
-                flags &= u16::MAX ^ arguments::SHELL_ENABLED;
+                config.shell_enabled = false;
             }
 
-            let (start_time, end_time, exit_value, signal) = match command::get_command_output(&input, flags) {
-                Ok(child) => {
-                    handle_child(child, &self.output_tx, flags, job_id, input.clone(), has_timeout, self.timeout,
-                        &self.tempdir, &mut id_buffer)
-                },
-                Err(why) => {
-                    let mut stderr = stderr.lock();
-                    let _ = write!(&mut stderr, "parallel: command error: {}: {}\n", input, why);
-                    let message = format!("{}: {}: {}\n", job_id, input, why);
-                    let _ = self.output_tx.send(State::Error(job_id, message));
-                    (Timespec::new(0, 0), Timespec::new(0, 0), -1, 0)
+            if let Some(ref trace) = self.trace { phase_start = trace.span(job_id+1, Phase::Build, phase_start); }
+
+            capture.begin(job_id, &mut id_buffer);
+            let mut retry = 0;
+            let (start_time, end_time, exit_value, signal, usage, timed_out) = loop {
+                match command::get_command_output(&command_line, self.shell, self.wrapper, config, workdir) {
+                    Ok(child) => {
+                        if let Some(ref trace) = self.trace { phase_start = trace.span(job_id+1, Phase::Spawn, phase_start); }
+                        let pid = child.id();
+                        self.inputs.started(pid);
+                        let redirect = Redirect { append: retry != 0, stdout: stdout_file, stderr: stderr_file };
+                        let result = handle_child(child, config, job_id, has_timeout, timeout,
+                            self.stall_timeout, &redirect, &self.patterns, &mut capture);
+                        self.inputs.exited(pid);
+                        if let Some(ref trace) = self.trace { trace.span(job_id+1, Phase::Run, phase_start); }
+
+                        // A failed job will be retried until its retries are exhausted.
+                        if (result.2 != 0 || result.3 != 0) && retry < self.retries.retries {
+                            retry += 1;
+                            thread::sleep(self.retries.delay(retry));
+                            phase_start = Instant::now();
+                            continue
+                        }
+
+                        let _ = self.output_tx.send(State::Completed(job_id, self.input.clone(), capture.finish()));
+                        self.inputs.reported = true;
+                        break result
+                    },
+                    Err(why) => {
+                        let mut stderr = stderr.lock();
+                        let _ = write!(&mut stderr, "parallel: command error: {}: {}\n", self.input, why);
+                        let message = format!("{}: {}: {}\n", job_id, self.input, why);
+                        let _ = self.output_tx.send(State::Error(job_id, message));
+                        self.inputs.reported = true;
+                        break (Timespec::new(0, 0), Timespec::new(0, 0), -1, 0, Usage::default(), false)
+                    }
                 }
             };
 
-            if flags & JOBLOG != 0 {
+            self.inputs.report(exit_value, signal);
+
+            if config.joblog {
                 let runtime = end_time - start_time;
                 let _ = self.output_tx.send(State::JobLog(JobLog {
                     job_id:     job_id,
@@ -66,14 +176,48 @@ impl<IO: Read> ExecInputs<IO> {
                     runtime:    runtime.num_nanoseconds().unwrap_or(0) as u64,
                     exit_value: exit_value,
                     signal:     signal,
-                    flags:      flags,
-                    command:    input.clone(),
+                    usage:      usage,
+                    config:     config,
+                    command:    self.input.clone(),
                 }));
+                self.inputs.logged = true;
+            }
+
+            let runtime = (end_time - start_time).num_nanoseconds().unwrap_or(0) as u64;
+            summary::job_finished(job_id+1, exit_value, signal, timed_out, runtime, usage, &self.input);
+            if let (Some(template), true) = (self.on_fail, exit_value != 0 || signal != 0) {
+                notify::job_failed(template, &job, exit_value, signal);
+            }
+
+            if let Some(ref events) = self.events {
+                events.job_finished(job_id+1, &self.input, exit_value, signal, runtime);
+            }
+
+            if config.log_syslog {
+                syslog::job_finished(job_id+1, exit_value, signal, runtime, &self.input);
             }
 
-            if flags & arguments::VERBOSE_MODE != 0 {
-                verbose::task_complete(&stdout, job_id, self.num_inputs, &input);
+            if let Some(ref results) = self.results {
+                if let Err(why) = results.job_finished(job_id+1, &self.input, &self.input, exit_value, signal,
+                    start_time, runtime, usage)
+                {
+                    let _ = writeln!(stderr.lock(), "parallel: unable to write the results of job {}: {}", job_id+1, why);
+                }
+            }
+
+            if let Some(ref queue) = self.queue {
+                if let Err(why) = queue.job_finished(job_id, &self.input, &self.input, exit_value, signal,
+                    start_time, runtime, usage)
+                {
+                    let _ = writeln!(stderr.lock(), "parallel: unable to record job {} in the queue: {}", job_id+1, why);
+                }
             }
+
+            if config.verbose {
+                verbose::task_complete(&stdout, job_id, self.num_inputs, &self.input, usage);
+            }
+
+            phase_start = Instant::now();
         }
     }
 }
@@ -1,8 +1,129 @@
-use arguments::JOBLOG_8601;
+use arguments::Config;
 use numtoa::NumToA;
+use raw;
 use std::fs::File;
-use std::io::{Write, BufWriter};
+use std::io::{self, Write, BufWriter};
+use std::path::Path;
+use super::child::Usage;
+use sys_info;
 use time::{at, Timespec};
+use tokenizer::{self, TokenErr};
+
+/// A column of the job log, as given within the `--joblog-template` parameter.
+#[derive(Clone, Debug, PartialEq)]
+enum Field {
+    Text(String),
+    Seq,
+    Host,
+    StartTime,
+    Runtime,
+    ExitVal,
+    Signal,
+    MaxRss,
+    UserTime,
+    SysTime,
+    Command,
+}
+
+/// Defines which columns will be written to the job log, and in which order, such as
+/// `{seq}\t{host}\t{runtime}\t{exitval}\t{command}`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct JoblogTemplate {
+    fields: Vec<Field>,
+    host:   String,
+}
+
+impl JoblogTemplate {
+    /// Parses the template, where `\t` and `\n` are escaped tabs and newlines. Returns the token
+    /// that was not recognized if the template contains an invalid token.
+    pub fn parse(template: &str) -> Result<JoblogTemplate, String> {
+        // The tokens of the template borrow from it, and the template is only parsed once.
+        let template: &'static str = Box::leak(template.replace("\\t", "\t").replace("\\n", "\n").into_boxed_str());
+        // Every column is an unrecognized pattern to the tokenizer, so no input will be read.
+        let tokens = tokenizer::tokenize_spans(template, Path::new(""), 0).map_err(|why| match why {
+            TokenErr::Unclosed(position) => template[position..].to_owned(),
+            TokenErr::OutOfBounds(span, ..) | TokenErr::Streamed(span, ..) => {
+                template[span.start..span.end].to_owned()
+            },
+            TokenErr::File(_) => template.to_owned(),
+        })?;
+
+        let mut fields = Vec::new();
+        for (_, span) in tokens {
+            let token = &template[span.start..span.end];
+            if !token.starts_with('{') {
+                fields.push(Field::Text(token.to_owned()));
+                continue
+            }
+
+            fields.push(match &token[1..token.len()-1] {
+                "seq"       => Field::Seq,
+                "host"      => Field::Host,
+                "starttime" => Field::StartTime,
+                "runtime"   => Field::Runtime,
+                "exitval"   => Field::ExitVal,
+                "signal"    => Field::Signal,
+                "maxrss"    => Field::MaxRss,
+                "usertime"  => Field::UserTime,
+                "systime"   => Field::SysTime,
+                "command"   => Field::Command,
+                _           => return Err(token.to_owned())
+            });
+        }
+
+        // Jobs are executed on this host, so the host of each entry will be the same.
+        let host = if fields.contains(&Field::Host) { sys_info::hostname().unwrap_or_default() } else { String::new() };
+        Ok(JoblogTemplate { fields: fields, host: host })
+    }
+
+    /// Writes the header of the job log, where each column is named.
+    fn write_header<W: Write>(&self, output: &mut W, config: Config) -> io::Result<()> {
+        for field in &self.fields {
+            output.write_all(match *field {
+                Field::Text(ref text) => text.as_bytes(),
+                Field::Seq       => b"Sequence",
+                Field::Host      => b"Host",
+                Field::StartTime => if config.joblog_8601 { b"StartTime(ISO-8601)" } else { b"StartTime(s)" },
+                Field::Runtime   => b"Runtime(s)",
+                Field::ExitVal   => b"ExitVal",
+                Field::Signal    => b"Signal",
+                Field::MaxRss    => b"MaxRSS(KiB)",
+                Field::UserTime  => b"UserTime(s)",
+                Field::SysTime   => b"SysTime(s)",
+                Field::Command   => b"Command",
+            })?;
+        }
+        output.write_all(b"\n")
+    }
+
+    /// Writes the entry of a job, with the columns in the order that they were given.
+    fn write_entry<W: Write>(&self, output: &mut W, log: &JobLog) -> io::Result<()> {
+        for field in &self.fields {
+            match *field {
+                Field::Text(ref text) => output.write_all(text.as_bytes())?,
+                Field::Seq       => write!(output, "{}", log.job_id + 1)?,
+                Field::Host      => output.write_all(self.host.as_bytes())?,
+                Field::StartTime if log.config.joblog_8601 => {
+                    let tm = at(log.start_time);
+                    write!(output, "{}-{:02}-{:02} {:02}:{:02}:{:02}", 1900+tm.tm_year, 1+tm.tm_mon, tm.tm_mday,
+                        tm.tm_hour, tm.tm_min, tm.tm_sec)?;
+                },
+                Field::StartTime => write!(output, "{}.{:03}", log.start_time.sec, log.start_time.nsec / 1_000_000)?,
+                Field::Runtime   => write!(output, "{}.{:03}", log.runtime / 1_000_000_000,
+                    log.runtime % 1_000_000_000 / 1_000_000)?,
+                Field::ExitVal   => write!(output, "{}", log.exit_value)?,
+                Field::Signal    => write!(output, "{}", log.signal)?,
+                Field::MaxRss    => write!(output, "{}", log.usage.max_rss)?,
+                Field::UserTime  => write!(output, "{}.{:03}", log.usage.user / 1_000_000_000,
+                    log.usage.user % 1_000_000_000 / 1_000_000)?,
+                Field::SysTime   => write!(output, "{}.{:03}", log.usage.system / 1_000_000_000,
+                    log.usage.system % 1_000_000_000 / 1_000_000)?,
+                Field::Command   => output.write_all(&raw::encode(&log.command))?,
+            }
+        }
+        output.write_all(b"\n")
+    }
+}
 
 // Each `JobLog` consists of a single job's statistics ready to be written to the job log file.
 pub struct JobLog {
@@ -16,17 +137,29 @@ pub struct JobLog {
     pub exit_value: i32,
     /// The `signal` contains a non-zero value if the job was killed by a signal
     pub signal:     i32,
+    /// The peak memory and the CPU time that were consumed by the job
+    pub usage:      Usage,
     /// Contains the configuration parameters for the joblog
-    pub flags:      u16,
+    pub config:     Config,
     /// The actual `command` that was executed for this job
     pub command:    String
 }
 
 impl JobLog {
     /// Writes an individual job log to the job log file, efficiently.
-    pub fn write_entry(&self, joblog: &mut File, id_buffer: &mut [u8], pad: usize) {
+    /// The entry is written while holding a lock on the file, so that entries from other instances
+    /// of parallel which share the job log will not be interleaved.
+    /// If a template was given, the columns of the template will be written instead.
+    pub fn write_entry(&self, joblog: &mut File, id_buffer: &mut [u8], pad: usize, template: Option<&JoblogTemplate>) {
+        let lock = Lock::new(joblog);
+        let mut joblog = BufWriter::new(lock.file);
+        if let Some(template) = template {
+            let _ = template.write_entry(&mut joblog, self).and_then(|_| joblog.flush());
+            self.sync(lock.file);
+            return
+        }
+
         // 1: JobID
-        let mut joblog = BufWriter::new(joblog);
         let mut index = (self.job_id + 1).numtoa(10, id_buffer);
         let _ = joblog.write(&id_buffer[index..]);
         for _ in 0..pad - (20 - index) {
@@ -34,7 +167,7 @@ impl JobLog {
         }
 
         // 2: StartTime
-        if self.flags & JOBLOG_8601 != 0 {
+        if self.config.joblog_8601 {
             // ISO 8601 representation of the time
             let tm = at(self.start_time);
             let _ = write!(joblog, "{}-{:02}-{:02} {:02}:{:02}:{:02}  ", 1900+tm.tm_year, 1+tm.tm_mon,
@@ -96,21 +229,64 @@ impl JobLog {
         }
 
         // 5: Command
-        let _ = joblog.write(self.command.as_bytes());
+        let _ = joblog.write(&raw::encode(&self.command));
         let _ = joblog.write(b"\n");
+
+        // The entry must be written before the lock is released.
+        let _ = joblog.flush();
+        self.sync(lock.file);
+    }
+
+    /// Syncs the entry to the disk if the `--sync` parameter was passed, so that it will survive a power loss.
+    fn sync(&self, joblog: &File) {
+        if self.config.sync { let _ = joblog.sync_data(); }
+    }
+}
+
+/// Holds an exclusive advisory lock on a file until it is dropped.
+struct Lock<'a> {
+    file: &'a File,
+}
+
+impl<'a> Lock<'a> {
+    #[cfg(unix)]
+    fn new(file: &'a File) -> Lock<'a> {
+        use libc;
+        use std::os::unix::io::AsRawFd;
+        unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX); }
+        Lock { file: file }
+    }
+
+    /// Advisory locks are not supported on this platform.
+    #[cfg(not(unix))]
+    fn new(file: &'a File) -> Lock<'a> { Lock { file: file } }
+}
+
+#[cfg(unix)]
+impl<'a> Drop for Lock<'a> {
+    fn drop(&mut self) {
+        use libc;
+        use std::os::unix::io::AsRawFd;
+        unsafe { libc::flock(self.file.as_raw_fd(), libc::LOCK_UN); }
     }
 }
 
-/// Creates the column headers in the first line of the job log file
-pub fn create(file: &mut File, padding: usize, flags: u16) {
-    let mut joblog = BufWriter::new(file);
+/// Creates the column headers in the first line of the job log file, if the file is empty.
+pub fn create(file: &mut File, padding: usize, config: Config, template: Option<&JoblogTemplate>) {
+    let lock = Lock::new(file);
+    if lock.file.metadata().ok().map_or(true, |metadata| metadata.len() != 0) { return }
+    let mut joblog = BufWriter::new(lock.file);
+    if let Some(template) = template {
+        let _ = template.write_header(&mut joblog, config).and_then(|_| joblog.flush());
+        return
+    }
 
     // Sequence column is at least 10 chars long, counting space separator.
     let id_column_resize = if padding < 10 { 0 } else { padding - 10 };
     let _ = joblog.write(b"Sequence  ");
     for _ in 0..id_column_resize { let _ = joblog.write(b" "); }
 
-    if flags & JOBLOG_8601 != 0 {
+    if config.joblog_8601 {
         let _ = joblog.write(b"StartTime(ISO-8601)  ");
     } else {
         let _ = joblog.write(b"StartTime(s)    ");
@@ -119,4 +295,36 @@ pub fn create(file: &mut File, padding: usize, flags: u16) {
 
     // Remaining columns, with the runtim column left-padded.
     let _ = joblog.write(b"Runtime(s)  ExitVal  Signal  Command\n");
+    let _ = joblog.flush();
+}
+
+#[test]
+fn joblog_templates() {
+    let template = JoblogTemplate::parse("{seq}\\t{exitval}: {command}").unwrap();
+    let log = JobLog {
+        job_id: 4, start_time: Timespec::new(0, 0), runtime: 1_500_000_000, exit_value: 1, signal: 0,
+        usage: Usage { max_rss: 2048, user: 250_000_000, system: 5_000_000 }, config: Config::default(),
+        command: String::from("false")
+    };
+
+    let mut output = Vec::new();
+    template.write_header(&mut output, Config::default()).unwrap();
+    template.write_entry(&mut output, &log).unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(), "Sequence\tExitVal: Command\n5\t1: false\n");
+
+    let template = JoblogTemplate::parse("{runtime} {starttime}").unwrap();
+    let mut output = Vec::new();
+    template.write_entry(&mut output, &log).unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(), "1.500 0.000\n");
+
+    let template = JoblogTemplate::parse("{maxrss} {usertime} {systime}").unwrap();
+    let mut output = Vec::new();
+    template.write_header(&mut output, Config::default()).unwrap();
+    template.write_entry(&mut output, &log).unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(), "MaxRSS(KiB) UserTime(s) SysTime(s)\n2048 0.250 0.005\n");
+
+    assert_eq!(JoblogTemplate::parse("{seq} {exit}"), Err(String::from("{exit}")));
+    assert_eq!(JoblogTemplate::parse("{seq"), Err(String::from("{seq")));
+    assert_eq!(JoblogTemplate::parse("{seq} {#}"), Err(String::from("{#}")));
+    assert_eq!(JoblogTemplate::parse("{1} {seq}"), Err(String::from("{1}")));
 }
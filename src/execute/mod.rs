@@ -6,11 +6,26 @@ mod exec_inputs;
 mod job_log;
 mod signals;
 mod receive;
+mod record_file;
+mod recovery;
+mod retry;
+mod scan;
+mod timestamp;
+mod window;
 
 pub mod command;
+pub mod container;
 pub mod pipe;
 
+pub use self::child::Usage;
 pub use self::dry::dry_run;
 pub use self::exec_commands::ExecCommands;
 pub use self::exec_inputs::ExecInputs;
+pub use self::job_log::JoblogTemplate;
 pub use self::receive::receive_messages;
+pub use self::record_file::RecordFile;
+pub use self::recovery::recover;
+pub use self::retry::Retries;
+pub use self::scan::Patterns;
+pub use self::timestamp::Timestamp;
+pub use self::window::{ResultSender, Window};
@@ -1,38 +1,307 @@
 pub mod disk {
-    use std::fs::File;
-    use std::io::{Read, Write};
+    use std::fs::{self, File, OpenOptions};
+    use std::io::{self, Read, Write};
+    use std::mem;
+    use std::path::Path;
     use std::process::Child;
-    use std::sync::mpsc::Sender;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use std::time::{Duration, Instant};
     use filepaths;
     use super::super::job_log::JobLog;
+    use super::super::scan::{Matches, Patterns, Scanner};
+    use super::super::timestamp::Timestamp;
+    use time;
+
+    /// The number of bytes that each output of a job may write before it is spilled to the job's file.
+    const MEMORY_LIMIT: usize = 64 * 1024;
 
     /// When using grouped mode, the `State` will tell the program whether the program is still
     /// processing, or if it has completed.
     pub enum State {
-        /// The integer supplied with this signal tells the program which process has finished.
-        Completed(usize, String),
+        /// The integer supplied with this signal tells the program which process has finished,
+        /// along with the outputs of the job that were kept in memory.
+        Completed(usize, String, Captured),
         /// An error occurred, so the error will be marked.
         Error(usize, String),
         /// (job_id, start_time, runtime, exit_value, signal, command)
         JobLog(JobLog),
     }
 
-    /// Sends messages received by a `Child` process's standard output and error and sends them
-    /// to be handled by the grouped output channel.
-    pub fn output(child: &mut Child, job_id: usize, name: String, output_tx: &Sender<State>, quiet: bool,
-        base: &str, buffer: &mut [u8])
+    /// Records when a job last wrote to its standard output or error, so that a job whose outputs have
+    /// stalled may be killed with the `--stall-timeout` parameter.
+    pub struct Activity {
+        start: Instant,
+        /// The number of milliseconds since `start` at which the job last wrote a message.
+        last:  AtomicUsize,
+    }
+
+    impl Activity {
+        pub fn new() -> Activity {
+            Activity { start: Instant::now(), last: AtomicUsize::new(0) }
+        }
+
+        /// Records that the job has written a message.
+        fn touch(&self) {
+            let elapsed = self.start.elapsed();
+            let millis = elapsed.as_secs() as usize * 1000 + elapsed.subsec_nanos() as usize / 1_000_000;
+            self.last.store(millis, Ordering::SeqCst);
+        }
+
+        /// The length of time since the job last wrote a message, or since it was started.
+        pub fn idle(&self) -> Duration {
+            self.start.elapsed() - Duration::from_millis(self.last.load(Ordering::SeqCst) as u64)
+        }
+    }
+
+    /// Determines where the standard output and error of a job will be written.
+    pub struct Redirect<'a> {
+        /// When a job is retried, the messages of each attempt are appended to the files.
+        pub append: bool,
+        /// The file that the standard output will be written to, given by the `--output` parameter.
+        pub stdout: Option<&'a Path>,
+        /// The file that the standard error will be written to, given by the `--error-output` parameter.
+        pub stderr: Option<&'a Path>,
+    }
+
+    /// One of the outputs of a job, which is kept in memory until it exceeds `MEMORY_LIMIT` bytes, at which
+    /// point it is spilled to the job's file. Most jobs write little, so most jobs never need files of their own.
+    struct Output {
+        path:     String,
+        memory:   Vec<u8>,
+        file:     Option<File>,
+        /// Once the tempdir is full, the remainder of the output is written to this file within the
+        /// fallback directory, if it could be created.
+        overflow: Option<(String, Option<File>)>,
+        /// The file that the output is redirected to by the `--output` or `--error-output` parameter.
+        redirect: Option<File>,
+        /// Each line is prefixed with the time that it was read, as given by the `--timestamp` parameter.
+        timestamp: Option<Timestamp>,
+        /// Whether the next data that is read begins a new line.
+        line_start: bool,
+    }
+
+    impl Output {
+        fn new(path: String, timestamp: Option<Timestamp>) -> Output {
+            Output { path: path, memory: Vec::new(), file: None, overflow: None, redirect: None, timestamp: timestamp,
+                line_start: true }
+        }
+
+        fn write(&mut self, data: &[u8]) {
+            let stamped;
+            let data = match self.timestamp {
+                Some(ref timestamp) => {
+                    stamped = timestamp.stamp(data, &mut self.line_start, &time::now());
+                    &stamped[..]
+                },
+                None => data
+            };
+
+            if let Some(ref mut file) = self.redirect {
+                let _ = file.write_all(data);
+                return
+            }
+
+            if self.file.is_none() && self.overflow.is_none() {
+                if self.memory.len() + data.len() <= MEMORY_LIMIT {
+                    self.memory.extend_from_slice(data);
+                    return
+                }
+
+                match File::create(&self.path) {
+                    Ok(file) => self.file = Some(file),
+                    Err(ref why) if filepaths::is_full(why) => (),
+                    Err(why) => panic!("unable to create job output file: {}", why)
+                }
+
+                let memory = mem::replace(&mut self.memory, Vec::new());
+                self.spill(&memory);
+            }
+
+            self.spill(data);
+        }
+
+        /// Writes to the job's file, continuing within a file in the fallback directory once the tempdir is full.
+        fn spill(&mut self, data: &[u8]) {
+            let data = match (self.overflow.is_none(), self.file.as_mut()) {
+                (true, Some(file)) => match write_until_full(file, data) {
+                    Some(written) => &data[written..],
+                    None => return
+                },
+                _ => data
+            };
+
+            if self.overflow.is_none() {
+                self.overflow = Some(match filepaths::overflow(&self.path) {
+                    Ok((path, file)) => (path, Some(file)),
+                    Err(why) => {
+                        let stderr = io::stderr();
+                        let _ = writeln!(stderr.lock(), "parallel: unable to write job output file {:?} after the \
+                            tempdir became full: {}", self.path, why);
+                        (String::new(), None)
+                    }
+                });
+            }
+
+            if let Some((_, Some(ref mut file))) = self.overflow { let _ = file.write_all(data); }
+        }
+
+        /// The output, if it was kept in memory, or `None` if it was spilled to the job's file, along with
+        /// the file which the output was continued within if the tempdir became full.
+        fn finish(&mut self) -> (Option<Vec<u8>>, Option<String>) {
+            self.redirect = None;
+            self.line_start = true;
+            let memory = mem::replace(&mut self.memory, Vec::new());
+            let overflow = self.overflow.take().map(|(path, _)| path);
+            if self.file.take().is_some() || overflow.is_some() { (None, overflow) } else { (Some(memory), None) }
+        }
+    }
+
+    /// Writes as much of the data to the file as will fit, returning the number of bytes that were written
+    /// if the disk became full. Other errors are ignored, as the output of a job may not be recovered.
+    fn write_until_full(file: &mut File, data: &[u8]) -> Option<usize> {
+        let mut written = 0;
+        while written < data.len() {
+            match file.write(&data[written..]) {
+                Ok(0) => return None,
+                Ok(bytes) => written += bytes,
+                Err(ref why) if why.kind() == io::ErrorKind::Interrupted => (),
+                Err(ref why) if filepaths::is_full(why) => return Some(written),
+                Err(_) => return None
+            }
+        }
+        None
+    }
+
+    /// Captures the standard output and error of each job that a worker executes, across every attempt of
+    /// the job. The paths of the job's files, and the buffers that outputs are read into, are reused by each job.
+    pub struct Capture {
+        stdout:        Output,
+        stderr:        Output,
+        stdout_buffer: Vec<u8>,
+        stderr_buffer: Vec<u8>,
+        /// The length of the paths of the job's files, without the job's ID.
+        truncate:      usize,
+    }
+
+    impl Capture {
+        /// Creates the capture of a worker, whose outputs are read through buffers of the given size,
+        /// as given by the `--buffer-size` parameter, and whose lines may be prefixed with a `timestamp`.
+        pub fn new(base: &str, buffer_size: usize, timestamp: Option<Timestamp>) -> Capture {
+            let (truncate, stdout_path, stderr_path) = filepaths::new_job(base, 0, &mut [0u8; 20]);
+            Capture {
+                stdout:        Output::new(stdout_path, timestamp.clone()),
+                stderr:        Output::new(stderr_path, timestamp),
+                stdout_buffer: vec![0u8; buffer_size],
+                stderr_buffer: vec![0u8; buffer_size],
+                truncate:      truncate,
+            }
+        }
+
+        /// Prepares to capture the outputs of the given job.
+        pub fn begin(&mut self, job_id: usize, buffer: &mut [u8]) {
+            filepaths::next_job_path(job_id, self.truncate, buffer, &mut self.stdout.path, &mut self.stderr.path);
+        }
+
+        /// Opens the files that the outputs of the next attempt are redirected to, if they were redirected.
+        fn redirect(&mut self, redirect: &Redirect) {
+            let append = redirect.append;
+            let open = |path: &Path| {
+                let opened = match path.parent() {
+                    Some(parent) if !parent.as_os_str().is_empty() => fs::create_dir_all(parent),
+                    _ => Ok(())
+                }.and_then(|_| OpenOptions::new().write(true).create(true).truncate(!append).append(append).open(path));
+
+                opened.map_err(|why| {
+                    let stderr = io::stderr();
+                    let _ = writeln!(stderr.lock(), "parallel: unable to open output file {:?}: {}", path, why);
+                }).ok()
+            };
+
+            self.stdout.redirect = redirect.stdout.and_then(&open);
+            self.stderr.redirect = redirect.stderr.and_then(&open);
+        }
+
+        /// The outputs of the job, once every attempt has completed.
+        pub fn finish(&mut self) -> Captured {
+            let (stdout, stdout_overflow) = self.stdout.finish();
+            let (stderr, stderr_overflow) = self.stderr.finish();
+            Captured { stdout: stdout, stderr: stderr, stdout_overflow: stdout_overflow,
+                stderr_overflow: stderr_overflow }
+        }
+    }
+
+    /// The outputs of a completed job, where an output which was spilled to the job's file is `None`.
+    pub struct Captured {
+        pub stdout: Option<Vec<u8>>,
+        pub stderr: Option<Vec<u8>>,
+        /// The files within the fallback directory that the outputs were continued within, once the tempdir
+        /// became full.
+        pub stdout_overflow: Option<String>,
+        pub stderr_overflow: Option<String>,
+    }
+
+    /// Copies messages from the source to the output until the source is exhausted, recording the activity,
+    /// and returns the patterns that were found by the scanner.
+    fn copy<R: Read>(source: &mut R, output: &mut Output, buffer: &mut [u8], activity: &Activity,
+        mut scanner: Scanner) -> Matches
+    {
+        while let Ok(bytes_read) = source.read(buffer) {
+            if bytes_read == 0 { break }
+            activity.touch();
+            scanner.scan(&buffer[0..bytes_read]);
+            output.write(&buffer[0..bytes_read]);
+        }
+        scanner.finish()
+    }
+
+    /// Captures messages from the standard output and error of a `Child` process, as with `output`, while
+    /// recording the activity of the job. Standard error is read on another thread, so that messages written
+    /// to either are recorded as they are written.
+    pub fn output_watched(child: &mut Child, quiet: bool, redirect: &Redirect, capture: &mut Capture,
+        activity: &Activity, patterns: &Patterns, job_id: usize) -> Matches
+    {
+        capture.redirect(redirect);
+        let Capture {
+            stdout: ref mut stdout_output, stderr: ref mut stderr_output,
+            ref mut stdout_buffer, ref mut stderr_buffer, ..
+        } = *capture;
+        let mut stderr = child.stderr.take().expect("unable to open stderr of child");
+        let stderr_scanner = patterns.scanner(job_id);
+        if quiet {
+            copy(&mut stderr, stderr_output, stderr_buffer, activity, stderr_scanner)
+        } else {
+            let stdout = child.stdout.as_mut().expect("unable to open stdout of child");
+            thread::scope(|scope| {
+                let stderr_thread = scope.spawn(move || {
+                    copy(&mut stderr, stderr_output, stderr_buffer, activity, stderr_scanner)
+                });
+                let matches = copy(stdout, stdout_output, stdout_buffer, activity, patterns.scanner(job_id));
+                matches.merge(stderr_thread.join().unwrap_or_default())
+            })
+        }
+    }
+
+    /// Captures messages received by a `Child` process's standard output and error, to be handled by the
+    /// grouped output channel once the job has completed, unless they were redirected. Each line of the
+    /// outputs is also checked against the `patterns` as it is received, and the patterns that were found
+    /// are returned.
+    pub fn output(child: &mut Child, quiet: bool, redirect: &Redirect, capture: &mut Capture, patterns: &Patterns,
+        job_id: usize) -> Matches
     {
-        let (_, stdout_path, stderr_path) = filepaths::new_job(base, job_id, buffer);
-        let mut stdout_file = File::create(stdout_path).expect("unable to create job stdout file");
-        let mut stderr_file = File::create(stderr_path).expect("unable to create job stderr file");
+        capture.redirect(redirect);
+        let Capture { stdout: ref mut stdout_output, stderr: ref mut stderr_output, stdout_buffer: ref mut membuffer, .. }
+            = *capture;
+        let mut stdout_scanner = patterns.scanner(job_id);
+        let mut stderr_scanner = patterns.scanner(job_id);
 
         let stderr = child.stderr.as_mut().expect("unable to open stderr of child");
-        let mut membuffer = [0u8; 8 * 1024];
         if quiet {
             // Only pipe messages from standard error when quiet mode is enabled.
             while let Ok(bytes_read) = stderr.read(&mut membuffer[..]) {
                 if bytes_read != 0 {
-                    let _ = stderr_file.write(&membuffer[0..bytes_read]);
+                    stderr_scanner.scan(&membuffer[0..bytes_read]);
+                    stderr_output.write(&membuffer[0..bytes_read]);
                 } else {
                     break
                 }
@@ -44,17 +313,20 @@ pub mod disk {
             loop {
                 if let Ok(bytes_read) = stdout.read(&mut membuffer[..]) {
                     if bytes_read != 0 {
-                        let _ = stdout_file.write(&membuffer[0..bytes_read]);
+                        stdout_scanner.scan(&membuffer[0..bytes_read]);
+                        stdout_output.write(&membuffer[0..bytes_read]);
                     } else if let Ok(bytes_read) = stderr.read(&mut membuffer[..]) {
                         if bytes_read != 0 {
-                            let _ = stderr_file.write(&membuffer[0..bytes_read]);
+                            stderr_scanner.scan(&membuffer[0..bytes_read]);
+                            stderr_output.write(&membuffer[0..bytes_read]);
                         } else {
                             break
                         }
                     }
                 } else if let Ok(bytes_read) = stderr.read(&mut membuffer[..]) {
                     if bytes_read != 0 {
-                        let _ = stderr_file.write(&membuffer[0..bytes_read]);
+                        stderr_scanner.scan(&membuffer[0..bytes_read]);
+                        stderr_output.write(&membuffer[0..bytes_read]);
                     } else {
                         break
                     }
@@ -64,7 +336,6 @@ pub mod disk {
             }
         }
 
-        // Signal to the channel that the job has completed.
-        let _ = output_tx.send(State::Completed(job_id, name));
+        stdout_scanner.finish().merge(stderr_scanner.finish())
     }
 }
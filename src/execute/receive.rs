@@ -1,70 +1,84 @@
+use std::cmp;
 use std::fs::{self, File};
-use std::io::{self, Write, Read, BufWriter};
-use std::path::Path;
-use std::sync::mpsc::Receiver;
+use std::io::{self, IsTerminal, Write, Read};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, TryRecvError};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use arguments::Args;
+use checkpoint::Checkpoint;
 use filepaths;
+use input_iterator::UNKNOWN_TOTAL;
 use misc::Digits;
+use running;
 use super::job_log::{self, JobLog};
 use super::pipe::disk::State;
+use super::record_file::RecordFile;
+use super::window::Window;
 use smallvec::SmallVec;
+use time;
+use trace::{Phase, TraceLog};
 
-/// Reads the standard output and error files of the current unit, writing them to the standard output/error.
-macro_rules! read_outputs {
-    ($stdout:ident, $stderr:ident, $buffer:ident, $stdout_out:ident, $stderr_out:ident) => {
-        let mut bytes_read = $stdout.read(&mut $buffer).unwrap_or(0);
-        while bytes_read != 0 {
-            if let Err(why) = $stdout_out.write(&$buffer[0..bytes_read]) {
-                let _ = write!($stderr_out, "parallel: I/O error: unable to write to standard output: {}\n", why);
-            }
-            bytes_read = $stdout.read(&mut $buffer).unwrap_or(0);
+/// The minimum number of nanoseconds between each save of the `--checkpoint` file.
+const CHECKPOINT_INTERVAL: u64 = 1_000_000_000;
+
+/// Writes the standard output and error of the current job, which were either kept in memory, or spilled to
+/// the job's files, from where they were tailed to if the files were being tailed. The files are kept if `$keep`.
+macro_rules! replay_outputs {
+    ($captured:expr, $tailed:expr, $stdout_path:ident, $stderr_path:ident, $buffer:ident, $stdout_out:ident,
+        $stderr_out:ident, $tint:ident, $job:expr, $keep:expr) => {{
+        let (stdout_file, stderr_file) = $tailed;
+        let stdout_data = $captured.stdout.as_ref().map(|data| data.as_slice());
+        let stdout_overflow = $captured.stdout_overflow.as_ref().map(|path| path.as_str());
+        if let Err(why) = replay(stdout_data, stdout_file, &$stdout_path, stdout_overflow, $keep, &mut $buffer,
+            |data| $tint.stdout(&mut $stdout_out, $job, data))
+        {
+            let _ = write!($stderr_out, "parallel: I/O error: unable to write standard output of job: {}\n", why);
         }
 
-        bytes_read = $stderr.read(&mut $buffer).unwrap_or(0);
-        while bytes_read != 0 {
-            if let Err(why) = $stderr_out.write(&$buffer[0..bytes_read]) {
-                let _ = write!($stderr_out, "parallel: I/O error: unable to write to standard error: {}\n", why);
-            }
-            bytes_read = $stderr.read(&mut $buffer).unwrap_or(0);
+        let stderr_data = $captured.stderr.as_ref().map(|data| data.as_slice());
+        let stderr_overflow = $captured.stderr_overflow.as_ref().map(|path| path.as_str());
+        if let Err(why) = replay(stderr_data, stderr_file, &$stderr_path, stderr_overflow, $keep, &mut $buffer,
+            |data| $tint.stderr(&mut $stderr_out, $job, data))
+        {
+            let _ = write!($stderr_out, "parallel: I/O error: unable to write standard error of job: {}\n", why);
         }
-    }
+    }}
 }
 
-/// Removes both the standard output and error file of the current job
-macro_rules! remove_job_files {
-    ($stdout_path:ident, $stderr_path:ident, $stderr:ident) => {{
-        if let Err(why) = fs::remove_file(&$stdout_path).and_then(|_| fs::remove_file(&$stderr_path)) {
-            let _ = write!($stderr, "parallel: I/O error: unable to remove job files: {}\n", why);
+/// Append the current job to the processed file
+macro_rules! append_to_processed {
+    ($processed:ident, $job:expr, $input:ident, $stderr:ident) => {{
+        if let Err(why) = $processed.append($job + 1, $input) {
+            let _ = write!($stderr, "parallel: I/O error: unable to append to processed: {}\n", why);
         }
     }}
 }
 
-/// Opens the standard output and error files of the next job, attempting repeatedly until a success.
-macro_rules! open_job_files {
-    ($stdout_path:ident, $stderr_path:ident) => {{
-        let stdout_file = loop {
-            if let Ok(file) = File::open(&$stdout_path) { break file }
-            thread::sleep(Duration::from_millis(100));
-        };
+/// The colors that jobs will cycle through, as ANSI SGR color codes.
+const COLORS: [u8; 10] = [32, 33, 34, 35, 36, 92, 93, 94, 95, 96];
 
-        let stderr_file = loop {
-            if let Ok(file) = File::open(&$stderr_path) { break file }
-            thread::sleep(Duration::from_millis(100));
-        };
+/// Tints the outputs of each job with a stable color that is chosen by the job's ID, where the standard
+/// error of a job is written in bold. Outputs are only tinted when written to a terminal.
+struct Tint {
+    stdout: bool,
+    stderr: bool,
+}
 
-        (stdout_file, stderr_file)
-    }}
+impl Tint {
+    fn stdout<W: Write>(&self, output: &mut W, job: usize, data: &[u8]) -> io::Result<()> {
+        if self.stdout { tinted(output, job, false, data) } else { output.write_all(data) }
+    }
+
+    fn stderr<W: Write>(&self, output: &mut W, job: usize, data: &[u8]) -> io::Result<()> {
+        if self.stderr { tinted(output, job, true, data) } else { output.write_all(data) }
+    }
 }
 
-/// Append the current job to the processed file
-macro_rules! append_to_processed {
-    ($processed:ident, $input:ident, $stderr:ident) => {{
-        if let Err(why) = $processed.write($input.as_bytes()).and_then(|_| $processed.write(b"\n")) {
-            let _ = write!($stderr, "parallel: I/O error: unable to append to processed: {}\n", why);
-        }
-    }}
+fn tinted<W: Write>(output: &mut W, job: usize, bold: bool, data: &[u8]) -> io::Result<()> {
+    write!(output, "\x1b[{}{}m", if bold { "1;" } else { "" }, COLORS[job % COLORS.len()])?;
+    output.write_all(data)?;
+    output.write_all(b"\x1b[0m")
 }
 
 #[allow(cyclomatic_complexity)]
@@ -72,6 +86,9 @@ macro_rules! append_to_processed {
 pub fn receive_messages (
     input_rx: Receiver<State>,
     args: Args,
+    resume: Checkpoint,
+    trace: Option<&TraceLog>,
+    window: &Window,
     base: &str,
     processed_path: &Path,
     errors_path: &Path
@@ -79,12 +96,13 @@ pub fn receive_messages (
     let stdout = io::stdout();
     let stderr = io::stderr();
 
-    // Store the flags value outside of the `args` structure
-    let flags = args.flags;
+    // Store the config value outside of the `args` structure
+    let config = args.config;
     // Keeps track of which job is currently allowed to print to standard output/error.
-    let mut counter = 0;
+    // If a checkpoint is being resumed, the jobs that it recorded as completed will have been skipped.
+    let mut counter = resume.completed;
     // Keep a record of how many errors have occurred.
-    let mut error_count = 0;
+    let mut error_count = cmp::min(resume.failed, 254) as i32;
     // In the event that the joblog parameter was passed, a counter will be needed for jobs.
     let mut job_counter = args.ninputs;
     // The following `buffer` is used to store completed jobs that are awaiting processing.
@@ -95,58 +113,87 @@ pub fn receive_messages (
     let mut drop = SmallVec::<[usize; 32]>::new();
     // Similar to the above for for `JobLog` events.
     let mut job_drop = SmallVec::<[usize; 32]>::new();
-    // An opened disk buffer pointing to the processed file.
-    let processed_file = fs::OpenOptions::new().create(true).write(true).open(processed_path).unwrap();
-    let mut processed_file = BufWriter::new(processed_file);
+    // An opened disk buffer pointing to the processed file, which is synced after each entry if `--sync` was passed.
+    // The inputs of the jobs that were completed by a run that is being resumed will remain within the file.
+    let processed_file = RecordFile::create(processed_path, resume.completed, args.buffer_size, config.sync).unwrap();
     // An opened disk buffer pointing to the error file.
-    let error_file = fs::OpenOptions::new().truncate(true).create(true).write(true).open(errors_path).unwrap();
-    let mut error_file = BufWriter::new(error_file);
+    let error_file = RecordFile::create(errors_path, 0, args.buffer_size, false).unwrap();
+    // If the run is interrupted, the records that have been buffered will be written before exiting.
+    {
+        let (processed_file, error_file) = (processed_file.clone(), error_file.clone());
+        running::on_interrupt(move || {
+            let _ = processed_file.commit();
+            let _ = error_file.commit();
+        });
+    }
     // Obtaining the number of digits in the total number of inputs is required for padding purposes.
-    let mut id_pad_length = args.ninputs.digits();
+    let mut id_pad_length = if args.ninputs == UNKNOWN_TOTAL { 10 } else { args.ninputs.digits() };
+    // If the `--color` parameter was passed, outputs written to a terminal will be tinted by job.
+    let tint = Tint {
+        stdout: config.color && stdout.is_terminal(),
+        stderr: config.color && stderr.is_terminal(),
+    };
     // A buffer for buffering the outputs of temporary files on disk.
-    let mut read_buffer = [0u8; 8192];
+    let mut read_buffer = vec![0u8; args.buffer_size];
     // A buffer for converting job ID's into a byte array representation of a string.
     let mut id_buffer = [0u8; 20];
     // Generates the stdout and stderr paths, along with a truncation value to truncate the job ID from the paths.
     let (truncate_size, mut stdout_path, mut stderr_path) = filepaths::new_job(base, counter, &mut id_buffer);
+    // If the joblog-template parameter was passed, the job log will be written with the given columns.
+    let joblog_template = args.joblog_template;
     // If the joblog parameter was passed, open the file for writing.
     let mut joblog = args.joblog.map(|path| {
-        job_counter = 0;
+        job_counter = resume.completed;
         if id_pad_length < 10 { id_pad_length = 10; }
-        let _ = fs::remove_file(&path);
-        let mut file = fs::OpenOptions::new().create(true).write(true).open(path).unwrap();
-        job_log::create(&mut file, id_pad_length, flags);
+        // The job log is always appended to, as it may be shared by other instances of parallel.
+        let mut file = fs::OpenOptions::new().create(true).append(true).open(path).unwrap();
+        // Jobs that completed after the checkpoint was saved will be logged again when they are rerun, and
+        // as the latest entry of a command is the one that is read back, their earlier entries are left intact.
+        job_log::create(&mut file, id_pad_length, config, joblog_template.as_ref());
         file
     });
+    // If the checkpoint parameter was passed, the state of the run will periodically be saved to this file.
+    let checkpoint = args.checkpoint.map(PathBuf::from);
+    let mut last_checkpoint = time::precise_time_ns();
 
     // The loop will only quit once all inputs have been processed
     while counter < args.ninputs || job_counter < args.ninputs {
+        // Workers may only report the results of jobs which are within the window of the earliest unwritten job.
+        window.release(if joblog.is_some() { cmp::min(counter, job_counter) } else { counter });
+
         // Tracks whether the next file in the queue should be trailed.
         let mut tail_next = false;
 
         // First receive the next input signal from the running jobs
-        match input_rx.recv().unwrap() {
+        // When inputs are streamed, the total is unknown, so messages are received until every job has exited.
+        let message = match input_rx.recv() {
+            Ok(message) => message,
+            Err(_)      => break
+        };
+
+        match message {
             // If the job's id matches the current counter, there's no need to buffer it -- print immediately
-            State::Completed(id, ref name) if id == counter => {
+            State::Completed(id, ref name, ref captured) if id == counter => {
                 let mut stdout = stdout.lock();
                 let mut stderr = stderr.lock();
                 filepaths::next_job_path(counter, truncate_size, &mut id_buffer, &mut stdout_path, &mut stderr_path);
-                let (mut stdout_file, mut stderr_file) = open_job_files!(stdout_path, stderr_path);
-                append_to_processed!(processed_file, name, stderr);
-                read_outputs!(stdout_file, stderr_file, read_buffer, stdout, stderr);
-                remove_job_files!(stdout_path, stderr_path, stderr);
+                let replay_start = Instant::now();
+                append_to_processed!(processed_file, counter, name, stderr);
+                replay_outputs!(captured, (None, None), stdout_path, stderr_path, read_buffer, stdout, stderr, tint,
+                    counter, config.keep_temp);
+                if let Some(trace) = trace { trace.span(counter+1, Phase::Replay, replay_start); }
                 counter += 1;
             },
             // Otherwise, add the job to the job complete buffer and mark the current job for trailing
-            State::Completed(id, name) => {
-                buffer.push(State::Completed(id, name));
+            State::Completed(id, name, captured) => {
+                buffer.push(State::Completed(id, name, captured));
                 tail_next = true;
             },
             // If an error occured and the id matches the counter, print the error immediately.
             State::Error(id, ref message) if id == counter => {
                 counter += 1;
                 if error_count != 254 { error_count += 1; }
-                if let Err(why) = error_file.write(message.as_bytes()) {
+                if let Err(why) = error_file.append(id + 1, message.trim_end_matches('\n')) {
                     let mut stderr = stderr.lock();
                     let _ = write!(stderr, "parallel: I/O error: {}", why);
                 }
@@ -161,7 +208,7 @@ pub fn receive_messages (
             State::JobLog(ref data) if data.job_id == job_counter => {
                 job_counter += 1;
                 let mut joblog = joblog.as_mut().unwrap();
-                data.write_entry(&mut joblog, &mut id_buffer, id_pad_length);
+                data.write_entry(&mut joblog, &mut id_buffer, id_pad_length, joblog_template.as_ref());
             },
             // Otherwise, add it to the job buffer.
             State::JobLog(data) => job_buffer.push(data),
@@ -169,29 +216,36 @@ pub fn receive_messages (
 
         // If the received job ID doesn't match the ID that we wanted, we should trail the current job's files
         // and print new messages as they come available, until the completion signal has been received.
+        // Outputs are only written to files once they have been spilled from memory, so the files are opened
+        // once they exist.
         if tail_next {
             filepaths::next_job_path(counter, truncate_size, &mut id_buffer, &mut stdout_path, &mut stderr_path);
-            let (mut stdout_file, mut stderr_file) = open_job_files!(stdout_path, stderr_path);
+            let mut stdout_file: Option<File> = None;
+            let mut stderr_file: Option<File> = None;
 
             loop {
+                window.release(if joblog.is_some() { cmp::min(counter, job_counter) } else { counter });
                 // If no message is received then tail the file, else handle the message
                 match input_rx.try_recv() {
                     // When the completion signal is received, print remaining messages and break the loop
-                    Ok(State::Completed(id, ref name)) if id == counter => {
+                    Ok(State::Completed(id, ref name, ref captured)) if id == counter => {
+                        // Outputs were replayed while the job was running, so only the remainder is timed.
+                        let replay_start = Instant::now();
                         let mut stdout = stdout.lock();
                         let mut stderr = stderr.lock();
-                        append_to_processed!(processed_file, name, stderr);
-                        read_outputs!(stdout_file, stderr_file, read_buffer, stdout, stderr);
-                        remove_job_files!(stdout_path, stderr_path, stderr);
+                        append_to_processed!(processed_file, counter, name, stderr);
+                        replay_outputs!(captured, (stdout_file.take(), stderr_file.take()), stdout_path, stderr_path,
+                            read_buffer, stdout, stderr, tint, counter, config.keep_temp);
+                        if let Some(trace) = trace { trace.span(counter+1, Phase::Replay, replay_start); }
                         counter += 1;
                         break
                     },
                     // We are only concerned about the current job ID
-                    Ok(State::Completed(id, name)) => buffer.push(State::Completed(id, name)),
+                    Ok(State::Completed(id, name, captured)) => buffer.push(State::Completed(id, name, captured)),
                     // If an error occured, print the error and break
                     Ok(State::Error(id, ref message)) if id == counter => {
                         counter += 1;
-                        if let Err(why) = error_file.write(message.as_bytes()) {
+                        if let Err(why) = error_file.append(id + 1, message.trim_end_matches('\n')) {
                             let mut stderr = stderr.lock();
                             let _ = write!(stderr, "parallel: I/O error: {}", why);
                         }
@@ -203,19 +257,27 @@ pub fn receive_messages (
                     Ok(State::JobLog(ref data)) if data.job_id == job_counter => {
                         job_counter += 1;
                         let mut joblog = joblog.as_mut().unwrap();
-                        data.write_entry(&mut joblog, &mut id_buffer, id_pad_length);
+                        data.write_entry(&mut joblog, &mut id_buffer, id_pad_length, joblog_template.as_ref());
                     },
                     // Otherwise, add it to the job buffer.
                     Ok(State::JobLog(data)) => job_buffer.push(data),
+                    // All jobs have exited, so there are no more messages to receive.
+                    Err(TryRecvError::Disconnected) => break,
                     // Tail the file and wait a specified time before checking for the next message
-                    _ => {
+                    Err(TryRecvError::Empty) => {
                         let mut stdout = stdout.lock();
                         let mut stderr = stderr.lock();
-                        let mut bytes_read = stdout_file.read(&mut read_buffer).unwrap();
-                        if bytes_read != 0 { stdout.write_all(&read_buffer[0..bytes_read]).unwrap(); }
+                        if stdout_file.is_none() { stdout_file = File::open(&stdout_path).ok(); }
+                        if let Some(ref mut file) = stdout_file {
+                            let bytes_read = file.read(&mut read_buffer).unwrap();
+                            if bytes_read != 0 { tint.stdout(&mut stdout, counter, &read_buffer[0..bytes_read]).unwrap(); }
+                        }
 
-                        bytes_read = stderr_file.read(&mut read_buffer).unwrap();
-                        if bytes_read != 0 { stderr.write_all(&read_buffer[0..bytes_read]).unwrap(); }
+                        if stderr_file.is_none() { stderr_file = File::open(&stderr_path).ok(); }
+                        if let Some(ref mut file) = stderr_file {
+                            let bytes_read = file.read(&mut read_buffer).unwrap();
+                            if bytes_read != 0 { tint.stderr(&mut stderr, counter, &read_buffer[0..bytes_read]).unwrap(); }
+                        }
                         thread::sleep(Duration::from_millis(1));
                     }
                 }
@@ -229,21 +291,22 @@ pub fn receive_messages (
             changed = false;
             for (index, state) in buffer.iter().enumerate() {
                 match *state {
-                    State::Completed(id, ref name) if id == counter => {
+                    State::Completed(id, ref name, ref captured) if id == counter => {
                         let mut stdout = stdout.lock();
                         let mut stderr = stderr.lock();
                         filepaths::next_job_path(counter, truncate_size, &mut id_buffer, &mut stdout_path, &mut stderr_path);
-                        let (mut stdout_file, mut stderr_file) = open_job_files!(stdout_path, stderr_path);
-                        append_to_processed!(processed_file, name, stderr);
-                        read_outputs!(stdout_file, stderr_file, read_buffer, stdout, stderr);
-                        remove_job_files!(stdout_path, stderr_path, stderr);
+                        let replay_start = Instant::now();
+                        append_to_processed!(processed_file, counter, name, stderr);
+                        replay_outputs!(captured, (None, None), stdout_path, stderr_path, read_buffer, stdout, stderr,
+                            tint, counter, config.keep_temp);
+                        if let Some(trace) = trace { trace.span(counter+1, Phase::Replay, replay_start); }
                         counter += 1;
                         changed = true;
                         drop.push(index);
                     },
                     State::Error(id, ref message) if id == counter => {
                         counter += 1;
-                        if let Err(why) = error_file.write(message.as_bytes()) {
+                        if let Err(why) = error_file.append(id + 1, message.trim_end_matches('\n')) {
                             let mut stderr = stderr.lock();
                             let _ = write!(stderr, "parallel: I/O error: {}", why);
                         }
@@ -263,7 +326,7 @@ pub fn receive_messages (
                         job_counter += 1;
                         job_drop.push(index);
                         changed = true;
-                        log.write_entry(joblog, &mut id_buffer, id_pad_length);
+                        log.write_entry(joblog, &mut id_buffer, id_pad_length, joblog_template.as_ref());
                     }
                 }
             }
@@ -271,6 +334,21 @@ pub fn receive_messages (
 
         drop_used_states(&mut buffer, &mut drop);
         drop_used_logs(&mut job_buffer, &mut job_drop);
+
+        // A checkpoint may only be saved while the job log contains the entries of every completed job.
+        if let Some(ref path) = checkpoint {
+            let now = time::precise_time_ns();
+            if now - last_checkpoint >= CHECKPOINT_INTERVAL && (joblog.is_none() || job_counter == counter) {
+                save_checkpoint(path, resume.command, counter, error_count);
+                last_checkpoint = now;
+            }
+        }
+    }
+
+    if let Some(ref path) = checkpoint {
+        if joblog.is_none() || job_counter == counter {
+            save_checkpoint(path, resume.command, counter, error_count);
+        }
     }
 
     if let Some(mut joblog) = joblog {
@@ -280,18 +358,81 @@ pub fn receive_messages (
         }
     }
 
-    if let Err(why) = processed_file.flush() {
+    if let Err(why) = processed_file.commit() {
         let mut stderr = stderr.lock();
         let _ = write!(stderr, "parallel: I/O error: {}", why);
     }
 
-    if let Err(why) = error_file.flush() {
+    if let Err(why) = error_file.commit() {
         let mut stderr = stderr.lock();
         let _ = write!(stderr, "parallel: I/O error: {}", why);
     }
     error_count
 }
 
+/// Writes one of the outputs of a completed job, which is either the `data` that was kept in memory, or the
+/// job's file at `path`, which is read from where it was tailed to if the `file` was being tailed, and is
+/// removed once it has been written, unless the files are to be kept, in which case outputs that were kept in
+/// memory are also written to the job's file. If the tempdir became full, the output
+/// continues within the `overflow` file, and the job's file may not exist.
+fn replay<F: FnMut(&[u8]) -> io::Result<()>>(data: Option<&[u8]>, file: Option<File>, path: &str,
+    overflow: Option<&str>, keep: bool, buffer: &mut [u8], mut write: F) -> io::Result<()>
+{
+    let file = match (data, file) {
+        (Some(data), _) => {
+            let written = if data.is_empty() { Ok(()) } else { write(data) };
+            // Outputs which were kept in memory are written to the job's file, so that every job's file is kept.
+            if keep { fs::write(path, data)?; }
+            return written
+        },
+        (None, Some(file)) => Some(file),
+        (None, None) => match File::open(path) {
+            Ok(file) => Some(file),
+            Err(ref why) if overflow.is_some() && why.kind() == io::ErrorKind::NotFound => None,
+            Err(why) => return Err(why)
+        }
+    };
+
+    let mut written = Ok(());
+    if let Some(file) = file {
+        drain(file, buffer, &mut write, &mut written)?;
+        if !keep { fs::remove_file(path)?; }
+    }
+
+    if let Some(path) = overflow {
+        drain(File::open(path)?, buffer, &mut write, &mut written)?;
+        if !keep { fs::remove_file(path)?; }
+    }
+
+    written
+}
+
+/// Writes the remainder of the file, until an error occurs while writing.
+fn drain<F: FnMut(&[u8]) -> io::Result<()>>(mut file: File, buffer: &mut [u8], write: &mut F,
+    written: &mut io::Result<()>) -> io::Result<()>
+{
+    loop {
+        let bytes_read = file.read(buffer)?;
+        if bytes_read == 0 { return Ok(()) }
+        if written.is_ok() { *written = write(&buffer[0..bytes_read]); }
+    }
+}
+
+/// Records the number of jobs which have been completed, after ensuring that their outputs have been written.
+fn save_checkpoint(path: &Path, command: u64, completed: usize, failed: i32) {
+    let _ = io::stdout().flush();
+    let checkpoint = Checkpoint {
+        completed: completed,
+        failed:    failed as usize,
+        command:   command,
+    };
+
+    if let Err(why) = checkpoint.save(path) {
+        let stderr = io::stderr();
+        let _ = writeln!(stderr.lock(), "parallel: I/O error: unable to save checkpoint {:?}: {}", path, why);
+    }
+}
+
 /// Drops states that have been processed and are no longer required
 fn drop_used_states(buffer: &mut SmallVec<[State; 32]>, drop: &mut SmallVec<[usize; 32]>) {
     drop.sort();
@@ -307,3 +448,52 @@ fn drop_used_logs(buffer: &mut SmallVec<[JobLog; 32]>, drop: &mut SmallVec<[usiz
         let _ = buffer.remove(id);
     }
 }
+
+#[test]
+fn replay_outputs() {
+    let path = ::std::env::temp_dir().join(format!("parallel-replay-{}", ::std::process::id()));
+    let path = path.to_str().unwrap();
+    let mut buffer = [0u8; 4];
+    let mut output = Vec::new();
+    replay(Some(b"memory"), None, path, None, false, &mut buffer, |data| output.write_all(data)).unwrap();
+    assert_eq!(output, b"memory");
+
+    output.clear();
+    fs::write(path, b"spilled to disk").unwrap();
+    replay(None, None, path, None, false, &mut buffer, |data| output.write_all(data)).unwrap();
+    assert_eq!(output, b"spilled to disk");
+    assert!(!Path::new(path).exists());
+
+    // With `--keep-temp`, the job's file remains once it has been written, even if it was kept in memory.
+    output.clear();
+    replay(Some(b"memory"), None, path, None, true, &mut buffer, |data| output.write_all(data)).unwrap();
+    assert_eq!(fs::read(path).unwrap(), b"memory");
+
+    output.clear();
+    fs::write(path, b"kept").unwrap();
+    replay(None, None, path, None, true, &mut buffer, |data| output.write_all(data)).unwrap();
+    assert_eq!(output, b"kept");
+    assert!(Path::new(path).exists());
+    fs::remove_file(path).unwrap();
+
+    // Once the tempdir is full, the output continues within the overflow file, if the job's file exists at all.
+    output.clear();
+    let overflow = format!("{}-overflow", path);
+    fs::write(path, b"spilled ").unwrap();
+    fs::write(&overflow, b"and overflowed").unwrap();
+    replay(None, None, path, Some(&overflow), false, &mut buffer, |data| output.write_all(data)).unwrap();
+    assert_eq!(output, b"spilled and overflowed");
+    assert!(!Path::new(&overflow).exists());
+
+    output.clear();
+    fs::write(&overflow, b"overflowed").unwrap();
+    replay(None, None, path, Some(&overflow), false, &mut buffer, |data| output.write_all(data)).unwrap();
+    assert_eq!(output, b"overflowed");
+}
+
+#[test]
+fn output_tinting() {
+    let mut output = Vec::new();
+    tinted(&mut output, 11, true, b"error").unwrap();
+    assert_eq!(output, b"\x1b[1;33merror\x1b[0m");
+}
@@ -0,0 +1,116 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// A file within the tempdir which records an entry for each job, such as the processed and errors files.
+/// Each record is a line of the job's sequence number and its entry, separated by a tab, where tabs,
+/// newlines and backslashes within the entry are escaped. Records are written to a `.partial` file which
+/// replaces the file once the run has completed, or was interrupted, so that a line which was only partially
+/// written will never be read as a record.
+///
+/// Entries are buffered, unless the file is synced, in which case each entry is synced to the disk as it
+/// is appended. The file is shared, so that it may be committed if the run is interrupted.
+#[derive(Clone)]
+pub struct RecordFile {
+    file:    Arc<Mutex<BufWriter<File>>>,
+    path:    Arc<PathBuf>,
+    partial: Arc<PathBuf>,
+    sync:    bool,
+}
+
+impl RecordFile {
+    /// Creates the partial file of the records at `path`. The records of the jobs up to and including `keep`,
+    /// which were completed by a run that is being resumed, are carried over from the records of that run.
+    pub fn create(path: &Path, keep: usize, buffer_size: usize, sync: bool) -> io::Result<RecordFile> {
+        let mut partial = PathBuf::from(path);
+        partial.set_extension("partial");
+
+        // If the previous run was killed, its records will only exist within its partial file.
+        let previous = if keep == 0 { Vec::new() } else {
+            match fs::read_to_string(&partial).or_else(|_| fs::read_to_string(path)) {
+                Ok(contents) => parse(&contents).into_iter().filter(|&(job, _)| job <= keep).collect(),
+                Err(_) => Vec::new()
+            }
+        };
+
+        let file = RecordFile {
+            file:    Arc::new(Mutex::new(BufWriter::with_capacity(buffer_size, File::create(&partial)?))),
+            path:    Arc::new(PathBuf::from(path)),
+            partial: Arc::new(partial),
+            sync:    sync,
+        };
+
+        for (job, entry) in previous { file.append(job, &entry)?; }
+        Ok(file)
+    }
+
+    /// Reads the records at `path`, ignoring a final line which is incomplete.
+    pub fn read(path: &Path) -> io::Result<Vec<(usize, String)>> {
+        fs::read_to_string(path).map(|contents| parse(&contents))
+    }
+
+    /// Appends a record of the entry of the given job, counting from 1, to the file.
+    pub fn append(&self, job: usize, entry: &str) -> io::Result<()> {
+        let mut file = self.file.lock().unwrap();
+        file.write_all(format!("{}\t{}\n", job, escape(entry)).as_bytes())?;
+        if self.sync {
+            file.flush()?;
+            file.get_ref().sync_data()?;
+        }
+        Ok(())
+    }
+
+    /// Writes the records that have been buffered, and replaces the file with the partial file.
+    pub fn commit(&self) -> io::Result<()> {
+        let mut file = self.file.lock().unwrap();
+        file.flush()?;
+        if self.sync { file.get_ref().sync_data()?; }
+        fs::rename(&*self.partial, &*self.path)
+    }
+}
+
+fn escape(entry: &str) -> String {
+    let mut escaped = String::with_capacity(entry.len());
+    for character in entry.chars() {
+        match character {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            _    => escaped.push(character)
+        }
+    }
+    escaped
+}
+
+fn unescape(entry: &str) -> String {
+    let mut unescaped = String::with_capacity(entry.len());
+    let mut characters = entry.chars();
+    while let Some(character) = characters.next() {
+        match (character, if character == '\\' { characters.next() } else { None }) {
+            ('\\', Some('t')) => unescaped.push('\t'),
+            ('\\', Some('n')) => unescaped.push('\n'),
+            ('\\', Some(escaped)) => unescaped.push(escaped),
+            (character, _) => unescaped.push(character)
+        }
+    }
+    unescaped
+}
+
+/// Parses each complete line of the records, skipping any that are not valid records.
+fn parse(contents: &str) -> Vec<(usize, String)> {
+    let complete = contents.rfind('\n').map_or("", |end| &contents[..end]);
+    complete.lines().filter_map(|line| {
+        let tab = line.find('\t')?;
+        let job = line[..tab].parse::<usize>().ok()?;
+        Some((job, unescape(&line[tab+1..])))
+    }).collect()
+}
+
+#[test]
+fn record_parsing() {
+    assert_eq!(escape("a\tb\\c\nd"), "a\\tb\\\\c\\nd");
+    assert_eq!(unescape(&escape("a\tb\\c\nd\\")), "a\tb\\c\nd\\");
+    assert_eq!(parse("1\ta\n2\tb\\tc\nx\n3\td"), vec![(1, "a".to_owned()), (2, "b\tc".to_owned())]);
+    assert_eq!(parse("4\tpartial"), Vec::new());
+}
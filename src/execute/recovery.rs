@@ -0,0 +1,65 @@
+use input_iterator::InputsLock;
+use running;
+use super::child::Usage;
+use super::job_log::JobLog;
+use super::pipe::disk::State;
+use super::window::ResultSender;
+
+use std::any::Any;
+use std::io::{self, Read, Write};
+use time::Timespec;
+
+/// Handles a panic within the worker of the given slot, whose input buffer contains the input of the job that
+/// it was executing. The job is killed if it is still running. If the result of the job was not yet sent, the
+/// job is recorded as an error, so that the outputs of the jobs which follow it are not held back, and so that
+/// the run will exit with a failure. Returns `true` if the worker should resume with the next input, or
+/// `false` if it panicked before a job was dispatched to it, in which case it would only panic again.
+pub fn recover<IO: Read>(slot: usize, inputs: &mut InputsLock<IO>, input: &str, output_tx: &ResultSender,
+    why: Box<dyn Any + Send>) -> bool
+{
+    let reason = match why.downcast_ref::<&str>() {
+        Some(reason) => *reason,
+        None => why.downcast_ref::<String>().map_or("unknown cause", |reason| reason.as_str())
+    };
+
+    let stderr = io::stderr();
+    let mut stderr = stderr.lock();
+    let job_id = match inputs.dispatched.take() {
+        Some(job_id) => job_id,
+        None => {
+            let _ = writeln!(stderr, "parallel: the worker in slot {} panicked: {}", slot, reason);
+            return false
+        }
+    };
+
+    let _ = writeln!(stderr, "parallel: the worker in slot {} panicked while executing job {}: {}", slot, job_id+1,
+        reason);
+
+    // The job would otherwise be left running, and would not be interrupted along with the other jobs.
+    if let Some(pid) = inputs.child {
+        running::kill(pid);
+        inputs.exited(pid);
+    }
+
+    if !inputs.reported {
+        let message = format!("{}: {}: the worker panicked: {}\n", job_id+1, input, reason);
+        let _ = output_tx.send(State::Error(job_id, message));
+        inputs.report(-1, 0);
+    }
+
+    // The job log is written in the order of the jobs, so the entry of this job must still be sent.
+    if inputs.config.joblog && !inputs.logged {
+        let _ = output_tx.send(State::JobLog(JobLog {
+            job_id:     job_id,
+            start_time: Timespec::new(0, 0),
+            runtime:    0,
+            exit_value: -1,
+            signal:     0,
+            usage:      Usage::default(),
+            config:     inputs.config,
+            command:    input.to_owned(),
+        }));
+    }
+
+    true
+}
@@ -0,0 +1,45 @@
+use std::time::Duration;
+
+/// The delay between retries when only a backoff was given, which the backoff will multiply.
+const DEFAULT_DELAY: u64 = 1000;
+
+/// Determines how many times a failed job will be retried with the same input, and how long to wait
+/// before each retry, as given by the `--retries`, `--retry-delay`, and `--retry-backoff` parameters.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Retries {
+    /// The number of times that a failed job will be retried.
+    pub retries: usize,
+    /// The delay before the first retry.
+    pub delay:   Duration,
+    /// The factor that the delay is multiplied by with each successive retry.
+    pub backoff: f64,
+}
+
+impl Retries {
+    /// Obtains the delay before the given retry, counting from 1.
+    pub fn delay(&self, retry: usize) -> Duration {
+        let delay = if self.delay == Duration::from_millis(0) && self.backoff != 1.0 {
+            DEFAULT_DELAY as f64
+        } else {
+            self.delay.as_secs() as f64 * 1000f64 + self.delay.subsec_nanos() as f64 / 1_000_000f64
+        };
+        Duration::from_millis((delay * self.backoff.powi(retry as i32 - 1)) as u64)
+    }
+}
+
+#[test]
+fn retry_delays() {
+    let retries = Retries { retries: 3, delay: Duration::from_millis(0), backoff: 1.0 };
+    assert_eq!(retries.delay(2), Duration::from_millis(0));
+
+    let retries = Retries { retries: 3, delay: Duration::from_millis(5000), backoff: 1.0 };
+    assert_eq!(retries.delay(1), Duration::from_millis(5000));
+    assert_eq!(retries.delay(3), Duration::from_millis(5000));
+
+    let retries = Retries { retries: 3, delay: Duration::from_millis(500), backoff: 2.0 };
+    assert_eq!(retries.delay(1), Duration::from_millis(500));
+    assert_eq!(retries.delay(3), Duration::from_millis(2000));
+
+    let retries = Retries { retries: 3, delay: Duration::from_millis(0), backoff: 3.0 };
+    assert_eq!(retries.delay(2), Duration::from_millis(3000));
+}
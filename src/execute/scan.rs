@@ -0,0 +1,143 @@
+use halt::Halting;
+use regex::bytes::Regex;
+use std::mem;
+use std::sync::Arc;
+
+/// The longest line that will be buffered before it is scanned, so that jobs which never write
+/// a newline will not consume an unbounded amount of memory.
+const MAX_LINE: usize = 64 * 1024;
+
+/// The patterns that the outputs of each job will be scanned for as they are captured.
+#[derive(Clone)]
+pub struct Patterns {
+    /// Halts the run when a job writes a matching line, as given by the `--halt-on-output` parameter.
+    halt:    Option<Arc<Halting>>,
+    /// Fails a job that writes a matching line, as given by the `--fail-on-output` parameter.
+    fail:    Option<Arc<Regex>>,
+    /// Fails a job that does not write a matching line, as given by the `--require-output` parameter.
+    require: Option<Arc<Regex>>,
+}
+
+impl Patterns {
+    pub fn new(halt: Option<&Arc<Halting>>, fail: Option<Regex>, require: Option<Regex>) -> Patterns {
+        Patterns {
+            halt:    halt.filter(|halt| halt.scans_output()).cloned(),
+            fail:    fail.map(Arc::new),
+            require: require.map(Arc::new),
+        }
+    }
+
+    /// Returns `true` if the outputs of jobs do not need to be scanned.
+    pub fn is_empty(&self) -> bool { self.halt.is_none() && self.fail.is_none() && self.require.is_none() }
+
+    /// Creates a scanner for one of the outputs of the given job.
+    pub fn scanner(&self, job_id: usize) -> Scanner {
+        Scanner { patterns: self.clone(), job_id: job_id, line: Vec::new(), matches: Matches::default() }
+    }
+
+    /// Returns `true` if a job should be considered to have failed, given the matches within its outputs.
+    pub fn failed(&self, matches: Matches) -> bool {
+        (self.fail.is_some() && matches.failed) || (self.require.is_some() && !matches.required)
+    }
+}
+
+/// Records which of the patterns that determine the success of a job were found within its outputs.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Matches {
+    /// A line matched the `--fail-on-output` pattern.
+    failed:   bool,
+    /// A line matched the `--require-output` pattern.
+    required: bool,
+}
+
+impl Matches {
+    /// Combines the matches of the standard output and error of a job.
+    pub fn merge(self, other: Matches) -> Matches {
+        Matches { failed: self.failed || other.failed, required: self.required || other.required }
+    }
+}
+
+/// Splits an output of a job into lines, which are checked against the patterns as they are completed.
+pub struct Scanner {
+    patterns: Patterns,
+    job_id:   usize,
+    /// The incomplete line that has been written so far.
+    line:     Vec<u8>,
+    matches:  Matches,
+}
+
+impl Scanner {
+    /// Scans a message that was written by the job.
+    pub fn scan(&mut self, mut message: &[u8]) {
+        if self.patterns.is_empty() { return }
+
+        while let Some(pos) = message.iter().position(|&byte| byte == b'\n') {
+            if self.line.is_empty() {
+                self.check(&message[..pos]);
+            } else {
+                self.line.extend_from_slice(&message[..pos]);
+                self.check_buffered();
+            }
+            message = &message[pos+1..];
+        }
+
+        self.line.extend_from_slice(message);
+        if self.line.len() >= MAX_LINE { self.check_buffered(); }
+    }
+
+    /// Checks the final line, which may not have been terminated by a newline, once the output has closed,
+    /// and returns the patterns that were found.
+    pub fn finish(mut self) -> Matches {
+        if !self.line.is_empty() { self.check_buffered(); }
+        self.matches
+    }
+
+    fn check_buffered(&mut self) {
+        let line = mem::replace(&mut self.line, Vec::new());
+        self.check(&line);
+        self.line = line;
+        self.line.clear();
+    }
+
+    fn check(&mut self, line: &[u8]) {
+        if let Some(ref halt) = self.patterns.halt { halt.output_line(self.job_id, line); }
+        if let Some(ref fail) = self.patterns.fail {
+            if !self.matches.failed && fail.is_match(line) { self.matches.failed = true; }
+        }
+        if let Some(ref require) = self.patterns.require {
+            if !self.matches.required && require.is_match(line) { self.matches.required = true; }
+        }
+    }
+}
+
+#[test]
+fn scanner_lines() {
+    use halt;
+
+    let halting = Arc::new(Halting::new(None, Some(halt::parse_output("^PANIC$").unwrap()), 1));
+    let mut scanner = Patterns::new(Some(&halting), None, None).scanner(0);
+    scanner.scan(b"one\nPA");
+    assert!(!halting.halted());
+    scanner.scan(b"NIC");
+    assert!(!halting.halted());
+    scanner.finish();
+    assert!(halting.halted());
+}
+
+#[test]
+fn scanner_matches() {
+    let patterns = Patterns::new(None, Some(Regex::new("ERROR").unwrap()), Some(Regex::new("^done$").unwrap()));
+    let mut stdout = patterns.scanner(0);
+    stdout.scan(b"working\ndone\n");
+    let stdout = stdout.finish();
+    assert!(!patterns.failed(stdout));
+
+    let mut stderr = patterns.scanner(0);
+    stderr.scan(b"ERR");
+    stderr.scan(b"OR: disk full");
+    assert!(patterns.failed(stdout.merge(stderr.finish())));
+
+    let mut stdout = patterns.scanner(0);
+    stdout.scan(b"not done\n");
+    assert!(patterns.failed(stdout.finish()));
+}
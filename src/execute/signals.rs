@@ -1,12 +1,22 @@
 use std::process::ExitStatus;
 
+/// The signal that is recorded when a job is killed for exceeding the timeout.
+#[cfg(unix)]
+pub const TIMEOUT: i32 = 15;
+
+/// Windows terminates processes with `TerminateProcess` rather than a signal.
+#[cfg(not(unix))]
+pub const TIMEOUT: i32 = 0;
+
+/// Obtains the signal that killed the process, if the process was killed by a signal.
 #[cfg(unix)]
 pub fn get(status: ExitStatus) -> i32 {
     use std::os::unix::process::ExitStatusExt;
     status.signal().unwrap_or(0)
 }
 
+/// Windows does not have signals, so there is no signal to obtain from the exit status.
 #[cfg(not(unix))]
-pub fn get(child: ExitStatus) -> i32 {
+pub fn get(_status: ExitStatus) -> i32 {
     0
 }
@@ -0,0 +1,67 @@
+use time::{self, Tm};
+
+/// The wall-clock time that prefixes each line of the outputs of jobs, as given by `--timestamp`, which is
+/// recorded when the line was read from the job, rather than when it was written by parallel.
+#[derive(Clone)]
+pub enum Timestamp {
+    /// An ISO 8601 timestamp of the local time, with milliseconds and the offset from UTC.
+    Iso8601,
+    /// The local time, formatted by a `strftime` format, such as `%H:%M:%S`.
+    Format(String),
+}
+
+impl Timestamp {
+    /// Parses the format of `--timestamp=FORMAT`, returning the format if it is not valid.
+    pub fn parse(format: &str) -> Result<Timestamp, String> {
+        if format.is_empty() || time::now().strftime(format).is_err() { return Err(format.to_owned()) }
+        Ok(Timestamp::Format(format.to_owned()))
+    }
+
+    fn format(&self, now: &Tm) -> String {
+        match *self {
+            Timestamp::Iso8601 => {
+                let offset = now.tm_utcoff / 60;
+                format!("{}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}{}{:02}:{:02}", 1900+now.tm_year, 1+now.tm_mon,
+                    now.tm_mday, now.tm_hour, now.tm_min, now.tm_sec, now.tm_nsec / 1_000_000,
+                    if offset < 0 { '-' } else { '+' }, offset.abs() / 60, offset.abs() % 60)
+            },
+            Timestamp::Format(ref format) => now.strftime(format).map(|time| time.to_string()).unwrap_or_default()
+        }
+    }
+
+    /// Prefixes each line which begins within the data with the time that it was read, `now`, followed by a
+    /// space. As a line may span several reads, `line_start` records whether the data begins a new line.
+    pub fn stamp(&self, data: &[u8], line_start: &mut bool, now: &Tm) -> Vec<u8> {
+        let prefix = self.format(now);
+        let mut stamped = Vec::with_capacity(data.len() + prefix.len() + 1);
+        let mut start = 0;
+        while start < data.len() {
+            if *line_start {
+                stamped.extend_from_slice(prefix.as_bytes());
+                stamped.push(b' ');
+            }
+
+            let end = data[start..].iter().position(|&byte| byte == b'\n').map_or(data.len(), |pos| start + pos + 1);
+            stamped.extend_from_slice(&data[start..end]);
+            *line_start = data[end-1] == b'\n';
+            start = end;
+        }
+        stamped
+    }
+}
+
+#[test]
+fn timestamp_lines() {
+    let now = time::at_utc(time::Timespec::new(1_500_000_000, 250_000_000));
+    assert_eq!(Timestamp::Iso8601.format(&now), "2017-07-14T02:40:00.250+00:00");
+
+    let timestamp = Timestamp::parse("%H:%M:%S").unwrap();
+    let mut line_start = true;
+    assert_eq!(timestamp.stamp(b"one\ntw", &mut line_start, &now), b"02:40:00 one\n02:40:00 tw".to_vec());
+    assert_eq!(timestamp.stamp(b"o\n", &mut line_start, &now), b"o\n".to_vec());
+    assert_eq!(timestamp.stamp(b"three\n\n", &mut line_start, &now), b"02:40:00 three\n02:40:00 \n".to_vec());
+    assert!(line_start);
+
+    assert_eq!(Timestamp::parse("%Q").err(), Some(String::from("%Q")));
+    assert!(Timestamp::parse("").is_err());
+}
@@ -0,0 +1,59 @@
+use super::pipe::disk::State;
+use std::sync::{Arc, Condvar, Mutex};
+use std::sync::mpsc::{SendError, SyncSender};
+
+/// The range of jobs whose results may be sent to the receiver, which begins at the earliest job that the
+/// receiver has yet to write. Results which arrive out of order are held by the receiver until the jobs before
+/// them have been written, so the number of results that it must hold while waiting on a slow job is bounded
+/// by the size of the window.
+pub struct Window {
+    released: Mutex<usize>,
+    advanced: Condvar,
+    size:     usize,
+}
+
+impl Window {
+    pub fn new(size: usize) -> Window {
+        Window { released: Mutex::new(0), advanced: Condvar::new(), size: size }
+    }
+
+    /// Records that the results of every job before `job_id` have been written, waking the workers whose
+    /// results now fall within the window.
+    pub fn release(&self, job_id: usize) {
+        let mut released = self.released.lock().unwrap();
+        if *released != job_id {
+            *released = job_id;
+            self.advanced.notify_all();
+        }
+    }
+
+    /// Blocks until the result of the given job falls within the window.
+    fn wait(&self, job_id: usize) {
+        let mut released = self.released.lock().unwrap();
+        while job_id >= *released + self.size {
+            released = self.advanced.wait(released).unwrap();
+        }
+    }
+}
+
+/// Sends the results of jobs to the receiver, blocking the worker until the result of its job falls within
+/// the window, so that workers can not outpace the receiver while it waits on a slow job.
+#[derive(Clone)]
+pub struct ResultSender {
+    sender: SyncSender<State>,
+    window: Arc<Window>,
+}
+
+impl ResultSender {
+    pub fn new(sender: SyncSender<State>, window: Arc<Window>) -> ResultSender {
+        ResultSender { sender: sender, window: window }
+    }
+
+    pub fn send(&self, state: State) -> Result<(), SendError<State>> {
+        self.window.wait(match state {
+            State::Completed(job_id, ..) | State::Error(job_id, _) => job_id,
+            State::JobLog(ref log) => log.job_id,
+        });
+        self.sender.send(state)
+    }
+}
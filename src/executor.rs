@@ -0,0 +1,405 @@
+use std::fs;
+use std::io::{self, Read, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::sync_channel;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use arguments::{jobs, Args, Config};
+use checkpoint::Checkpoint;
+use events::EventLog;
+use halt::Halting;
+use execute::{self, command::{JobTimeout, OutputFiles, Records}, pipe::disk::State, ResultSender, Timestamp, Window};
+use input_iterator::{InputIterator, InputsLock, StartRate, UNKNOWN_TOTAL};
+use ionice;
+use misc::Random;
+use queue::WorkQueue;
+use results::ResultsDir;
+use running;
+use shell;
+use status::{Monitor, StatusFile};
+use syslog;
+use trace::TraceLog;
+use time;
+use tokenizer::{Replacements, Token};
+use verbose;
+
+/// The interval at which the file given to the `--jobs` parameter is re-read.
+const JOBS_FILE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The number of messages which may be queued for the receiver before the workers are blocked, which is
+/// also the number of jobs that workers may report ahead of the job whose outputs are being written, so
+/// that fast jobs can not outpace the printing of their outputs without bound.
+const MESSAGE_BOUND: usize = 1024;
+
+/// Executes the commands generated from a command template and an `InputIterator` across a
+/// number of worker threads, printing the outputs of each job in the order that inputs were given.
+pub struct Executor {
+    /// The options that were collected for this run.
+    pub args:           Args,
+    /// The tokenized command template, which is empty if the inputs are commands.
+    pub arguments:      &'static [Token],
+    /// The strings which will be replaced with the job and slot numbers within inputs that are commands.
+    pub replace:        Replacements,
+    /// The shell that commands will be executed within, if a shell other than the default is desired.
+    pub shell:          Option<&'static str>,
+    /// A command that each job will be executed by, such as `systemd-run --scope --user`.
+    pub wrapper:        &'static [String],
+    /// The templates of the files that the outputs of each job will be written to, instead of being printed.
+    pub outputs:        OutputFiles,
+    /// The template of a command that will be executed for each job that fails.
+    pub on_fail:        Option<&'static [Token]>,
+    /// The template of the directory that each job will be executed within.
+    pub workdir:        Option<&'static [Token]>,
+    /// The timeout of each job, which may be obtained from the input of the job.
+    pub timeout:        JobTimeout,
+    /// If set, lifecycle events for each job will be written to this log.
+    pub events:         Option<EventLog>,
+    /// If set, the metadata of each job will be written within this directory.
+    pub results:        Option<ResultsDir>,
+    /// If set, the time spent within each phase of each job will be written to this log.
+    pub trace:          Option<TraceLog>,
+    /// The state of a previous run which is being resumed with the `--checkpoint` parameter.
+    pub resume:         Checkpoint,
+    /// The directory where the outputs of each job will be temporarily stored.
+    pub base_path:      String,
+    /// The file where inputs will be recorded as they are processed.
+    pub processed_path: PathBuf,
+    /// The file where errors will be recorded as they occur.
+    pub errors_path:    PathBuf,
+}
+
+impl Executor {
+    /// Executes a job for each input, and returns the number of errors that occurred, or the exit status of
+    /// the failed job which halted the run, if the `--halt` policy was met by failures.
+    pub fn run<IO: Read + Send + 'static>(self, inputs: InputIterator<IO>) -> i32 {
+        let Executor { mut args, arguments, replace, shell, wrapper, outputs, on_fail, workdir, timeout, events, results, trace,
+            resume, base_path, processed_path, errors_path } = self;
+        let stdout = io::stdout();
+
+        // The `InputIterator` will be wrapped within a `Mutex` so that it can safely be shared
+        // across all of the upcoming threads. A `Mutex` is required because each time a thread
+        // pulls the next input from the queue, it needs to update various data fields which
+        // rely on threads waiting for their turn to report back.
+        let shared_input = Arc::new(Mutex::new(inputs));
+
+        // This channel is used exclusively for signaling back to the main thread when a task
+        // has completed or has errored. Workers will block when the main thread falls behind.
+        // Workers will also block while their job is too far ahead of the job whose outputs are being written.
+        let (output_tx, input_rx) = sync_channel::<State>(MESSAGE_BOUND);
+        let window = Arc::new(Window::new(MESSAGE_BOUND));
+        let output_tx = ResultSender::new(output_tx, window.clone());
+
+        // Will contain handles to the upcoming threads to know when the threads are finished.
+        let mut threads = Vec::with_capacity(args.ncores);
+
+        // Job start delays are only applied if a delay or a delay range was supplied.
+        let has_delay = args.delay != Duration::from_millis(0) || args.jitter != Duration::from_millis(0);
+
+        // Tracks consecutive job failures across all threads for the `--delay-backoff` parameter.
+        let failures = Arc::new(AtomicUsize::new(0));
+
+        // Tracks the results and outputs of jobs across all threads for the `--halt` and `--halt-on-output` parameters.
+        // The run is also halted by a worker once the `--memfree-timeout` has been reached.
+        let waits_for_memory = args.memory > 0 && args.mem_timeout != Duration::from_millis(0);
+        let halt = match (args.halt, args.halt_on_output.take()) {
+            (None, None) if !waits_for_memory => None,
+            (policy, output) => Some(Arc::new(Halting::new(policy, output, args.ninputs))),
+        };
+
+        let start_time = time::precise_time_ns();
+
+        // If the `--log-syslog` parameter was passed, job results will also be written to the system logger.
+        if args.config.log_syslog { syslog::open(); }
+
+        // If the `--ionice` parameter was passed, jobs will be spawned with the given IO priority.
+        if let Some(priority) = args.ionice { ionice::init(priority); }
+
+        // Interrupts and suspensions will be forwarded to the running jobs, which are within their own process groups.
+        running::handle_signals();
+
+        if args.config.verbose {
+            verbose::total_inputs(&stdout, args.ncores, args.expected_total());
+        }
+
+        if args.config.inputs_are_commands {
+            if cfg!(windows) {
+                // Commands are always executed within `cmd` on Windows.
+            } else if shell::ion_exists() {
+                args.config.ion_exists = true;
+            } else if shell::dash_exists() {
+                args.config.dash_exists = true;
+            }
+        } else {
+            shell::set_flags(&mut args.config, arguments);
+        }
+
+        let workers = Workers {
+            inputs:      shared_input.clone(),
+            output_tx:   output_tx,
+            arguments:   arguments,
+            replace:     replace,
+            shell:       shell,
+            wrapper:     wrapper,
+            events:      events.clone(),
+            results:     results,
+            queue:       args.queue.take(),
+            trace:       trace.clone(),
+            tempdir:     base_path.clone(),
+            buffer_size: args.buffer_size,
+            timestamp:   args.timestamp.take(),
+            config:      args.config,
+            num_inputs:  args.expected_total(),
+            records:     args.records(),
+            outputs:     outputs,
+            on_fail:     on_fail,
+            workdir:     workdir,
+            retries:     execute::Retries {
+                retries: args.retries,
+                delay:   args.retry_delay,
+                backoff: args.retry_backoff,
+            },
+            timeout:     timeout,
+            stall_timeout: args.stall_timeout,
+            patterns:    execute::Patterns::new(halt.as_ref(), args.fail_on_output.take(), args.require_output.take()),
+            memory:      args.memory,
+            mem_poll:    args.mem_poll,
+            mem_timeout: args.mem_timeout,
+            delay:       args.delay,
+            jitter:      args.jitter,
+            seed:        args.seed,
+            has_delay:   has_delay,
+            failures:    failures,
+            halt:        halt.clone(),
+            start_rate:  args.max_starts.map(|rate| Arc::new(StartRate::new(rate))),
+            jobs:        Arc::new(AtomicUsize::new(args.ncores)),
+        };
+
+        // The `slot` variable is required by the {%} token.
+        for slot in 1..args.ncores+1 { threads.push(workers.spawn(slot)); }
+
+        // If the number of jobs was given by a file, it will be re-read while jobs are running, and
+        // more workers will be spawned as it is raised. Only the threads should hold a sender, so
+        // that the channel is closed once they have exited.
+        let monitor = match args.jobs_file.take() {
+            Some(path) => {
+                let ncores = args.ncores;
+                Some(thread::spawn(move || workers.monitor(&path, ncores)))
+            },
+            None => {
+                drop(workers);
+                None
+            }
+        };
+
+        // If the `--status-file` parameter was passed, the progress of the run will periodically be written to it.
+        let total = match args.expected_total() { UNKNOWN_TOTAL => None, total => Some(total) };
+        let status = args.status_file.take().map(|path| Monitor::start(StatusFile::new(path, total)));
+
+        // Prints messages from executed commands in the correct order.
+        let checkpoint = args.checkpoint.clone();
+        let errors = execute::receive_messages(input_rx, args, resume, trace.as_ref(), &window, &base_path,
+            &processed_path, &errors_path);
+
+        // Wait for all threads to exit before proceeding.
+        if let Some(monitor) = monitor { threads.extend(monitor.join().unwrap()); }
+        for thread in threads { thread.join().unwrap(); }
+        if let Some(status) = status { status.finish(); }
+
+        // The checkpoint is no longer needed once every input has been processed.
+        if let Some(ref path) = checkpoint {
+            let inputs = shared_input.lock().unwrap();
+            if inputs.curr_argument == inputs.total_arguments { let _ = fs::remove_file(path); }
+        }
+
+        if let Some(ref events) = events {
+            // The total is only discovered after the inputs have been exhausted when streaming inputs.
+            let ninputs = shared_input.lock().unwrap().total_arguments;
+            events.summary(ninputs, time::precise_time_ns() - start_time);
+        }
+
+        if let Some(ref trace) = trace { trace.summary(); }
+
+        halt.and_then(|halt| halt.status()).unwrap_or(errors)
+    }
+}
+
+/// The state that is shared by every worker thread, from which a worker may be spawned into any slot.
+struct Workers<IO: Read + Send + 'static> {
+    inputs:      Arc<Mutex<InputIterator<IO>>>,
+    output_tx:   ResultSender,
+    arguments:   &'static [Token],
+    replace:     Replacements,
+    shell:       Option<&'static str>,
+    wrapper:     &'static [String],
+    events:      Option<EventLog>,
+    results:     Option<ResultsDir>,
+    queue:       Option<WorkQueue>,
+    trace:       Option<TraceLog>,
+    tempdir:     String,
+    buffer_size: usize,
+    timestamp:   Option<Timestamp>,
+    config:      Config,
+    num_inputs:  usize,
+    records:     Records,
+    retries:     execute::Retries,
+    outputs:     OutputFiles,
+    on_fail:     Option<&'static [Token]>,
+    workdir:     Option<&'static [Token]>,
+    timeout:     JobTimeout,
+    stall_timeout: Duration,
+    patterns:    execute::Patterns,
+    memory:      u64,
+    mem_poll:    Duration,
+    mem_timeout: Duration,
+    delay:       Duration,
+    jitter:      Duration,
+    /// Seeds the random delay of each slot, as given by the `--seed` parameter, or by the time if not given.
+    seed:        Option<u64>,
+    has_delay:   bool,
+    failures:    Arc<AtomicUsize>,
+    halt:        Option<Arc<Halting>>,
+    start_rate:  Option<Arc<StartRate>>,
+    /// The number of jobs that may run at once, which only workers in slots up to this number may start.
+    jobs:        Arc<AtomicUsize>,
+}
+
+impl<IO: Read + Send + 'static> Workers<IO> {
+    /// Spawns a worker thread that will execute jobs within the given slot. If the worker panics, it will
+    /// resume with the next input, so that the jobs of the remaining inputs are not lost.
+    fn spawn(&self, slot: usize) -> JoinHandle<()> {
+        let config = self.config;
+        let inputs = InputsLock {
+            inputs:      self.inputs.clone(),
+            slot:        slot,
+            jobs:        self.jobs.clone(),
+            memory:      self.memory,
+            mem_poll:    self.mem_poll,
+            mem_timeout: self.mem_timeout,
+            delay:       self.delay,
+            jitter:      self.jitter,
+            has_delay:   self.has_delay,
+            completed:   false,
+            config:      config,
+            failures:    self.failures.clone(),
+            random:      Random::new(self.seed.unwrap_or_else(time::precise_time_ns) ^ slot as u64),
+            halt:        self.halt.clone(),
+            start_rate:  self.start_rate.clone(),
+            dispatched:  None,
+            reported:    false,
+            logged:      false,
+            child:       None,
+        };
+
+        if config.inputs_are_commands {
+            let mut exec = execute::ExecInputs {
+                slot:       slot,
+                num_inputs: self.num_inputs,
+                retries:    self.retries,
+                outputs:    self.outputs,
+                on_fail:    self.on_fail,
+                workdir:    self.workdir,
+                timeout:    self.timeout,
+                stall_timeout: self.stall_timeout,
+                patterns:   self.patterns.clone(),
+                output_tx:  self.output_tx.clone(),
+                tempdir:    self.tempdir.clone(),
+                buffer_size: self.buffer_size,
+                timestamp:  self.timestamp.clone(),
+                events:     self.events.clone(),
+                results:    self.results.clone(),
+                queue:      self.queue.clone(),
+                trace:      self.trace.clone(),
+                replace:    self.replace,
+                shell:      self.shell,
+                wrapper:    self.wrapper,
+                inputs:     inputs,
+                input:      String::with_capacity(64),
+            };
+
+            thread::spawn(move || {
+                while let Err(why) = panic::catch_unwind(AssertUnwindSafe(|| exec.run(config))) {
+                    if !execute::recover(slot, &mut exec.inputs, &exec.input, &exec.output_tx, why) { break }
+                }
+            })
+        } else {
+            // The command will be built from the arguments, and inputs will be transferred to the command.
+            let mut exec = execute::ExecCommands {
+                slot:       slot,
+                num_inputs: self.num_inputs,
+                records:    self.records,
+                retries:    self.retries,
+                outputs:    self.outputs,
+                on_fail:    self.on_fail,
+                workdir:    self.workdir,
+                config:     config,
+                timeout:    self.timeout,
+                stall_timeout: self.stall_timeout,
+                patterns:   self.patterns.clone(),
+                inputs:     inputs,
+                input:      String::with_capacity(64),
+                output_tx:  self.output_tx.clone(),
+                arguments:  self.arguments,
+                tempdir:    self.tempdir.clone(),
+                buffer_size: self.buffer_size,
+                timestamp:  self.timestamp.clone(),
+                events:     self.events.clone(),
+                results:    self.results.clone(),
+                queue:      self.queue.clone(),
+                trace:      self.trace.clone(),
+                shell:      self.shell,
+                wrapper:    self.wrapper,
+            };
+
+            thread::spawn(move || {
+                while let Err(why) = panic::catch_unwind(AssertUnwindSafe(|| exec.run())) {
+                    if !execute::recover(slot, &mut exec.inputs, &exec.input, &exec.output_tx, why) { break }
+                }
+            })
+        }
+    }
+
+    /// Periodically re-reads the number of jobs from the file given to the `--jobs` parameter until
+    /// no more jobs will be started, spawning workers into the new slots as the number is raised.
+    /// Returns the handles of the workers that were spawned.
+    fn monitor(self, path: &Path, mut spawned: usize) -> Vec<JoinHandle<()>> {
+        let mut threads = Vec::new();
+        let mut last_read = Instant::now();
+        let mut failed = false;
+
+        while !self.finished() {
+            thread::sleep(Duration::from_millis(100));
+            if last_read.elapsed() < JOBS_FILE_INTERVAL { continue }
+            last_read = Instant::now();
+
+            match jobs::read(path) {
+                Ok(jobs) => {
+                    failed = false;
+                    self.jobs.store(jobs, Ordering::SeqCst);
+                    while spawned < jobs {
+                        spawned += 1;
+                        threads.push(self.spawn(spawned));
+                    }
+                },
+                // The number of jobs will remain unchanged until the file is valid again.
+                Err(why) => if !failed {
+                    failed = true;
+                    let stderr = io::stderr();
+                    let _ = writeln!(stderr.lock(), "parallel: unable to read the number of jobs from {:?}: {}", path, why);
+                }
+            }
+        }
+
+        threads
+    }
+
+    /// Returns `true` if every input has been taken by a worker, or if the run has been halted.
+    fn finished(&self) -> bool {
+        if self.halt.as_ref().map_or(false, |halt| halt.halted()) { return true }
+        let inputs = self.inputs.lock().unwrap();
+        inputs.curr_argument == inputs.total_arguments
+    }
+}
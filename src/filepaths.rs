@@ -1,23 +1,99 @@
+use checkpoint::hash_command;
 use numtoa::NumToA;
-use std::path::PathBuf;
+use std::env;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{MAIN_SEPARATOR, Path, PathBuf};
+use std::process;
+use std::sync::Mutex;
+
+/// The directory that temporary files are written within once the tempdir has run out of space.
+static FALLBACK: Mutex<Option<PathBuf>> = Mutex::new(None);
 
 #[cfg(not(windows))]
+/// Temporary files are stored within `$TMPDIR/parallel`, falling back to `/tmp/parallel` if the
+/// `TMPDIR` variable has not been set.
 pub fn base() -> Option<PathBuf> {
-    Some(PathBuf::from("/tmp/parallel"))
+    let mut path = env::var_os("TMPDIR").filter(|temp| !temp.is_empty())
+        .map_or_else(|| PathBuf::from("/tmp"), PathBuf::from);
+    path.push("parallel");
+    Some(path)
+}
+
+#[cfg(not(windows))]
+/// Once the tempdir is full, temporary files are stored within `$XDG_CACHE_HOME/parallel`, or
+/// `$HOME/.cache/parallel` if the `XDG_CACHE_HOME` variable has not been set.
+fn fallback_base() -> Option<PathBuf> {
+    if let Some(cache) = env::var_os("XDG_CACHE_HOME").filter(|cache| !cache.is_empty()) {
+        return Some(PathBuf::from(cache).join("parallel"))
+    }
+
+    env::var_os("HOME").filter(|home| !home.is_empty())
+        .map(|home| PathBuf::from(home).join(".cache").join("parallel"))
 }
 
 #[cfg(windows)]
+/// Once the tempdir is full, temporary files are stored within the user's local application data
+/// directory.
+fn fallback_base() -> Option<PathBuf> {
+    env::var_os("LOCALAPPDATA").map(|data| PathBuf::from(data).join("parallel"))
+}
+
+#[cfg(windows)]
+/// Temporary files are stored within `%TEMP%\parallel`, falling back to the user's local
+/// application data directory if the `TEMP` variable has not been set.
 pub fn base() -> Option<PathBuf> {
     use std::env::home_dir;
+    if let Some(temp) = env::var_os("TEMP").or_else(|| env::var_os("TMP")) {
+        let mut path = PathBuf::from(temp);
+        path.push("parallel");
+        return Some(path)
+    }
+
     home_dir().map(|mut path| {
-        path.push("AppData/Local/Temp/parallel");
+        path.push("AppData\\Local\\Temp\\parallel");
         path
     })
 }
 
+/// Returns the directory within the tempdir at `base` that the files of this run are written within, so that
+/// runs which share a tempdir never read or remove each other's files. Each run is given a directory named after
+/// its process ID, unless it was given a `--checkpoint`, in which case the directory is named after the path of
+/// the checkpoint, so that a run which resumes from the checkpoint will find the files of the run before it.
+pub fn run_dir(base: &Path, checkpoint: Option<&str>) -> PathBuf {
+    match checkpoint {
+        Some(checkpoint) => {
+            let path = env::current_dir().map(|dir| dir.join(checkpoint)).unwrap_or_else(|_| PathBuf::from(checkpoint));
+            base.join(format!("checkpoint-{:016x}", hash_command(&path.to_string_lossy())))
+        },
+        None => base.join(process::id().to_string())
+    }
+}
+
+/// Removes the outputs of jobs within the run's directory at `base` which were left behind by a previous run
+/// that was resumed from the same checkpoint, as they would otherwise be tailed as the outputs of this run's jobs.
+pub fn remove_job_files(base: &Path) {
+    if let Ok(entries) = fs::read_dir(base) {
+        for entry in entries.filter_map(Result::ok) {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if name.starts_with("stdout_") || name.starts_with("stderr_") { let _ = fs::remove_file(entry.path()); }
+        }
+    }
+}
+
+/// Removes the run's directory at `base`, along with every file that the run has written within it. The
+/// tempdir that contains it is left as is, as it may be shared with other runs.
+pub fn remove_tempdir(base: &Path) {
+    let _ = fs::remove_dir_all(base);
+}
+
 pub fn new_job(base: &str, id: usize, buffer: &mut [u8]) -> (usize, String, String) {
-    let mut stdout = String::from(base) + "/stdout_";
-    let mut stderr = String::from(base) + "/stderr_";
+    let mut stdout = String::from(base);
+    stdout.push(MAIN_SEPARATOR);
+    let mut stderr = stdout.clone();
+    stdout.push_str("stdout_");
+    stderr.push_str("stderr_");
     let truncate_value = stdout.len();
     let start_indice = id.numtoa(10, buffer);
     for byte in &buffer[start_indice..] {
@@ -36,3 +112,39 @@ pub fn next_job_path(id: usize, truncate: usize, buffer: &mut [u8], stdout: &mut
         stderr.push(*byte as char);
     }
 }
+
+/// Returns `true` if the error was caused by the disk, or the user's quota of it, being full.
+pub fn is_full(why: &io::Error) -> bool {
+    why.kind() == io::ErrorKind::StorageFull || why.kind() == io::ErrorKind::QuotaExceeded
+}
+
+/// Creates a file within the fallback directory to continue the temporary file at `path`, after the tempdir
+/// has run out of space. The fallback directory is created, with a warning, the first time that it is needed.
+pub fn overflow(path: &str) -> io::Result<(String, File)> {
+    let directory = {
+        let mut fallback = FALLBACK.lock().unwrap();
+        match *fallback {
+            Some(ref directory) => directory.clone(),
+            None => {
+                // The files are written within a directory that is named after the run's directory.
+                let tempdir = Path::new(path).parent().unwrap_or_else(|| Path::new(path));
+                let directory = fallback_base().and_then(|base| tempdir.file_name().map(|name| base.join(name)))
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no directory is available to fall back to"))?;
+                fs::create_dir_all(&directory)?;
+                let stderr = io::stderr();
+                let _ = writeln!(stderr.lock(), "parallel: the tempdir {:?} is full, so temporary files will be \
+                    written within {:?}", tempdir, directory);
+                *fallback = Some(directory.clone());
+                directory
+            }
+        }
+    };
+
+    let name = Path::new(path).file_name().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput,
+        "the temporary file has no name"))?;
+    let path = directory.join(name);
+    let file = File::create(&path)?;
+    let path = path.to_str().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData,
+        "the fallback directory is not valid UTF-8"))?.to_owned();
+    Ok((path, file))
+}
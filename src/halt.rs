@@ -0,0 +1,273 @@
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use input_iterator::UNKNOWN_TOTAL;
+use regex::bytes::Regex;
+use running;
+
+/// Determines what happens to running jobs once the halting condition has been met.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum When {
+    /// No more jobs will be started, but running jobs will be allowed to finish.
+    Soon,
+    /// No more jobs will be started, and running jobs will be killed.
+    Now,
+}
+
+/// The kind of job completion that is counted towards the halting threshold.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Condition {
+    /// Counts jobs which have failed.
+    Fail,
+    /// Counts jobs which have succeeded.
+    Success,
+    /// Counts every job which has completed.
+    Done,
+}
+
+/// The number of counted jobs that will trigger a halt.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Threshold {
+    /// A number of jobs.
+    Count(usize),
+    /// A percentage of the total number of jobs.
+    Percent(f64),
+}
+
+/// The policy given to the `--halt` parameter, such as `soon,fail=1` or `now,done=50%`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Halt {
+    pub when:      When,
+    pub condition: Condition,
+    pub threshold: Threshold,
+}
+
+impl Halt {
+    /// Parses a halting policy, where `never` disables halting and returns `None`.
+    pub fn parse(input: &str) -> Result<Option<Halt>, ()> {
+        if input == "never" { return Ok(None) }
+
+        let (when, rule) = match input.find(',') {
+            Some(pos) => (&input[..pos], &input[pos+1..]),
+            None      => return Err(())
+        };
+
+        let when = match when {
+            "soon" => When::Soon,
+            "now"  => When::Now,
+            _      => return Err(())
+        };
+
+        let (condition, threshold) = match rule.find('=') {
+            Some(pos) => (&rule[..pos], &rule[pos+1..]),
+            None      => return Err(())
+        };
+
+        let condition = match condition {
+            "fail"    => Condition::Fail,
+            "success" => Condition::Success,
+            "done"    => Condition::Done,
+            _         => return Err(())
+        };
+
+        let threshold = if threshold.ends_with('%') {
+            match threshold[..threshold.len()-1].parse::<f64>() {
+                Ok(percent) if percent > 0f64 && percent <= 100f64 => Threshold::Percent(percent),
+                _ => return Err(())
+            }
+        } else {
+            match threshold.parse::<usize>() {
+                Ok(count) if count != 0 => Threshold::Count(count),
+                _ => return Err(())
+            }
+        };
+
+        Ok(Some(Halt { when: when, condition: condition, threshold: threshold }))
+    }
+}
+
+/// Parses the pattern given to the `--halt-on-output` parameter, which may be prefixed with `soon,` or
+/// `now,` to determine what happens to running jobs, defaulting to `soon`.
+pub fn parse_output(input: &str) -> Result<(When, Regex), String> {
+    let (when, pattern) = if input.starts_with("soon,") {
+        (When::Soon, &input[5..])
+    } else if input.starts_with("now,") {
+        (When::Now, &input[4..])
+    } else {
+        (When::Soon, input)
+    };
+
+    Regex::new(pattern).map(|regex| (when, regex)).map_err(|why| why.to_string())
+}
+
+/// Tracks the completion of jobs across all threads, and records when the halting policy has been met.
+pub struct Halting {
+    policy:    Option<Halt>,
+    /// The pattern given by the `--halt-on-output` parameter, which halts the run once a job writes it.
+    output:    Option<(When, Regex)>,
+    total:     usize,
+    done:      AtomicUsize,
+    failed:    AtomicUsize,
+    halted:    AtomicBool,
+    /// Set when the run was halted with `now`, so that running jobs are killed.
+    killing:   AtomicBool,
+    /// The exit status of the run, once it has been halted by the failure of a job, by its output, or by a worker.
+    status:    Mutex<Option<i32>>,
+}
+
+impl Halting {
+    pub fn new(policy: Option<Halt>, output: Option<(When, Regex)>, total: usize) -> Halting {
+        Halting {
+            policy:  policy,
+            output:  output,
+            total:   total,
+            done:    AtomicUsize::new(0),
+            failed:  AtomicUsize::new(0),
+            halted:  AtomicBool::new(false),
+            killing: AtomicBool::new(false),
+            status:  Mutex::new(None),
+        }
+    }
+
+    /// Returns `true` if no more jobs should be started.
+    pub fn halted(&self) -> bool { self.halted.load(Ordering::SeqCst) }
+
+    /// Returns the exit status of the job whose failure met the halting policy, if the run was halted by it,
+    /// or 1 if the run was halted by the `--halt-on-output` pattern or by a worker.
+    pub fn status(&self) -> Option<i32> { *self.status.lock().unwrap() }
+
+    /// Returns `true` if the outputs of jobs must be scanned for the `--halt-on-output` pattern.
+    pub fn scans_output(&self) -> bool { self.output.is_some() }
+
+    /// Records that a job has been started with the given process ID. If halting now, it will be killed immediately.
+    pub fn started(&self, pid: u32) {
+        if self.killing.load(Ordering::SeqCst) { running::terminate(pid); }
+    }
+
+    /// Checks a line written by the given job against the `--halt-on-output` pattern, halting if it matches.
+    pub fn output_line(&self, job_id: usize, line: &[u8]) {
+        if let Some((when, ref pattern)) = self.output {
+            if pattern.is_match(line) && !self.halted()
+                && self.halt(when, format_args!("job {} printed a line matching '{}'", job_id+1, pattern.as_str()))
+            {
+                *self.status.lock().unwrap() = Some(1);
+            }
+        }
+    }
+
+    /// Halts the run now on behalf of a worker which can not start its next job, such as when the `--memfree-timeout`
+    /// has been reached, so that the run will be shut down normally and exit with a status of 1.
+    pub fn abort(&self, reason: fmt::Arguments) {
+        if self.halt(When::Now, reason) { *self.status.lock().unwrap() = Some(1); }
+    }
+
+    /// Records the result of a job, halting if the policy's threshold has been reached.
+    pub fn report(&self, exit_value: i32, signal: i32) {
+        let policy = match self.policy {
+            Some(policy) => policy,
+            None         => return
+        };
+
+        let succeeded = exit_value == 0 && signal == 0;
+
+        let done   = self.done.fetch_add(1, Ordering::SeqCst) + 1;
+        let failed = if succeeded {
+            self.failed.load(Ordering::SeqCst)
+        } else {
+            self.failed.fetch_add(1, Ordering::SeqCst) + 1
+        };
+
+        let (counted, description) = match policy.condition {
+            Condition::Fail    => (failed, "failed"),
+            Condition::Success => (done - failed, "succeeded"),
+            Condition::Done    => (done, "completed"),
+        };
+
+        let reached = match policy.threshold {
+            Threshold::Count(count) => counted >= count,
+            // The percentage can not be determined if the total number of jobs is unknown.
+            Threshold::Percent(_) if self.total == UNKNOWN_TOTAL || self.total == 0 => false,
+            Threshold::Percent(percent) => counted as f64 * 100f64 >= percent * self.total as f64,
+        };
+
+        if !reached { return }
+        let jobs = if counted == 1 { "job has" } else { "jobs have" };
+        if self.halt(policy.when, format_args!("{} {} {}", counted, jobs, description)) && !succeeded
+            && policy.condition == Condition::Fail
+        {
+            // The run exits with the status of the job which failed, or that of a shell if it was killed.
+            let status = match (exit_value, signal) {
+                (_, signal) if signal != 0 => 128 + signal,
+                (exit_value, _) if exit_value > 0 && exit_value < 256 => exit_value,
+                _ => 1
+            };
+            *self.status.lock().unwrap() = Some(status);
+        }
+    }
+
+    /// Stops the starting of jobs, and kills the running jobs if halting now, unless already halted.
+    /// Returns `true` if the run was halted by this call.
+    fn halt(&self, when: When, reason: fmt::Arguments) -> bool {
+        if self.halted.swap(true, Ordering::SeqCst) { return false }
+        let stderr = io::stderr();
+        let mut stderr = stderr.lock();
+        if when == When::Now {
+            self.killing.store(true, Ordering::SeqCst);
+            let _ = writeln!(stderr, "parallel: halting: {}, killing the running jobs", reason);
+            running::terminate_all();
+        } else {
+            let _ = writeln!(stderr, "parallel: halting: {}, starting no more jobs", reason);
+        }
+        true
+    }
+}
+
+#[test]
+fn halt_parsing() {
+    assert_eq!(Halt::parse("never"), Ok(None));
+    assert_eq!(Halt::parse("soon,done=50%"), Ok(Some(Halt {
+        when: When::Soon, condition: Condition::Done, threshold: Threshold::Percent(50f64)
+    })));
+    assert_eq!(Halt::parse("now,success=1"), Ok(Some(Halt {
+        when: When::Now, condition: Condition::Success, threshold: Threshold::Count(1)
+    })));
+    assert!(Halt::parse("later,fail=1").is_err());
+    assert!(Halt::parse("soon,fail=0").is_err());
+}
+
+#[test]
+fn halt_threshold() {
+    let halting = Halting::new(Halt::parse("soon,done=50%").unwrap(), None, 4);
+    halting.report(1, 0);
+    assert!(!halting.halted());
+    halting.report(0, 0);
+    assert!(halting.halted());
+    assert_eq!(halting.status(), None);
+
+    let halting = Halting::new(Halt::parse("now,fail=2").unwrap(), None, 4);
+    halting.report(3, 0);
+    halting.report(0, 0);
+    assert!(!halting.halted());
+    halting.report(-1, 15);
+    assert!(halting.halted());
+    assert_eq!(halting.status(), Some(143));
+}
+
+#[test]
+fn halt_on_output() {
+    let (when, pattern) = parse_output("now,^PANIC").unwrap();
+    assert_eq!(when, When::Now);
+    assert_eq!(pattern.as_str(), "^PANIC");
+    assert_eq!(parse_output("a,b").unwrap().1.as_str(), "a,b");
+    assert!(parse_output("soon,(").is_err());
+
+    let halting = Halting::new(None, Some(parse_output("PANIC").unwrap()), 4);
+    halting.report(1, 0);
+    halting.output_line(0, b"no panic here");
+    assert!(!halting.halted());
+    assert_eq!(halting.status(), None);
+    halting.output_line(0, b"thread main: PANIC");
+    assert!(halting.halted());
+    assert_eq!(halting.status(), Some(1));
+}
@@ -1,6 +1,6 @@
 use disk_buffer::*;
 use arguments::errors::{FileErr};
-use super::InputIteratorErr;
+use super::{InputIteratorErr, UNKNOWN_TOTAL};
 use itoa;
 use time;
 use std::io::{self, Write, Read};
@@ -46,24 +46,36 @@ impl ETA {
 /// stored in memory are depleted.
 pub struct InputIterator<IO: Read> {
     pub total_arguments: usize,
+    /// The total that is displayed while `total_arguments` is not yet known, as given by `--total-jobs`.
+    pub expected_total:  usize,
     pub curr_argument:   usize,
     pub completed:       usize,
+    /// The number of times that each input is given to a job, as given by the `--repeat` parameter.
+    pub repeat:          usize,
+    /// The number of inputs that have been read, which differs from `curr_argument` if inputs are repeated.
+    curr_input:          usize,
+    /// The input that is being repeated.
+    repeated:            String,
     start_time:          u64,
     average_time:        u64,
     input_buffer:        InputBuffer<IO>,
 }
 
 impl<IO: Read> InputIterator<IO> {
-    pub fn new(path: &Path, file: IO, args: usize) -> Result<InputIterator<IO>, FileErr> {
+    pub fn new(path: &Path, file: IO, args: usize, buffer_size: usize) -> Result<InputIterator<IO>, FileErr> {
         // Create an `InputBuffer` from the unprocessed file.
-        let disk_buffer = DiskBufferReader::new(path, file);
+        let disk_buffer = DiskBufferReader::new(path, file, buffer_size);
 
         let input_buffer = InputBuffer::new(disk_buffer)?;
 
         Ok(InputIterator {
             total_arguments: args,
+            expected_total:  args,
             curr_argument:   0,
             completed:       0,
+            repeat:          1,
+            curr_input:      0,
+            repeated:        String::new(),
             input_buffer:    input_buffer,
             start_time:      time::precise_time_ns(),
             average_time:    0,
@@ -71,46 +83,67 @@ impl<IO: Read> InputIterator<IO> {
     }
 
     fn buffer(&mut self) -> Result<(), InputIteratorErr> {
-        // Read the next set of arguments from the unprocessed file, but only read as many bytes
-        // as the buffer can hold without overwriting the unused bytes that was shifted to the left.
-        self.input_buffer.disk_buffer.buffer(self.input_buffer.capacity).map_err(|why| {
+        // Update the recorded number of arguments and indices from the next set of arguments.
+        self.input_buffer.start = self.input_buffer.end + 1;
+        self.input_buffer.fill().map_err(|why| {
             InputIteratorErr::FileRead(PathBuf::from(self.input_buffer.disk_buffer.path.clone()), why)
         })?;
-        let bytes_read = self.input_buffer.disk_buffer.capacity;
-
-        // Update the recorded number of arguments and indices.
-        self.input_buffer.start = self.input_buffer.end + 1;
-        count_arguments(&mut self.input_buffer, bytes_read);
         self.input_buffer.index = 0;
         Ok(())
     }
 
+    /// The total number of inputs, or the expected total if the inputs are streamed and have not been exhausted.
+    pub fn total(&self) -> usize {
+        if self.total_arguments == UNKNOWN_TOTAL { self.expected_total } else { self.total_arguments }
+    }
+
     pub fn eta(&self) -> ETA {
-        let left = self.total_arguments as u64 - self.completed as u64;
+        let left = (self.total() as u64).saturating_sub(self.completed as u64);
         ETA {
             left: left,
-            time: left * self.average_time,
+            time: left.saturating_mul(self.average_time),
             average: self.average_time
         }
     }
 
+    /// Skips the given number of inputs, such as those whose jobs were completed before a checkpoint.
+    pub fn skip_inputs(&mut self, count: usize, buffer: &mut String) -> Result<(), InputIteratorErr> {
+        for _ in 0..count {
+            match self.next_value(buffer) {
+                Some(Ok(())) => (),
+                Some(Err(why)) => return Err(why),
+                None => break
+            }
+        }
+        Ok(())
+    }
+
     pub fn next_value(&mut self, buffer: &mut String) -> Option<Result<(), InputIteratorErr>> {
+        let range = match self.advance()? {
+            Ok(range) => range,
+            Err(why) => return Some(Err(why))
+        };
+
+        // Copy the input from the buffer, or the input that is being repeated, into a `String`.
+        buffer.truncate(0);
+        match range {
+            Some((start, end)) => {
+                unsafe { buffer.push_str(str::from_utf8_unchecked(&self.input_buffer.disk_buffer.data[start..end])); }
+                if self.repeat > 1 { self.repeated.clone_from(buffer); }
+            },
+            None => buffer.push_str(&self.repeated)
+        }
+        Some(Ok(()))
+    }
+
+    /// Advances to the next job, returning the range of its input within the buffer, or `None` if the
+    /// previous input is being repeated by the `--repeat` parameter.
+    fn advance(&mut self) -> Option<Result<Option<(usize, usize)>, InputIteratorErr>> {
         if self.curr_argument == self.total_arguments {
             // If all arguments have been depleted, return `None`.
             return None
-        } else if self.curr_argument == self.input_buffer.end {
-            // If the next argument is not stored in the internal buffer, update the buffer.
-            if let Err(err) = self.buffer() { return Some(Err(err)); }
         }
 
-        // Obtain the start and end indices to know where to find the input in the array.
-        let end   = self.input_buffer.indices[self.input_buffer.index + 1];
-        let start = if self.input_buffer.index == 0 {
-            self.input_buffer.indices[self.input_buffer.index]
-        } else {
-            self.input_buffer.indices[self.input_buffer.index] + 1
-        };
-
         // Update times
         match self.completed {
             0 => (),
@@ -118,28 +151,21 @@ impl<IO: Read> InputIterator<IO> {
             _ => self.average_time = (time::precise_time_ns() - self.start_time) / self.completed as u64,
         }
 
-        // Increment the iterator's state.
-        self.curr_argument      += 1;
-        self.input_buffer.index += 1;
-
-        // Copy the input from the buffer into a `String` and return it
-        buffer.truncate(0);
-        unsafe { buffer.push_str(str::from_utf8_unchecked(&self.input_buffer.disk_buffer.data[start..end])); }
-        Some(Ok(()))
-    }
-}
-
-// Implement the `Iterator` trait for `InputIterator` to gain access to all the `Iterator` methods for free.
-impl<IO: Read> Iterator for InputIterator<IO> {
-    type Item = Result<String, InputIteratorErr>;
+        // Each input is only read once, and is then given to each of its repetitions.
+        if self.curr_argument % self.repeat != 0 {
+            self.curr_argument += 1;
+            return Some(Ok(None))
+        }
 
-    fn next(&mut self) -> Option<Result<String, InputIteratorErr>> {
-        if self.curr_argument == self.total_arguments {
-            // If all arguments have been depleted, return `None`.
-            return None
-        } else if self.curr_argument == self.input_buffer.end {
+        if self.curr_input == self.input_buffer.end {
             // If the next argument is not stored in the internal buffer, update the buffer.
             if let Err(err) = self.buffer() { return Some(Err(err)); }
+
+            // If no inputs could be buffered, then the source of inputs has been depleted.
+            if self.curr_input == self.input_buffer.end {
+                self.total_arguments = self.curr_argument;
+                return None
+            }
         }
 
         // Obtain the start and end indices to know where to find the input in the array.
@@ -150,19 +176,28 @@ impl<IO: Read> Iterator for InputIterator<IO> {
             self.input_buffer.indices[self.input_buffer.index] + 1
         };
 
-        // Update times
-        match self.completed {
-            0 => (),
-            1 => self.average_time = time::precise_time_ns() - self.start_time,
-            _ => self.average_time = (time::precise_time_ns() - self.start_time) / self.completed as u64,
-        }
-
         // Increment the iterator's state.
-        self.curr_argument       += 1;
-        self.input_buffer.index  += 1;
+        self.curr_argument      += 1;
+        self.curr_input         += 1;
+        self.input_buffer.index += 1;
+        Some(Ok(Some((start, end))))
+    }
+}
 
-        // Copy the input from the buffer into a `String` and return it
-        Some(Ok(String::from_utf8_lossy(&self.input_buffer.disk_buffer.data[start..end]).into_owned()))
+// Implement the `Iterator` trait for `InputIterator` to gain access to all the `Iterator` methods for free.
+impl<IO: Read> Iterator for InputIterator<IO> {
+    type Item = Result<String, InputIteratorErr>;
+
+    fn next(&mut self) -> Option<Result<String, InputIteratorErr>> {
+        match self.advance()? {
+            Ok(Some((start, end))) => {
+                let input = String::from_utf8_lossy(&self.input_buffer.disk_buffer.data[start..end]).into_owned();
+                if self.repeat > 1 { self.repeated.clone_from(&input); }
+                Some(Ok(input))
+            },
+            Ok(None) => Some(Ok(self.repeated.clone())),
+            Err(why) => Some(Err(why))
+        }
     }
 }
 
@@ -173,48 +208,57 @@ struct InputBuffer<IO: Read> {
     index:       usize,
     start:       usize,
     end:         usize,
+    /// The number of bytes in the buffer which belong to complete inputs, including their newlines.
     capacity:    usize,
     disk_buffer: DiskBufferReader<IO>,
-    indices:     [usize; BUFFER_SIZE / 2],
+    indices:     Vec<usize>,
 }
 
 impl<IO: Read> InputBuffer<IO> {
     /// Takes ownership of a `DiskBufferReader` and transforms it into a higher level
     /// `InputBuffer` which will track additional information about the disk buffer.
-    fn new(mut unprocessed: DiskBufferReader<IO>) -> Result<InputBuffer<IO>, FileErr> {
-        unprocessed.buffer(0).map_err(|why| FileErr::Read(unprocessed.path.clone(), why))?;
-        let bytes_read = unprocessed.capacity;
-
+    fn new(unprocessed: DiskBufferReader<IO>) -> Result<InputBuffer<IO>, FileErr> {
         let mut temp = InputBuffer {
             index:       0,
             start:       0,
             end:         0,
             capacity:    0,
             disk_buffer: unprocessed,
-            indices:     [0usize; BUFFER_SIZE / 2]
+            indices:     Vec::with_capacity(BUFFER_SIZE / 2),
         };
 
-        count_arguments(&mut temp, bytes_read);
+        temp.fill().map_err(|why| FileErr::Read(temp.disk_buffer.path.clone(), why))?;
         Ok(temp)
     }
 
-
+    /// Discards the inputs that have been consumed, and buffers the next set of inputs. An input which
+    /// is longer than the buffer is read across as many refills as are needed, with the buffer growing to
+    /// hold it, so that at least one complete input is buffered unless the file has been exhausted.
+    fn fill(&mut self) -> io::Result<()> {
+        let mut bytes_used = self.capacity;
+        loop {
+            let bytes_read = self.disk_buffer.buffer(bytes_used)?;
+            if count_arguments(self) != 0 || bytes_read == 0 { return Ok(()); }
+            bytes_used = 0;
+        }
+    }
 }
 
 /// Counts the number of arguments that are stored in the buffer, marking the location of
 /// the indices and the actual capacity of the buffer's useful information.
-fn count_arguments<IO: Read>(buffer: &mut InputBuffer<IO>, bytes_read: usize) {
-    let mut newlines = 1;
-    buffer.capacity  = 0;
+fn count_arguments<IO: Read>(buffer: &mut InputBuffer<IO>) -> usize {
+    let bytes_read = buffer.disk_buffer.capacity;
+    buffer.indices.clear();
+    buffer.indices.push(0);
 
     for (indice, _) in buffer.disk_buffer.data.iter().take(bytes_read).enumerate().filter(|&(_, byte)| *byte == b'\n') {
-        buffer.indices[newlines] = indice;
-        newlines += 1;
+        buffer.indices.push(indice);
     }
 
-    newlines -= 1;
-    buffer.capacity = buffer.indices[newlines];
+    let newlines = buffer.indices.len() - 1;
+    buffer.capacity = if newlines == 0 { 0 } else { buffer.indices[newlines] + 1 };
     buffer.end += newlines;
+    newlines
 }
 
 #[cfg(test)]
@@ -225,11 +269,34 @@ mod tests {
     #[test]
     fn test_input_iterator() {
         let file = File::open("tests/buffer.dat").unwrap();
-        let iterator = InputIterator::new(Path::new("tests/buffer.dat"), file, 4096).unwrap();
+        let iterator = InputIterator::new(Path::new("tests/buffer.dat"), file, 4096, BUFFER_SIZE).unwrap();
         assert_eq!(0, iterator.input_buffer.start);
         assert_eq!(1859, iterator.input_buffer.end);
         for (actual, expected) in iterator.zip((1..4096)) {
             assert_eq!(actual.unwrap(), expected.to_string());
         }
     }
+
+    #[test]
+    fn test_input_iterator_repeat() {
+        let file = File::open("tests/buffer.dat").unwrap();
+        let mut iterator = InputIterator::new(Path::new("tests/buffer.dat"), file, 6, BUFFER_SIZE).unwrap();
+        iterator.repeat = 3;
+        let mut buffer = String::new();
+        iterator.next_value(&mut buffer).unwrap().unwrap();
+        assert_eq!(buffer, "1");
+        let inputs = iterator.map(Result::unwrap).collect::<Vec<_>>();
+        assert_eq!(inputs, vec!["1", "1", "2", "2", "2"]);
+    }
+
+    #[test]
+    fn test_input_iterator_long_inputs() {
+        let long = "a".repeat(BUFFER_SIZE * 3);
+        let data = format!("1\n{}\n2\n{}\n", long, long);
+        for &size in &[16, BUFFER_SIZE] {
+            let iterator = InputIterator::new(Path::new("long"), data.as_bytes(), 4, size).unwrap();
+            let inputs = iterator.map(Result::unwrap).collect::<Vec<_>>();
+            assert_eq!(inputs, vec!["1".to_owned(), long.clone(), "2".to_owned(), long.clone()]);
+        }
+    }
 }
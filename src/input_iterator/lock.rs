@@ -1,28 +1,80 @@
-use arguments;
-use super::{InputIterator, InputIteratorErr};
-use sys_info;
+use arguments::Config;
+use halt::Halting;
+use memory;
+use misc::Random;
+use running;
+use super::{InputIterator, InputIteratorErr, StartRate};
 
+use std::cmp;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::io::{self, Read, Write};
 use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The maximum number of times that the backoff delay may be doubled.
+const MAX_BACKOFF_SHIFT: usize = 6;
 
 pub struct InputsLock<IO: Read> {
     pub inputs:    Arc<Mutex<InputIterator<IO>>>,
+    /// The slot of the worker that is obtaining inputs.
+    pub slot:      usize,
+    /// The number of jobs that may run at once, which may be changed with the file given to `--jobs`.
+    pub jobs:      Arc<AtomicUsize>,
     pub memory:    u64,
+    /// The interval at which available memory is checked while waiting for memory to become available.
+    pub mem_poll:  Duration,
+    /// How long to wait for memory to become available before failing, or forever if zero.
+    pub mem_timeout: Duration,
     pub delay:     Duration,
+    pub jitter:    Duration,
     pub has_delay: bool,
     pub completed: bool,
-    pub flags:     u16
+    pub config:    Config,
+    /// Counts the number of consecutive jobs that have failed, which is shared by all threads.
+    pub failures:  Arc<AtomicUsize>,
+    pub random:    Random,
+    /// The halting policy given by the `--halt` parameter, which is shared by all threads.
+    pub halt:      Option<Arc<Halting>>,
+    /// Limits the rate at which jobs are started by all threads, as given by `--max-starts-per-second`.
+    pub start_rate: Option<Arc<StartRate>>,
+    /// The ID of the job that was last dispatched to this worker, which is recorded as an error if the worker
+    /// panics before its result has been sent. Its input remains within the worker's input buffer.
+    pub dispatched: Option<usize>,
+    /// Whether the result of the last dispatched job has been sent to the receiver.
+    pub reported:  bool,
+    /// Whether the job log entry of the last dispatched job has been sent to the receiver.
+    pub logged:    bool,
+    /// The process ID of the job that this worker is running, which is killed if the worker panics.
+    pub child:     Option<u32>,
 }
 
 impl<IO: Read> InputsLock<IO> {
     /// Attempts to obtain the next input in the queue, returning `None` when it is finished.
     /// It works the same as the `Iterator` trait's `next()` method, only re-using the same input buffer.
     pub fn try_next(&mut self, input: &mut String) -> Option<(usize)> {
+        // Once the halting policy has been met, no more jobs will be started.
+        if self.halt.as_ref().map_or(false, |halt| halt.halted()) { return None }
+
+        // No jobs will be started while the run is suspended.
+        while running::paused() { thread::sleep(Duration::from_millis(100)); }
+
+        // If the number of jobs has been lowered, workers in the slots beyond it will wait until it is raised.
+        if self.slot > self.jobs.load(Ordering::SeqCst) {
+            log!(Debug, "slot {} is waiting until the number of jobs is raised", self.slot);
+        }
+        while self.slot > self.jobs.load(Ordering::SeqCst) {
+            {
+                let inputs = self.inputs.lock().unwrap();
+                if inputs.curr_argument == inputs.total_arguments { return None }
+            }
+            if self.halt.as_ref().map_or(false, |halt| halt.halted()) { return None }
+            thread::sleep(Duration::from_millis(100));
+        }
+
         let mut inputs = self.inputs.lock().unwrap();
         let job_id = inputs.curr_argument;
-        if self.flags & arguments::ETA != 0 {
+        if self.config.eta {
             if self.completed {
                 inputs.completed += 1;
             } else {
@@ -31,20 +83,37 @@ impl<IO: Read> InputsLock<IO> {
             inputs.eta().write_to_stderr(inputs.completed);
         }
 
-        if self.has_delay { thread::sleep(self.delay); }
+        // There is no need to delay if there are no more inputs to process.
+        let inputs_remaining = inputs.curr_argument != inputs.total_arguments;
 
-        if self.memory > 0 {
-            if let Ok(mut mem_available) = sys_info::mem_info().map(|mem_info| mem_info.avail * 1000) {
-                while mem_available < self.memory {
-                    thread::sleep(Duration::from_millis(100));
-                    if let Ok(mem_info) = sys_info::mem_info() { mem_available = mem_info.avail * 1000; }
-                }
+        if self.has_delay && inputs_remaining {
+            // A random amount of jitter will be added to the delay if a range was given.
+            let jitter = self.random.up_to(duration_to_millis(self.jitter));
+            thread::sleep(self.delay + Duration::from_millis(jitter));
+        }
+
+        if self.config.delay_backoff && inputs_remaining {
+            let failures = self.failures.load(Ordering::SeqCst);
+            if failures != 0 {
+                let backoff = self.backoff(failures);
+                log!(Debug, "delaying the next job by {:?} after {} consecutive failures", backoff, failures);
+                thread::sleep(backoff);
             }
         }
 
+        if self.memory > 0 && !self.wait_for_memory() { return None }
+
+        if let (Some(ref rate), true) = (self.start_rate.as_ref(), inputs_remaining) { rate.acquire(); }
+
         match inputs.next_value(input) {
             None            => None,
-            Some(Ok(()))    => Some(job_id),
+            Some(Ok(()))    => {
+                log!(Trace, "dispatching job {} to slot {}: {}", job_id+1, self.slot, input);
+                self.dispatched = Some(job_id);
+                self.reported = false;
+                self.logged = false;
+                Some(job_id)
+            },
             Some(Err(why))  => {
                 let stderr = io::stderr();
                 let stderr = &mut stderr.lock();
@@ -57,4 +126,81 @@ impl<IO: Read> InputsLock<IO> {
             }
         }
     }
+
+    /// Blocks until the memory given by the `--memfree` parameter is available. If the `--memfree-timeout`
+    /// is reached first, the run will be halted now, terminating the running jobs, and `false` is returned.
+    fn wait_for_memory(&self) -> bool {
+        let start = Instant::now();
+        let mut waited = false;
+        while let Some(available) = memory::available() {
+            if available >= self.memory {
+                if waited {
+                    log!(Debug, "{} bytes of memory became available after {:?}", available, start.elapsed());
+                }
+                break
+            }
+
+            if waited {
+                log!(Trace, "{} of {} bytes of memory are available", available, self.memory);
+            } else {
+                log!(Debug, "waiting for {} bytes of memory to become available, as only {} bytes are available",
+                    self.memory, available);
+                waited = true;
+            }
+
+            if self.mem_timeout != Duration::from_millis(0) && start.elapsed() >= self.mem_timeout {
+                // The run is halted rather than exited, so that the records of the completed jobs will be written.
+                if let Some(ref halt) = self.halt {
+                    halt.abort(format_args!("timed out waiting for {} bytes of memory to become available, as only \
+                        {} bytes are available", self.memory, available));
+                }
+                return false
+            }
+
+            // Another worker may have halted the run while this worker was waiting.
+            if self.halt.as_ref().map_or(false, |halt| halt.halted()) { return false }
+
+            thread::sleep(self.mem_poll);
+        }
+        true
+    }
+
+    /// Records that a job has been started with the given process ID.
+    pub fn started(&mut self, pid: u32) {
+        self.child = Some(pid);
+        running::register(pid);
+        if let Some(ref halt) = self.halt { halt.started(pid); }
+    }
+
+    /// Records that the job with the given process ID has exited.
+    pub fn exited(&mut self, pid: u32) {
+        self.child = None;
+        running::unregister(pid);
+    }
+
+    /// Records the exit value and signal of the last job, so that the backoff delay may be adjusted,
+    /// and so that the halting policy may be checked.
+    pub fn report(&mut self, exit_value: i32, signal: i32) {
+        let succeeded = exit_value == 0 && signal == 0;
+        if let Some(ref halt) = self.halt { halt.report(exit_value, signal); }
+
+        if self.config.delay_backoff {
+            if succeeded {
+                self.failures.store(0, Ordering::SeqCst);
+            } else {
+                self.failures.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+    }
+
+    /// The backoff delay doubles with each consecutive failure, starting from the `--delay` value,
+    /// or one second if no delay was given.
+    fn backoff(&self, failures: usize) -> Duration {
+        let base = match duration_to_millis(self.delay) { 0 => 1000, millis => millis };
+        Duration::from_millis(base << cmp::min(failures - 1, MAX_BACKOFF_SHIFT))
+    }
+}
+
+fn duration_to_millis(duration: Duration) -> u64 {
+    duration.as_secs() * 1000 + (duration.subsec_nanos() / 1_000_000) as u64
 }
@@ -1,8 +1,12 @@
 mod lock;
 mod iterator;
+mod rate;
+mod stream;
 
 pub use self::lock::InputsLock;
 pub use self::iterator::{InputIterator, ETA};
+pub use self::rate::StartRate;
+pub use self::stream::{InputStream, UNKNOWN_TOTAL};
 
 use std::io;
 use std::path::PathBuf;
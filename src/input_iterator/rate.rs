@@ -0,0 +1,58 @@
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Limits the rate at which jobs are started across every worker, as given by the `--max-starts-per-second`
+/// parameter. Starts are taken from a token bucket which holds up to one second of starts, and which is
+/// refilled at the given rate, so that a burst of starts may occur before starts are spread out.
+pub struct StartRate {
+    /// The number of starts that are added to the bucket each second.
+    rate:     f64,
+    /// The largest number of starts that the bucket may hold.
+    capacity: f64,
+    bucket:   Mutex<Bucket>,
+}
+
+struct Bucket {
+    /// The number of starts that are available, which may be a fraction of a start.
+    tokens:  f64,
+    /// When the bucket was last refilled.
+    updated: Instant,
+}
+
+impl StartRate {
+    pub fn new(rate: f64) -> StartRate {
+        let capacity = rate.max(1f64);
+        StartRate { rate: rate, capacity: capacity, bucket: Mutex::new(Bucket { tokens: capacity, updated: Instant::now() }) }
+    }
+
+    /// Blocks until a job may be started. Workers that are waiting will start in the order that they called.
+    pub fn acquire(&self) {
+        let mut bucket = self.bucket.lock().unwrap();
+        loop {
+            let now = Instant::now();
+            let elapsed = now.duration_since(bucket.updated);
+            let elapsed = elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 / 1_000_000_000f64;
+            bucket.tokens = (bucket.tokens + elapsed * self.rate).min(self.capacity);
+            bucket.updated = now;
+
+            if bucket.tokens >= 1f64 {
+                bucket.tokens -= 1f64;
+                return
+            }
+
+            let wait = (1f64 - bucket.tokens) / self.rate;
+            thread::sleep(Duration::from_nanos((wait * 1_000_000_000f64) as u64 + 1));
+        }
+    }
+}
+
+#[test]
+fn start_rate() {
+    let rate = StartRate::new(100f64);
+    let start = Instant::now();
+    for _ in 0..100 { rate.acquire(); }
+    assert!(start.elapsed() < Duration::from_millis(40));
+    for _ in 0..5 { rate.acquire(); }
+    assert!(start.elapsed() >= Duration::from_millis(40));
+}
@@ -0,0 +1,263 @@
+use arguments::Filters;
+use queue::WorkQueue;
+use raw;
+use std::cmp;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::thread;
+use std::time::Duration;
+
+/// The number of inputs that may be queued before the standard input reader blocks.
+const QUEUE_SIZE: usize = 1024;
+
+/// How often a file that is followed by the `--follow` parameter is checked for new lines.
+const FOLLOW_POLL: Duration = Duration::from_millis(100);
+
+/// When streaming inputs, the total number of inputs is not known until the standard input has
+/// been exhausted, so the `InputIterator` will run until its source has been depleted.
+pub const UNKNOWN_TOTAL: usize = ::std::usize::MAX;
+
+/// A source of inputs which is fed by a background thread that reads from the standard input, or
+/// that follows a file, allowing jobs to begin executing while inputs are still being read.
+pub struct InputStream {
+    receiver: Receiver<Vec<u8>>,
+    pending:  Vec<u8>,
+    /// Inputs which do not satisfy the `--filter` expressions are discarded as they are received.
+    filters:  Filters,
+}
+
+impl InputStream {
+    /// Spawns a thread which reads inputs from the standard input, grouping every `max_args`
+    /// inputs into a single input, and transforming each line with `parse_line`.
+    pub fn spawn<F>(max_args: usize, parse_line: F) -> InputStream
+        where F: Fn(String) -> String + Send + 'static
+    {
+        let (sender, receiver) = sync_channel::<Vec<u8>>(QUEUE_SIZE);
+        thread::spawn(move || {
+            let stdin = io::stdin();
+            send_lines(raw::lines(stdin.lock()), &sender, max_args, parse_line);
+        });
+
+        InputStream { receiver: receiver, pending: Vec::new(), filters: Filters::default() }
+    }
+
+    /// Spawns a thread which reads inputs from the file at `path`, and which then waits for lines to be
+    /// appended to the file, like `tail -f`. The stream never ends, so the run ends once it is interrupted.
+    pub fn follow<F>(path: &Path, max_args: usize, parse_line: F) -> io::Result<InputStream>
+        where F: Fn(String) -> String + Send + 'static
+    {
+        let file = File::open(path)?;
+        let (sender, receiver) = sync_channel::<Vec<u8>>(QUEUE_SIZE);
+        thread::spawn(move || {
+            send_lines(raw::lines(BufReader::new(Follow { file: file, position: 0 })), &sender, max_args, parse_line);
+        });
+
+        Ok(InputStream { receiver: receiver, pending: Vec::new(), filters: Filters::default() })
+    }
+
+    /// Spawns a thread which reads inputs from the named pipe at `path`, which is created if it does not
+    /// exist. Inputs may be written to the pipe by any number of processes, one after another, and the
+    /// stream only ends once the line given by `end` has been written to the pipe.
+    pub fn fifo<F>(path: &Path, end: String, max_args: usize, parse_line: F) -> io::Result<InputStream>
+        where F: Fn(String) -> String + Send + 'static
+    {
+        if !path.exists() { create_fifo(path)?; }
+
+        // The pipe is also opened for writing, so that it will not be closed once its writers have closed it.
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        let (sender, receiver) = sync_channel::<Vec<u8>>(QUEUE_SIZE);
+        thread::spawn(move || {
+            let lines = raw::lines(BufReader::new(file))
+                .take_while(|line| line.as_ref().map_or(true, |line| *line != end));
+            send_lines(lines, &sender, max_args, parse_line);
+        });
+
+        Ok(InputStream { receiver: receiver, pending: Vec::new(), filters: Filters::default() })
+    }
+
+    /// Spawns a thread which claims inputs from the `queue` of the `--sqlworker` or `--queue` parameters.
+    /// Inputs are claimed one at a time, as they are needed, so that this worker will not claim inputs that
+    /// other workers could be executing. The stream ends once every input of the queue has been claimed.
+    pub fn queue(queue: WorkQueue) -> InputStream {
+        let (sender, receiver) = sync_channel::<Vec<u8>>(0);
+        thread::spawn(move || loop {
+            match queue.claim() {
+                Ok(Some(input)) => {
+                    let mut record = input.into_bytes();
+                    record.push(b'\n');
+                    if sender.send(record).is_err() { return }
+                },
+                Ok(None) => return,
+                Err(why) => {
+                    let stderr = io::stderr();
+                    let _ = writeln!(stderr.lock(), "parallel: unable to claim an input from the queue: {}", why);
+                    return
+                }
+            }
+        });
+
+        InputStream { receiver: receiver, pending: Vec::new(), filters: Filters::default() }
+    }
+
+    /// Only supplies the inputs which satisfy the given filters.
+    pub fn filtered(mut self, filters: Filters) -> InputStream {
+        self.filters = filters;
+        self
+    }
+
+    fn receive(&mut self, record: Vec<u8>) {
+        if self.filters.is_empty() || self.filters.keep_line(&record) { self.pending.extend_from_slice(&record); }
+    }
+}
+
+#[cfg(unix)]
+fn create_fifo(path: &Path) -> io::Result<()> {
+    use libc;
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|why| io::Error::new(io::ErrorKind::InvalidInput, why))?;
+    if unsafe { libc::mkfifo(path.as_ptr(), 0o600) } == 0 { Ok(()) } else { Err(io::Error::last_os_error()) }
+}
+
+#[cfg(not(unix))]
+fn create_fifo(_path: &Path) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Other, "named pipes are only supported on Unix"))
+}
+
+/// Sends each of the `lines` as an input, grouping every `max_args` lines into a single input.
+fn send_lines<I, F>(lines: I, sender: &SyncSender<Vec<u8>>, max_args: usize, parse_line: F)
+    where I: Iterator<Item = io::Result<String>>, F: Fn(String) -> String
+{
+    let max_args = cmp::max(max_args, 1);
+    let mut record = Vec::new();
+    let mut grouped = 0;
+    for line in lines {
+        let line = match line { Ok(line) => parse_line(line), Err(_) => break };
+        if line.is_empty() { continue }
+        if grouped != 0 { record.push(b' '); }
+        record.extend_from_slice(line.as_bytes());
+        grouped += 1;
+        if grouped == max_args {
+            record.push(b'\n');
+            if sender.send(record.clone()).is_err() { return }
+            record.clear();
+            grouped = 0;
+        }
+    }
+
+    if grouped != 0 {
+        record.push(b'\n');
+        let _ = sender.send(record);
+    }
+}
+
+/// Reads a file that is being appended to, which waits for more data once the end of the file is reached.
+struct Follow {
+    file:     File,
+    position: u64,
+}
+
+impl Read for Follow {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let read = self.file.read(buffer)?;
+            if read != 0 {
+                self.position += read as u64;
+                return Ok(read)
+            }
+
+            // If the file was truncated, such as when it has been emptied by a logrotate, it is read from the start.
+            if self.file.metadata()?.len() < self.position {
+                self.position = self.file.seek(SeekFrom::Start(0))?;
+                continue
+            }
+
+            thread::sleep(FOLLOW_POLL);
+        }
+    }
+}
+
+impl Read for InputStream {
+    /// Blocks until at least one input is available, and then supplies as many complete inputs
+    /// as will fit within the buffer. Returns `0` once the source of inputs has been exhausted.
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        while self.pending.is_empty() {
+            match self.receiver.recv() {
+                Ok(record) => self.receive(record),
+                Err(_)     => return Ok(0)
+            }
+        }
+
+        while self.pending.len() < buffer.len() {
+            match self.receiver.try_recv() {
+                Ok(record) => self.receive(record),
+                Err(_)     => break
+            }
+        }
+
+        // Only complete inputs are supplied, unless a single input exceeds the size of the buffer.
+        let mut end = cmp::min(buffer.len(), self.pending.len());
+        if end < self.pending.len() {
+            if let Some(newline) = self.pending[..end].iter().rposition(|&byte| byte == b'\n') {
+                end = newline + 1;
+            }
+        }
+
+        buffer[..end].copy_from_slice(&self.pending[..end]);
+        self.pending.drain(..end);
+        Ok(end)
+    }
+}
+
+#[test]
+fn follow_appended_lines() {
+    use std::env;
+    use std::fs::{self, OpenOptions};
+    use std::io::Write;
+
+    let path = env::temp_dir().join(format!("parallel-follow-{}", ::std::process::id()));
+    fs::write(&path, b"one\ntw").unwrap();
+    let mut stream = InputStream::follow(&path, 1, |line| line).unwrap();
+
+    let mut buffer = [0u8; 64];
+    let read = stream.read(&mut buffer).unwrap();
+    assert_eq!(&buffer[..read], b"one\n");
+
+    OpenOptions::new().append(true).open(&path).unwrap().write_all(b"o\nthree\n").unwrap();
+    let mut inputs = Vec::new();
+    while inputs.len() < b"two\nthree\n".len() {
+        let read = stream.read(&mut buffer).unwrap();
+        inputs.extend_from_slice(&buffer[..read]);
+    }
+    assert_eq!(inputs, b"two\nthree\n");
+    let _ = fs::remove_file(&path);
+}
+
+#[cfg(unix)]
+#[test]
+fn fifo_end_marker() {
+    use std::env;
+    use std::fs;
+    use std::io::Write;
+
+    let path = env::temp_dir().join(format!("parallel-fifo-{}", ::std::process::id()));
+    let mut stream = InputStream::fifo(&path, "EOF".to_owned(), 2, |line| line).unwrap();
+    for inputs in &[&b"a\nb\n"[..], b"c\nEOF\nd\n"] {
+        OpenOptions::new().write(true).open(&path).unwrap().write_all(inputs).unwrap();
+    }
+
+    let mut buffer = [0u8; 64];
+    let mut inputs = Vec::new();
+    loop {
+        match stream.read(&mut buffer).unwrap() {
+            0    => break,
+            read => inputs.extend_from_slice(&buffer[..read])
+        }
+    }
+    assert_eq!(inputs, b"a b\nc\n");
+    let _ = fs::remove_file(&path);
+}
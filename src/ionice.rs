@@ -0,0 +1,79 @@
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// The IO priority that jobs will be spawned with, or zero if it was not set.
+static PRIORITY: AtomicUsize = AtomicUsize::new(0);
+
+/// The priority level that is used when a level is not given with the class.
+const DEFAULT_LEVEL: u16 = 4;
+
+/// The number of bits that the class is shifted by within an IO priority.
+const CLASS_SHIFT: u16 = 13;
+
+/// Parses the value of the `--ionice` parameter, such as `idle` or `best-effort:7`, into an IO
+/// priority. Classes may also be given by their number, where realtime is 1, best-effort is 2,
+/// and idle is 3. The level, from 0 to 7, is a higher priority the lower that it is.
+pub fn parse(input: &str) -> Result<u16, ()> {
+    let (class, level) = match input.find(':') {
+        Some(pos) => (&input[..pos], Some(&input[pos+1..])),
+        None      => (input, None)
+    };
+
+    let class = match class {
+        "realtime" | "rt" | "1"    => 1,
+        "best-effort" | "be" | "2" => 2,
+        // Jobs within the idle class only receive IO time when no other process needs it.
+        "idle" | "3" => return if level.is_none() { Ok(3 << CLASS_SHIFT) } else { Err(()) },
+        _ => return Err(())
+    };
+
+    let level = match level {
+        Some(level) => match level.parse::<u16>() {
+            Ok(level) if level < 8 => level,
+            _ => return Err(())
+        },
+        None => DEFAULT_LEVEL
+    };
+
+    Ok(class << CLASS_SHIFT | level)
+}
+
+/// Sets the IO priority that all jobs will be spawned with.
+pub fn init(priority: u16) { PRIORITY.store(priority as usize, Ordering::SeqCst); }
+
+/// Sets the IO priority of the command's process before it is executed, if a priority was set.
+#[cfg(target_os = "linux")]
+pub fn set_priority(command: &mut Command) {
+    use libc;
+    use std::io;
+    use std::os::unix::process::CommandExt;
+
+    /// Sets the priority of a single process, given by its process ID.
+    const IOPRIO_WHO_PROCESS: libc::c_int = 1;
+
+    let priority = PRIORITY.load(Ordering::SeqCst);
+    if priority == 0 { return }
+    unsafe {
+        command.pre_exec(move || {
+            if libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, priority as libc::c_int) == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+}
+
+/// IO priorities are only supported on Linux.
+#[cfg(not(target_os = "linux"))]
+pub fn set_priority(_command: &mut Command) {}
+
+#[test]
+fn ionice_parsing() {
+    assert_eq!(parse("idle"), Ok(3 << 13));
+    assert_eq!(parse("best-effort"), Ok(2 << 13 | 4));
+    assert_eq!(parse("be:7"), Ok(2 << 13 | 7));
+    assert_eq!(parse("1:0"), Ok(1 << 13));
+    assert!(parse("idle:3").is_err());
+    assert!(parse("be:8").is_err());
+    assert!(parse("low").is_err());
+}
@@ -0,0 +1,49 @@
+//! The core of the MIT/Rust Parallel application, which may be embedded within other Rust programs
+//! to execute templated commands across a number of worker threads, with the outputs of each
+//! command printed in the order that the inputs were given.
+#![deny(dead_code)]
+#![allow(unknown_lints)]
+extern crate itoa;
+extern crate libc;
+extern crate numtoa;
+extern crate num_cpus;
+extern crate permutate;
+extern crate regex;
+#[cfg(feature = "sqlite")]
+extern crate rusqlite;
+extern crate smallvec;
+extern crate sys_info;
+extern crate time;
+
+#[macro_use]
+pub mod logging;
+
+pub mod arguments;
+pub mod checkpoint;
+mod disk_buffer;
+pub mod events;
+pub mod execute;
+mod executor;
+pub mod filepaths;
+pub mod halt;
+pub mod input_iterator;
+mod ionice;
+mod memory;
+mod misc;
+pub mod notify;
+pub mod queue;
+pub mod raw;
+pub mod results;
+mod running;
+pub mod tokenizer;
+mod shell;
+mod status;
+pub mod summary;
+mod syslog;
+pub mod trace;
+mod verbose;
+
+pub use arguments::{Args, Config};
+pub use executor::Executor;
+pub use input_iterator::InputIterator;
+pub use tokenizer::{Replacements, Span, Token, TokenErr, tokenize, tokenize_spans, tokenize_spans_with};
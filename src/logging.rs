@@ -0,0 +1,95 @@
+//! Diagnostics about the operation of parallel itself, such as how jobs are dispatched to workers,
+//! waits for memory to become available, retries, and the handling of signals, which are written
+//! separately from the outputs of jobs, as given by the `--log-level` and `--log-file` parameters.
+//! Nothing is written unless a level has been given.
+
+use std::fmt;
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use time::get_time;
+
+/// The severity of a diagnostic, where each level also includes the levels above it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Level {
+    Error = 1,
+    Warn  = 2,
+    Info  = 3,
+    Debug = 4,
+    Trace = 5,
+}
+
+impl Level {
+    /// Parses the name of a level, as given to the `--log-level` parameter.
+    pub fn parse(name: &str) -> Option<Level> {
+        match name.to_ascii_lowercase().as_str() {
+            "error"            => Some(Level::Error),
+            "warn" | "warning" => Some(Level::Warn),
+            "info"             => Some(Level::Info),
+            "debug"            => Some(Level::Debug),
+            "trace"            => Some(Level::Trace),
+            _                  => None
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Level::Error => "ERROR",
+            Level::Warn  => "WARN",
+            Level::Info  => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        }
+    }
+}
+
+/// The most verbose level that will be written, or zero if logging has not been enabled.
+static LEVEL: AtomicUsize = AtomicUsize::new(0);
+
+/// Where diagnostics are written, which is the standard error if a file was not given.
+static OUTPUT: Mutex<Option<Box<dyn Write + Send>>> = Mutex::new(None);
+
+/// Enables logging at the given level, appending to the file at `path`, where a path of `-`, or no
+/// path, denotes the standard error.
+pub fn init(level: Level, path: Option<&str>) -> io::Result<()> {
+    let output: Box<dyn Write + Send> = match path {
+        Some(path) if path != "-" => Box::new(OpenOptions::new().create(true).append(true).open(path)?),
+        _ => Box::new(io::stderr())
+    };
+
+    *OUTPUT.lock().unwrap() = Some(output);
+    LEVEL.store(level as usize, Ordering::SeqCst);
+    Ok(())
+}
+
+/// Returns `true` if diagnostics at the given level will be written, so that they are only formatted
+/// when they are needed.
+pub fn enabled(level: Level) -> bool { level as usize <= LEVEL.load(Ordering::Relaxed) }
+
+/// Writes a diagnostic from the given module, one line per diagnostic. This is used by the `log!` macro.
+pub fn write(level: Level, module: &str, message: fmt::Arguments) {
+    let now = get_time();
+    let line = format!("{}.{:03} {:<5} {}: {}\n", now.sec, now.nsec / 1_000_000, level.name(),
+        module.trim_start_matches("parallel_core::"), message);
+    if let Some(ref mut output) = *OUTPUT.lock().unwrap() {
+        let _ = output.write_all(line.as_bytes()).and_then(|_| output.flush());
+    }
+}
+
+/// Writes a diagnostic at the given level, such as `log!(Debug, "retrying job {}", id)`, if that
+/// level has been enabled.
+macro_rules! log {
+    ($level:ident, $($arg:tt)+) => {
+        if $crate::logging::enabled($crate::logging::Level::$level) {
+            $crate::logging::write($crate::logging::Level::$level, module_path!(), format_args!($($arg)+));
+        }
+    }
+}
+
+#[test]
+fn log_levels() {
+    assert_eq!(Level::parse("DEBUG"), Some(Level::Debug));
+    assert_eq!(Level::parse("warning"), Some(Level::Warn));
+    assert_eq!(Level::parse("verbose"), None);
+}
@@ -1,39 +1,24 @@
-#![deny(dead_code)]
-#![allow(unknown_lints)]
-extern crate arrayvec;
-extern crate itoa;
-extern crate numtoa;
-extern crate num_cpus;
-extern crate permutate;
-extern crate smallvec;
-extern crate sys_info;
-extern crate time;
-extern crate wait_timeout;
-
-mod arguments;
-mod disk_buffer;
-mod execute;
-mod filepaths;
-mod input_iterator;
-mod misc;
-mod tokenizer;
-mod shell;
-mod verbose;
+extern crate parallel_core;
 
 use std::env;
 use std::fs::{self, create_dir_all, File};
-use std::io::{self, BufRead, BufReader, Read, Write};
+use std::io::{self, Read, Write};
 use std::mem;
+use std::path::Path;
 use std::process::exit;
-use std::thread::{self, JoinHandle};
-use std::time::Duration;
-use std::sync::{Arc, Mutex};
-use std::sync::mpsc::channel;
-
-use arguments::Args;
-use execute::pipe::disk::State;
-use input_iterator::{InputIterator, InputsLock};
-use tokenizer::{Token, tokenize};
+use std::time::Instant;
+
+use parallel_core::{arguments, execute, filepaths, notify, raw, summary, Args, Executor, InputIterator, Replacements, Token,
+    tokenize, tokenize_spans_with};
+use parallel_core::execute::command::{JobTimeout, OutputFiles};
+use parallel_core::execute::RecordFile;
+use parallel_core::checkpoint::{self, Checkpoint};
+use parallel_core::events::EventLog;
+use parallel_core::queue::WorkQueue;
+use parallel_core::results::ResultsDir;
+use parallel_core::input_iterator::InputIteratorErr;
+use parallel_core::logging::{self, Level};
+use parallel_core::trace::TraceLog;
 
 /// The command string needs to be available in memory for the entirety of the application, so this
 /// is achievable by transmuting the lifetime of the reference into a static lifetime. To guarantee
@@ -50,8 +35,7 @@ unsafe fn leak_string(comm: String) -> &'static str {
 unsafe fn static_arg(args: &[Token]) -> &'static [Token] { mem::transmute(args) }
 
 fn main() {
-    // Obtain a handle to standard output/error's buffers so we can write directly to them.
-    let stdout = io::stdout();
+    // Obtain a handle to standard error's buffer so we can write directly to it.
     let stderr = io::stderr();
 
     // On Linux systems, check if transparent_hugepages is set to always and issue a warning if true.
@@ -66,12 +50,21 @@ fn main() {
         }
     }
 
-    // Parse arguments and collect flags and statistics.
+    // Parse arguments and collect options and statistics.
     let mut args      = Args::new();
     let mut comm      = String::with_capacity(128);
-    let raw_arguments = env::args().collect::<Vec<String>>();
+    let mut raw_arguments = env::args_os().map(|argument| raw::decode_os(&argument)).collect::<Vec<String>>();
+
+    // Options within the `PARALLEL` environment variable are parsed before the command-line arguments.
+    arguments::insert_default_options(&mut raw_arguments);
+
+    // Abbreviated and grouped options, and options given with `=` values, are rewritten for the parser.
+    let raw_arguments = match arguments::normalize_options(&raw_arguments) {
+        Ok(arguments) => arguments,
+        Err(why) => arguments::errors::ParseErr::from(why).handle(&raw_arguments)
+    };
 
-    // Attempt to obtain the default tempdir base path.
+    // Attempt to obtain the default tempdir base path, which may be replaced by the `--tmpdir` parameter.
     let mut base  = match filepaths::base() {
         Some(base) => base,
         None => {
@@ -81,18 +74,29 @@ fn main() {
         }
     };
 
-    // Create the base directory if it does not exist
+    // Collect the command, arguments, and the directory of this run within the tempdir.
+    args.ninputs = match args.parse(&mut comm, &raw_arguments, &mut base) {
+        Ok(inputs) => inputs,
+        Err(why) => why.handle(&raw_arguments)
+    };
+
+    // Create the run's directory if it does not exist, as inputs which are streamed are not written to it.
     if let Err(why) = create_dir_all(&base) {
         let stderr = &mut stderr.lock();
         let _ = writeln!(stderr, "parallel: unable to create tempdir {:?}: {}", base, why);
         exit(1);
     }
+    filepaths::remove_job_files(&base);
 
-    // Collect the command, arguments, and tempdir base path.
-    args.ninputs = match args.parse(&mut comm, &raw_arguments, &mut base) {
-        Ok(inputs) => inputs,
-        Err(why) => why.handle(&raw_arguments)
-    };
+    // If the `--log-level` or `--log-file` parameters were passed, parallel's own diagnostics will be written.
+    if args.log_level.is_some() || args.log_file.is_some() {
+        let level = args.log_level.unwrap_or(Level::Info);
+        if let Err(why) = logging::init(level, args.log_file.as_ref().map(|x| x.as_str())) {
+            let stderr = &mut stderr.lock();
+            let _ = writeln!(stderr, "parallel: unable to open log file {:?}: {}", args.log_file.as_ref().unwrap(), why);
+            exit(1);
+        }
+    }
 
     // Attempt to convert the base path into a string slice.
     let base_path = match base.to_str() {
@@ -114,28 +118,75 @@ fn main() {
     errors_path.push("errors");
 
     // This file is required by the upcoming `InputIterator`. It will remain open for the
-    // remainder of the application.
-    let file = match fs::OpenOptions::new().read(true).open(&unprocessed_path) {
-        Ok(file) => file,
-        Err(why) => {
-            let stderr = &mut stderr.lock();
-            let _ = writeln!(stderr, "parallel: unable to open unprocessed file: {:?}: {}", &unprocessed_path, why);
-            exit(1);
+    // remainder of the application. If inputs are being streamed, they will be read from the stream.
+    let file: Box<Read + Send> = match args.stream.take() {
+        Some(stream) => Box::new(stream),
+        None => match fs::OpenOptions::new().read(true).open(&unprocessed_path) {
+            Ok(file) => Box::new(file),
+            Err(why) => {
+                let stderr = &mut stderr.lock();
+                let _ = writeln!(stderr, "parallel: unable to open unprocessed file: {:?}: {}", &unprocessed_path, why);
+                exit(1);
+            }
         }
     };
 
     // Initialize the `InputIterator` structure, which efficiently generates inputs from the
     // above `unprocessed` file until all arguments have been processed, denoted by `args.ninputs`.
-    let inputs = InputIterator::new(&unprocessed_path, file, args.ninputs)
+    let mut inputs = InputIterator::new(&unprocessed_path, file, args.ninputs, args.buffer_size)
         .expect("unable to initialize the InputIterator structure");
+    inputs.repeat = args.repeat;
+    summary::repeat_inputs(args.repeat);
+    inputs.expected_total = args.expected_total();
+
+    // If the `--checkpoint` parameter was passed, and a previous run left a checkpoint behind, the
+    // inputs whose jobs were completed by that run will be skipped. Resuming a run with a different
+    // command would mix the results of two commands, so the command templates must be the same.
+    let command = checkpoint::hash_command(&comm);
+    let mut resume = match args.checkpoint.as_ref().map(|path| Checkpoint::load(Path::new(path))) {
+        Some(Ok(ref checkpoint)) if *checkpoint != Checkpoint::default() && checkpoint.command != command => {
+            let stderr = &mut stderr.lock();
+            let _ = writeln!(stderr, "parallel: checkpoint {:?} was recorded with a different command; remove it to \
+                start a new run", args.checkpoint.as_ref().unwrap());
+            exit(1);
+        },
+        Some(Ok(checkpoint)) => checkpoint,
+        Some(Err(why)) => {
+            let stderr = &mut stderr.lock();
+            let _ = writeln!(stderr, "parallel: unable to load checkpoint {:?}: {}", args.checkpoint.as_ref().unwrap(), why);
+            exit(1);
+        },
+        None => Checkpoint::default()
+    };
+    resume.command = command;
+
+    if let Err(InputIteratorErr::FileRead(path, why)) = inputs.skip_inputs(resume.completed, &mut String::new()) {
+        let stderr = &mut stderr.lock();
+        let _ = writeln!(stderr, "parallel: input file read error: {:?}: {}", path, why);
+        exit(1);
+    }
 
     // Coerce the `comm` `String` into a `&'static str` so that it may be shared by all threads.
     // This is safe because the original `comm` may no longer be modified due to shadowing rules.
     // It is also safe because `comm` lives to the end of the program.
     let static_comm = unsafe { leak_string(comm) };
 
+    // The shell that was selected with the `--shell` parameter will also be shared by all threads.
+    let shell = args.shell.take().map(|shell| unsafe { leak_string(shell) });
+
+    // As is the command that each job will be executed by, if the jobs are to be wrapped.
+    let wrapper: &'static [String] = Box::leak(mem::replace(&mut args.wrapper, Vec::new()).into_boxed_slice());
+
+    // The strings which will be replaced by the job and slot numbers may have been changed with
+    // the `--seqreplace` and `--slotreplace` parameters.
+    let mut replace = Replacements::default();
+    if let Some(seq) = args.seqreplace.take() { replace.seq = unsafe { leak_string(seq) }; }
+    if let Some(slot) = args.slotreplace.take() { replace.slot = unsafe { leak_string(slot) }; }
+
     // Attempt to tokenize the command argument into simple primitive placeholders.
-    if let Err(error) = tokenize(&mut args.arguments, static_comm, &unprocessed_path, args.ninputs) {
+    // The Nth input can not be obtained when inputs are streamed, as they have yet to be read.
+    let nargs = args.distinct_inputs();
+    if let Err(error) = tokenize(&mut args.arguments, static_comm, &unprocessed_path, nargs, &replace) {
         let stderr = &mut stderr.lock();
         let _ = writeln!(stderr, "{}", error);
         exit(1)
@@ -145,119 +196,158 @@ fn main() {
     // each thread.
     let arguments = unsafe { static_arg(&args.arguments) };
 
-    // If the `--dry-run` parameter was passsed, the program will simply print all commands to
-    // execute and will subsequently quit. Otherwise, real work will be performed.
-    if args.flags & arguments::DRY_RUN != 0 {
-        execute::dry_run(args.flags, inputs, arguments);
-    } else {
-
-
-        // The `InputIterator` will be wrapped within a `Mutex` so that it can safely be shared
-        // across all of the upcoming threads. A `Mutex` is required because each time a thread
-        // pulls the next input from the queue, it needs to update various data fields which
-        // rely on threads waiting for their turn to report back.
-        let shared_input = Arc::new(Mutex::new(inputs));
+    // The files that the outputs of each job will be written to are named by their own templates,
+    // as is the command that will be executed for each job that fails.
+    let template = |template: Option<String>| template.map(|template| {
+        let template = unsafe { leak_string(template) };
+        match tokenize_spans_with(template, &unprocessed_path, nargs, &replace) {
+            Ok(tokens) => {
+                let tokens: &'static [Token] = Box::leak(tokens.into_iter().map(|(token, _)| token).collect::<Vec<_>>()
+                    .into_boxed_slice());
+                tokens
+            },
+            Err(error) => {
+                let stderr = &mut stderr.lock();
+                let _ = writeln!(stderr, "{}", error);
+                exit(1)
+            }
+        }
+    });
+    let outputs = OutputFiles {
+        stdout: template(args.output.take()),
+        stderr: template(args.error_output.take()),
+    };
+    let on_fail = template(args.on_fail.take());
+    let workdir = template(args.workdir.take());
 
-        // This channel is used exclusively for signaling back to the main thread when a task
-        // has completed or has errored.
-        let (output_tx, input_rx) = channel::<State>();
+    // The timeout of each job may be expanded from its input. With the `--colsep` parameter, `{N}` is the
+    // Nth column of the job's input, rather than the Nth input.
+    let column = |template: &str| if template.starts_with('{') && template.ends_with('}') {
+        template[1..template.len()-1].parse::<usize>().ok().filter(|&column| column != 0)
+    } else {
+        None
+    };
+    let timeout = match args.timeout_template.take() {
+        Some(timeout) => match (column(&timeout), args.colsep.clone()) {
+            (Some(column), Some(colsep)) => JobTimeout::Column(column, unsafe { leak_string(colsep) }),
+            _ => JobTimeout::Template(template(Some(timeout)).unwrap()),
+        },
+        None => JobTimeout::Fixed(args.timeout),
+    };
 
-        // Will contain handles to the upcoming threads to know when the threads are finished.
-        let mut threads = Vec::with_capacity(args.ncores);
+    // Once the run has completed, its directory is removed from the tempdir, unless the `--keep-temp` parameter was
+    // passed, or a checkpoint remains from which the run may be resumed.
+    let (keep_temp, tempdir, checkpoint) = (args.config.keep_temp, base_path.clone(), args.checkpoint.clone());
+    let remove_tempdir = || if !keep_temp && !checkpoint.as_ref().map_or(false, |path| Path::new(path).exists()) {
+        filepaths::remove_tempdir(Path::new(&tempdir));
+    };
 
-        if args.flags & arguments::VERBOSE_MODE != 0 {
-            verbose::total_inputs(&stdout, args.ncores, args.ninputs);
+    // If the `--dry-run` parameter was passsed, the program will simply print all commands to
+    // execute and will subsequently quit. Otherwise, real work will be performed.
+    if args.config.dry_run {
+        execute::dry_run(args.config, inputs, arguments, args.records(), args.dry_run.as_ref().map(|x| x.as_str()));
+        remove_tempdir();
+    } else if let Some(url) = args.master.take() {
+        // If the `--sqlmaster` or `--queue` parameters were passed, the inputs will be added to the queue for
+        // workers to execute.
+        let inputs = inputs.map(|input| input.map_err(|InputIteratorErr::FileRead(path, why)| {
+            io::Error::new(why.kind(), format!("unable to read {:?}: {}", path, why))
+        }));
+        match WorkQueue::open(&url).and_then(|queue| queue.push(inputs)) {
+            Ok(count) => {
+                let _ = writeln!(stderr.lock(), "parallel: added {} inputs to the queue in {}", count, url);
+                remove_tempdir();
+            },
+            Err(why) => {
+                let _ = writeln!(stderr.lock(), "parallel: unable to add inputs to the queue in {}: {}", url, why);
+                exit(1);
+            }
         }
-
-        // The `slot` variable is required by the {%} token.
-        if args.flags & arguments::INPUTS_ARE_COMMANDS != 0 {
-            if shell::ion_exists() {
-                args.flags |= arguments::ION_EXISTS;
-            } else if shell::dash_exists() {
-                args.flags |= arguments::DASH_EXISTS;
+    } else {
+        // If the `--log-events` parameter was passed, job lifecycle events will be written to this log.
+        let events = args.events.as_ref().map(|path| match EventLog::open(path) {
+            Ok(events) => events,
+            Err(why) => {
+                let stderr = &mut stderr.lock();
+                let _ = writeln!(stderr, "parallel: unable to open event log {:?}: {}", path, why);
+                exit(1);
             }
+        });
 
-            for _ in 0..args.ncores {
-                let flags = args.flags;
-
-                let mut exec = execute::ExecInputs {
-                    num_inputs: args.ninputs,
-                    timeout:    args.timeout,
-                    output_tx:  output_tx.clone(),
-                    tempdir:    base_path.clone(),
-                    inputs:     InputsLock {
-                        inputs:    shared_input.clone(),
-                        memory:    args.memory,
-                        delay:     args.delay,
-                        has_delay: args.delay != Duration::from_millis(0),
-                        completed: false,
-                        flags:     flags,
-                    }
-                };
-
-                let handle: JoinHandle<()> = thread::spawn(move || exec.run(flags));
-
-                // Add the thread handle to the `threads` vector to know when to quit the program.
-                threads.push(handle);
+        // If the `--results-json` parameter was passed, the metadata of each job will be written within its directory.
+        let results = args.results.as_ref().map(|path| match ResultsDir::open(path) {
+            Ok(results) => results,
+            Err(why) => {
+                let stderr = &mut stderr.lock();
+                let _ = writeln!(stderr, "parallel: unable to create results directory {:?}: {}", path, why);
+                exit(1);
             }
-        } else {
-            shell::set_flags(&mut args.flags, arguments);
-
-            for slot in 1..args.ncores+1 {
-                let timeout    = args.timeout;
-                let num_inputs = args.ninputs;
-                let output_tx  = output_tx.clone();
-                let flags      = args.flags;
-                let base_path  = base_path.clone();
-
-                let inputs = InputsLock {
-                    inputs:    shared_input.clone(),
-                    memory:    args.memory,
-                    delay:     args.delay,
-                    has_delay: args.delay != Duration::from_millis(0),
-                    completed: false,
-                    flags:     flags,
-                };
-
-                // The command will be built from the arguments, and inputs will be transferred to the command.
-                let handle: JoinHandle<()> = thread::spawn(move || {
-                    let mut exec = execute::ExecCommands {
-                        slot:       slot,
-                        num_inputs: num_inputs,
-                        flags:      flags,
-                        timeout:    timeout,
-                        inputs:     inputs,
-                        output_tx:  output_tx,
-                        arguments:  arguments,
-                        tempdir:    base_path,
-                    };
-                    exec.run();
-                });
-
-                // Add the thread handle to the `threads` vector to know when to quit the program.
-                threads.push(handle);
+        });
+
+        // If the `--trace-file` parameter was passed, the time spent within each phase of each job will be written.
+        let trace = args.trace.as_ref().map(|path| match TraceLog::open(path) {
+            Ok(trace) => trace,
+            Err(why) => {
+                let stderr = &mut stderr.lock();
+                let _ = writeln!(stderr, "parallel: unable to open trace file {:?}: {}", path, why);
+                exit(1);
             }
+        });
+
+        let notify = args.notify.take();
+        let show_summary = args.config.summary;
+        let executor = Executor {
+            args:           args,
+            arguments:      arguments,
+            replace:        replace,
+            shell:          shell,
+            wrapper:        wrapper,
+            outputs:        outputs,
+            on_fail:        on_fail,
+            workdir:        workdir,
+            timeout:        timeout,
+            events:         events,
+            results:        results,
+            trace:          trace,
+            resume:         resume,
+            base_path:      base_path,
+            processed_path: processed_path,
+            errors_path:    errors_path.clone(),
+        };
+
+        // Executes each job in parallel, printing messages from executed commands in the correct order.
+        let start = Instant::now();
+        let errors = executor.run(inputs);
+
+        // If any jobs have failed, summarize the outcomes of the jobs, listing the first that failed.
+        // The `--summary` parameter will always summarize the outcomes, followed by the runtimes of jobs.
+        let elapsed = start.elapsed();
+        let wall_ns = elapsed.as_secs() * 1_000_000_000 + elapsed.subsec_nanos() as u64;
+        if show_summary || summary::has_failures() {
+            let _ = summary::report(&mut stderr.lock(), wall_ns);
         }
+        if show_summary { let _ = summary::statistics(&mut stderr.lock(), wall_ns); }
 
-        /// Prints messages from executed commands in the correct order.
-        let errors = execute::receive_messages(input_rx, args, &base_path, &processed_path, &errors_path);
-
-        /// Wait for all threads to exit before proceeding.
-        for thread in threads { thread.join().unwrap(); }
+        // If the `--notify-cmd` parameter was passed, its command will be notified that the run has finished.
+        if let Some(ref command) = notify { notify::run_finished(command, wall_ns); }
 
         // If errors have occurred, re-print these errors at the end.
-        if let Ok(file) = File::open(errors_path) {
-            if file.metadata().ok().map_or(0, |metadata| metadata.len()) > 0 {
+        let records = RecordFile::read(&errors_path);
+        remove_tempdir();
+        if let Ok(records) = records {
+            if !records.is_empty() {
                 let stderr = &mut stderr.lock();
                 let _ = stderr.write(b"parallel: encountered errors during processing:\n");
-                for line in BufReader::new(file).lines() {
-                    if let Ok(line) = line {
-                        let _ = stderr.write(line.as_bytes());
-                        let _ = stderr.write(b"\n");
-                    }
+                for (_, message) in records {
+                    let _ = stderr.write(message.as_bytes());
+                    let _ = stderr.write(b"\n");
                 }
                 exit(errors);
             }
         }
+
+        // Failures which were recorded by a checkpoint will not have been written to the errors file, and a run
+        // which was halted by a failed job exits with the status of that job.
+        if errors != 0 { exit(errors); }
     }
 }
@@ -0,0 +1,90 @@
+use std::cmp;
+use std::fs;
+use std::path::{Path, PathBuf};
+use sys_info;
+
+/// The mount point of the cgroup hierarchies on Linux.
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// Obtains the number of bytes of memory that are available to new jobs. If this process is within
+/// a cgroup whose memory is limited, such as within a container, the memory remaining within that
+/// limit is used when it is less than the memory available to the system.
+pub fn available() -> Option<u64> {
+    let system = sys_info::mem_info().ok().map(|mem_info| mem_info.avail * 1000);
+    match (system, cgroup_available()) {
+        (Some(system), Some(cgroup)) => Some(cmp::min(system, cgroup)),
+        (system, cgroup) => system.or(cgroup)
+    }
+}
+
+/// The memory remaining within the tightest limit of this process's cgroup and its ancestors.
+fn cgroup_available() -> Option<u64> {
+    let cgroups = fs::read_to_string("/proc/self/cgroup").ok()?;
+    let mut available = None;
+    for (limit, usage) in cgroup_paths(&cgroups) {
+        let limit = fs::read_to_string(&limit).ok().and_then(|limit| parse_limit(&limit));
+        let usage = fs::read_to_string(&usage).ok().and_then(|usage| usage.trim().parse::<u64>().ok());
+        if let (Some(limit), Some(usage)) = (limit, usage) {
+            let remaining = limit.saturating_sub(usage);
+            available = Some(available.map_or(remaining, |available| cmp::min(available, remaining)));
+        }
+    }
+    available
+}
+
+/// Obtains the paths of the limit and usage files of each cgroup that this process is within, and of
+/// their ancestors, whose limits also apply, from the contents of `/proc/self/cgroup`. Within a
+/// container, only the root of each hierarchy may be mounted, which will be its own ancestor.
+fn cgroup_paths(cgroups: &str) -> Vec<(PathBuf, PathBuf)> {
+    let mut paths = Vec::new();
+    for line in cgroups.lines() {
+        let mut fields = line.splitn(3, ':');
+        let (controllers, path) = match (fields.next(), fields.next(), fields.next()) {
+            (Some(_), Some(controllers), Some(path)) => (controllers, path.trim_start_matches('/')),
+            _ => continue
+        };
+
+        let (root, limit, usage) = if controllers.is_empty() {
+            // The unified hierarchy of cgroup v2.
+            (PathBuf::from(CGROUP_ROOT), "memory.max", "memory.current")
+        } else if controllers.split(',').any(|controller| controller == "memory") {
+            // The memory controller of cgroup v1.
+            (Path::new(CGROUP_ROOT).join("memory"), "memory.limit_in_bytes", "memory.usage_in_bytes")
+        } else {
+            continue
+        };
+
+        let mut directory = root.join(path);
+        loop {
+            paths.push((directory.join(limit), directory.join(usage)));
+            if directory == root || !directory.pop() { break }
+        }
+    }
+    paths
+}
+
+/// Parses the memory limit of a cgroup, where `max`, or a limit that is too large to be real, is no limit.
+fn parse_limit(limit: &str) -> Option<u64> {
+    match limit.trim().parse::<u64>() {
+        // cgroup v1 reports an unlimited cgroup with a limit near the maximum value of an `i64`.
+        Ok(limit) if limit < (1 << 62) => Some(limit),
+        _ => None
+    }
+}
+
+#[test]
+fn cgroup_limits() {
+    assert_eq!(parse_limit("max\n"), None);
+    assert_eq!(parse_limit("9223372036854771712\n"), None);
+    assert_eq!(parse_limit("536870912\n"), Some(536870912));
+
+    let paths = cgroup_paths("0::/user.slice/job.scope\n");
+    assert_eq!(paths.len(), 3);
+    assert_eq!(paths[0].0, Path::new("/sys/fs/cgroup/user.slice/job.scope/memory.max"));
+    assert_eq!(paths[2].1, Path::new("/sys/fs/cgroup/memory.current"));
+
+    let paths = cgroup_paths("4:memory:/docker/abc\n1:name=systemd:/docker/abc\n");
+    assert_eq!(paths.len(), 3);
+    assert_eq!(paths[0].0, Path::new("/sys/fs/cgroup/memory/docker/abc/memory.limit_in_bytes"));
+    assert_eq!(paths[2].1, Path::new("/sys/fs/cgroup/memory/memory.usage_in_bytes"));
+}
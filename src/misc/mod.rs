@@ -1,5 +1,8 @@
 ///! The purpose of this module is to supply supporting miscellanious traits for use throughout the project.
 mod digits;
+mod random;
 
 /// The `Digits` trait is used to get the number of digits within a number.
 pub use self::digits::Digits;
+/// The `Random` structure generates pseudo-random numbers for jittering job delays.
+pub use self::random::Random;
@@ -0,0 +1,39 @@
+/// A small xorshift pseudo-random number generator, which is more than sufficient for spreading
+/// out job start times without pulling in an additional dependency.
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    /// Creates a new generator from the given seed. A seed of zero would cause the generator to
+    /// only ever return zero, so it is substituted with a non-zero constant.
+    pub fn new(seed: u64) -> Random {
+        Random { state: if seed == 0 { 0x9E37_79B9_7F4A_7C15 } else { seed } }
+    }
+
+    /// Generates the next pseudo-random number in the sequence.
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    /// Generates a pseudo-random number within the range of `0..max`, inclusively.
+    pub fn up_to(&mut self, max: u64) -> u64 {
+        match max {
+            0 => 0,
+            // Every number is within the range, which can not be counted by a `u64`.
+            u64::MAX => self.next_u64(),
+            _ => self.next_u64() % (max + 1),
+        }
+    }
+}
+
+#[test]
+fn random_up_to() {
+    let mut random = Random::new(1);
+    for _ in 0..1000 { assert!(random.up_to(10) <= 10); }
+    assert_eq!(random.up_to(0), 0);
+    random.up_to(u64::MAX);
+}
@@ -0,0 +1,46 @@
+use arguments::Config;
+use execute::command::ParallelCommand;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+use summary;
+use tokenizer::Token;
+
+/// Executes the `--on-fail` command for a job that has failed, once its retries have been exhausted.
+/// The command is built from the template with the job's input, which will be quoted, and the exit
+/// value and signal of the job are supplied within the `PARALLEL_EXITVAL` and `PARALLEL_SIGNAL` variables.
+pub fn job_failed(template: &[Token], job: &ParallelCommand, exit_value: i32, signal: i32) {
+    let mut command = String::with_capacity(64);
+    ParallelCommand { command_template: template, config: Config { quote_inputs: true, ..Config::default() }, ..*job }.build_arguments(&mut command);
+    let job_no = String::from_utf8_lossy(job.job_no);
+    execute(&command, &[
+        ("PARALLEL_JOB", job_no.into_owned()),
+        ("PARALLEL_EXITVAL", exit_value.to_string()),
+        ("PARALLEL_SIGNAL", signal.to_string()),
+    ]);
+}
+
+/// Executes the `--notify-cmd` command once every job has finished, with a summary of the run
+/// supplied within environment variables, given the wall time of the run in nanoseconds.
+pub fn run_finished(command: &str, wall_ns: u64) {
+    let (succeeded, failed, timed_out) = summary::counts();
+    execute(command, &[
+        ("PARALLEL_TOTAL", (succeeded + failed + timed_out).to_string()),
+        ("PARALLEL_SUCCEEDED", succeeded.to_string()),
+        ("PARALLEL_FAILED", failed.to_string()),
+        ("PARALLEL_TIMED_OUT", timed_out.to_string()),
+        ("PARALLEL_RUNTIME", format!("{}.{:03}", wall_ns / 1_000_000_000, wall_ns % 1_000_000_000 / 1_000_000)),
+    ]);
+}
+
+/// Executes the command within the platform's shell, waiting for it to exit. The standard output of
+/// the command is discarded, so that it will not be mixed with the outputs of the jobs.
+fn execute(command: &str, variables: &[(&str, String)]) {
+    let mut shell = if cfg!(windows) { Command::new("cmd") } else { Command::new("sh") };
+    shell.arg(if cfg!(windows) { "/C" } else { "-c" }).arg(command).stdin(Stdio::null()).stdout(Stdio::null());
+    for &(name, ref value) in variables { shell.env(name, value); }
+
+    if let Err(why) = shell.status() {
+        let stderr = io::stderr();
+        let _ = writeln!(stderr.lock(), "parallel: unable to execute notification command: {}: {}", command, why);
+    }
+}
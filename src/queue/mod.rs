@@ -0,0 +1,82 @@
+mod redis;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+pub use self::redis::RedisQueue;
+#[cfg(feature = "sqlite")]
+pub use self::sqlite::SqlQueue;
+
+use execute::Usage;
+use std::io;
+use time::Timespec;
+
+/// A queue of inputs which is shared by any number of workers, which may be on other machines. Inputs are
+/// added by a master, given by `--sqlmaster` or `--queue` with inputs, and claimed by the workers, given by
+/// `--sqlworker` or `--queue` without inputs, which record the result of each job within the queue.
+#[derive(Clone)]
+pub enum WorkQueue {
+    Redis(RedisQueue),
+    #[cfg(feature = "sqlite")]
+    Sql(SqlQueue),
+}
+
+impl WorkQueue {
+    /// Opens the queue at `url`, which is a Redis list when it is prefixed with `redis://`, and otherwise
+    /// an SQLite database, which requires the `sqlite` feature.
+    pub fn open(url: &str) -> io::Result<WorkQueue> {
+        if url.starts_with("redis://") {
+            RedisQueue::open(url).map(WorkQueue::Redis)
+        } else {
+            WorkQueue::open_sql(url)
+        }
+    }
+
+    #[cfg(feature = "sqlite")]
+    fn open_sql(url: &str) -> io::Result<WorkQueue> { SqlQueue::open(url).map(WorkQueue::Sql) }
+
+    #[cfg(not(feature = "sqlite"))]
+    fn open_sql(_url: &str) -> io::Result<WorkQueue> {
+        Err(io::Error::new(io::ErrorKind::Other, "SQLite queues require the `sqlite` feature"))
+    }
+
+    /// Sets the number of consecutive jobs that are given each claimed input.
+    pub fn repeat(self, repeat: usize) -> WorkQueue {
+        match self {
+            WorkQueue::Redis(queue) => WorkQueue::Redis(queue.repeat(repeat)),
+            #[cfg(feature = "sqlite")]
+            WorkQueue::Sql(queue) => WorkQueue::Sql(queue.repeat(repeat)),
+        }
+    }
+
+    /// Adds each of the `inputs` to the queue, returning the number of inputs.
+    pub fn push<I: Iterator<Item = io::Result<String>>>(&self, inputs: I) -> io::Result<usize> {
+        match *self {
+            WorkQueue::Redis(ref queue) => queue.push(inputs),
+            #[cfg(feature = "sqlite")]
+            WorkQueue::Sql(ref queue) => queue.push(inputs),
+        }
+    }
+
+    /// Claims the next input of the queue, so that no other worker will execute it. Returns `None` once
+    /// every input has been claimed.
+    pub fn claim(&self) -> io::Result<Option<String>> {
+        match *self {
+            WorkQueue::Redis(ref queue) => queue.claim(),
+            #[cfg(feature = "sqlite")]
+            WorkQueue::Sql(ref queue) => queue.claim(),
+        }
+    }
+
+    /// Records the result of the given job, counting from 0, whose input was claimed from the queue.
+    pub fn job_finished(&self, job_id: usize, input: &str, command: &str, exit_value: i32, signal: i32,
+        start_time: Timespec, runtime: u64, usage: Usage) -> io::Result<()>
+    {
+        match *self {
+            WorkQueue::Redis(ref queue) => queue.job_finished(job_id+1, input, command, exit_value, signal,
+                start_time, runtime, usage),
+            #[cfg(feature = "sqlite")]
+            WorkQueue::Sql(ref queue) => queue.job_finished(job_id, command, exit_value, signal, start_time,
+                runtime),
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use rusqlite::{Connection, OptionalExtension, TransactionBehavior};
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use sys_info;
+use time::Timespec;
+
+/// How long a connection waits for another process which is holding a lock on the database.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// The table that holds the inputs of the queue, and the results of the jobs that were executed for them.
+const SCHEMA: &str = "CREATE TABLE IF NOT EXISTS parallel (
+    seq       INTEGER PRIMARY KEY,
+    input     TEXT NOT NULL,
+    state     TEXT NOT NULL DEFAULT 'pending',
+    host      TEXT,
+    command   TEXT,
+    starttime REAL,
+    runtime   REAL,
+    exitval   INTEGER,
+    signal    INTEGER
+)";
+
+/// A queue of inputs within an SQLite database, as given by the `--sqlmaster` and `--sqlworker` parameters.
+/// The master adds inputs to the queue, and any number of workers, which may be on other machines which
+/// share the database, claim the pending inputs one at a time, and record the result of each job.
+#[derive(Clone)]
+pub struct SqlQueue {
+    connection: Arc<Mutex<Connection>>,
+    /// The row of each input that has been claimed by this worker, in the order that they were claimed.
+    claimed:    Arc<Mutex<Vec<i64>>>,
+    /// The number of consecutive jobs that are given each input, as given by the `--repeat` parameter.
+    repeat:     usize,
+    host:       String,
+}
+
+impl SqlQueue {
+    /// Opens the database at `url`, which is a path that may be prefixed with `sqlite3://`, creating the
+    /// table of the queue if it does not exist.
+    pub fn open(url: &str) -> io::Result<SqlQueue> {
+        let path = if url.starts_with("sqlite3://") { &url[10..] } else { url };
+        let connection = Connection::open(path).map_err(other)?;
+        connection.busy_timeout(BUSY_TIMEOUT).map_err(other)?;
+        connection.execute_batch(SCHEMA).map_err(other)?;
+        Ok(SqlQueue {
+            connection: Arc::new(Mutex::new(connection)),
+            claimed:    Arc::new(Mutex::new(Vec::new())),
+            repeat:     1,
+            host:       sys_info::hostname().unwrap_or_default(),
+        })
+    }
+
+    /// Sets the number of consecutive jobs that are given each claimed input.
+    pub fn repeat(mut self, repeat: usize) -> SqlQueue {
+        self.repeat = repeat;
+        self
+    }
+
+    /// Adds each of the `inputs` to the queue, within a single transaction, returning the number of inputs.
+    pub fn push<I: Iterator<Item = io::Result<String>>>(&self, inputs: I) -> io::Result<usize> {
+        let mut connection = self.connection.lock().unwrap();
+        let transaction = connection.transaction().map_err(other)?;
+        let mut count = 0;
+        {
+            let mut insert = transaction.prepare("INSERT INTO parallel (input) VALUES (?1)").map_err(other)?;
+            for input in inputs {
+                insert.execute([input?]).map_err(other)?;
+                count += 1;
+            }
+        }
+        transaction.commit().map_err(other)?;
+        Ok(count)
+    }
+
+    /// Claims the pending input with the lowest sequence number, so that no other worker will execute it.
+    /// Returns `None` once every input has been claimed.
+    pub fn claim(&self) -> io::Result<Option<String>> {
+        let mut connection = self.connection.lock().unwrap();
+        // The database is locked for writing before the input is selected, so that two workers can not claim it.
+        let transaction = connection.transaction_with_behavior(TransactionBehavior::Immediate).map_err(other)?;
+        let claimed = transaction.query_row("SELECT seq, input FROM parallel WHERE state = 'pending' ORDER BY seq LIMIT 1",
+            [], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))).optional().map_err(other)?;
+        let (seq, input) = match claimed {
+            Some(claimed) => claimed,
+            None => return Ok(None)
+        };
+
+        transaction.execute("UPDATE parallel SET state = 'running', host = ?1 WHERE seq = ?2", (&self.host, seq))
+            .map_err(other)?;
+        transaction.commit().map_err(other)?;
+        self.claimed.lock().unwrap().push(seq);
+        Ok(Some(input))
+    }
+
+    /// Records the result of the given job, whose input was claimed from the queue.
+    pub fn job_finished(&self, job_id: usize, command: &str, exit_value: i32, signal: i32, start_time: Timespec,
+        runtime: u64) -> io::Result<()>
+    {
+        let seq = match self.claimed.lock().unwrap().get(job_id / self.repeat) {
+            Some(&seq) => seq,
+            None => return Err(io::Error::new(io::ErrorKind::NotFound, "the input of the job was not claimed"))
+        };
+
+        let start_time = start_time.sec as f64 + start_time.nsec as f64 / 1_000_000_000f64;
+        let runtime = runtime as f64 / 1_000_000_000f64;
+        self.connection.lock().unwrap().execute("UPDATE parallel SET state = 'done', command = ?1, starttime = ?2, \
+            runtime = ?3, exitval = ?4, signal = ?5 WHERE seq = ?6",
+            (command, start_time, runtime, exit_value, signal, seq)).map_err(other)?;
+        Ok(())
+    }
+}
+
+fn other(why: ::rusqlite::Error) -> io::Error { io::Error::new(io::ErrorKind::Other, why) }
+
+#[test]
+fn queue_claims() {
+    let queue = SqlQueue::open(":memory:").unwrap();
+    let inputs = vec!["a".to_owned(), "b".to_owned()];
+    assert_eq!(queue.push(inputs.into_iter().map(Ok)).unwrap(), 2);
+
+    assert_eq!(queue.claim().unwrap(), Some("a".to_owned()));
+    assert_eq!(queue.claim().unwrap(), Some("b".to_owned()));
+    assert_eq!(queue.claim().unwrap(), None);
+
+    queue.job_finished(1, "echo b", 3, 0, Timespec::new(10, 500_000_000), 250_000_000).unwrap();
+    let connection = queue.connection.lock().unwrap();
+    let result = connection.query_row("SELECT state, command, exitval, starttime FROM parallel WHERE input = 'b'", [],
+        |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, i32>(2)?, row.get::<_, f64>(3)?)))
+        .unwrap();
+    assert_eq!(result, ("done".to_owned(), "echo b".to_owned(), 3, 10.5));
+}
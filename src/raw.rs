@@ -0,0 +1,147 @@
+//! Inputs may contain bytes which are not valid UTF-8, such as file names in legacy encodings. Such
+//! inputs are decoded into strings losslessly, by substituting each invalid byte with a character in
+//! the last 256 code points of the private use plane, U+10FF00 to U+10FFFF, so that inputs may be
+//! tokenized and substituted into commands as strings, and then encoded back into their original bytes
+//! when commands are executed. Characters within that range which were already within the input are
+//! substituted byte by byte, so that every input survives the round trip.
+
+use std::borrow::Cow;
+use std::ffi::OsStr;
+use std::io::{self, BufRead};
+use std::str;
+
+/// The character that the byte `0x00` would be substituted with.
+const SUBSTITUTE_BASE: u32 = 0x10FF00;
+
+/// The first byte of each substitute character, when encoded as UTF-8.
+const SUBSTITUTE_LEAD: u8 = 0xF4;
+
+fn is_substitute(character: char) -> bool { character as u32 >= SUBSTITUTE_BASE }
+
+fn substitute(byte: u8) -> char {
+    ::std::char::from_u32(SUBSTITUTE_BASE + byte as u32).unwrap()
+}
+
+/// Pushes valid UTF-8 onto the `output`, substituting the bytes of characters that are substitutes.
+fn push_valid(output: &mut String, valid: &str) {
+    for character in valid.chars() {
+        if is_substitute(character) {
+            let mut buffer = [0u8; 4];
+            for &byte in character.encode_utf8(&mut buffer).as_bytes() { output.push(substitute(byte)); }
+        } else {
+            output.push(character);
+        }
+    }
+}
+
+/// Decodes bytes into a string, substituting the bytes which are not valid UTF-8.
+pub fn decode<'a>(mut bytes: &'a [u8]) -> Cow<'a, str> {
+    if let Ok(valid) = str::from_utf8(bytes) {
+        if !valid.chars().any(is_substitute) { return Cow::Borrowed(valid) }
+    }
+
+    let mut output = String::with_capacity(bytes.len());
+    loop {
+        match str::from_utf8(bytes) {
+            Ok(valid) => {
+                push_valid(&mut output, valid);
+                return Cow::Owned(output)
+            },
+            Err(why) => {
+                let (valid, rest) = bytes.split_at(why.valid_up_to());
+                push_valid(&mut output, unsafe { str::from_utf8_unchecked(valid) });
+                let invalid = why.error_len().unwrap_or(rest.len());
+                for &byte in &rest[..invalid] { output.push(substitute(byte)); }
+                bytes = &rest[invalid..];
+            }
+        }
+    }
+}
+
+/// Decodes an argument that was given to the program.
+#[cfg(unix)]
+pub fn decode_os(argument: &OsStr) -> String {
+    use std::os::unix::ffi::OsStrExt;
+    decode(argument.as_bytes()).into_owned()
+}
+
+/// Arguments on other platforms are not a sequence of bytes, so only invalid UTF-16 is replaced.
+#[cfg(not(unix))]
+pub fn decode_os(argument: &OsStr) -> String { argument.to_string_lossy().into_owned() }
+
+/// Encodes a string back into the bytes that it was decoded from.
+pub fn encode<'a>(input: &'a str) -> Cow<'a, [u8]> {
+    if !input.as_bytes().contains(&SUBSTITUTE_LEAD) { return Cow::Borrowed(input.as_bytes()) }
+
+    let mut output = Vec::with_capacity(input.len());
+    for character in input.chars() {
+        if is_substitute(character) {
+            output.push((character as u32 - SUBSTITUTE_BASE) as u8);
+        } else {
+            let mut buffer = [0u8; 4];
+            output.extend_from_slice(character.encode_utf8(&mut buffer).as_bytes());
+        }
+    }
+    Cow::Owned(output)
+}
+
+/// Encodes a string back into the bytes that it was decoded from, as an argument of a command.
+#[cfg(unix)]
+pub fn encode_os<'a>(input: &'a str) -> Cow<'a, OsStr> {
+    use std::ffi::OsString;
+    use std::os::unix::ffi::{OsStrExt, OsStringExt};
+    match encode(input) {
+        Cow::Borrowed(bytes) => Cow::Borrowed(OsStr::from_bytes(bytes)),
+        Cow::Owned(bytes)    => Cow::Owned(OsString::from_vec(bytes))
+    }
+}
+
+/// Arguments on other platforms are not a sequence of bytes, so strings are passed as they are.
+#[cfg(not(unix))]
+pub fn encode_os<'a>(input: &'a str) -> Cow<'a, OsStr> { Cow::Borrowed(OsStr::new(input)) }
+
+/// An iterator over the lines of a reader, like `BufRead::lines`, which decodes each line with `decode`
+/// instead of failing on lines that are not valid UTF-8.
+pub struct Lines<R: BufRead> {
+    reader: R,
+    buffer: Vec<u8>,
+}
+
+impl<R: BufRead> Iterator for Lines<R> {
+    type Item = io::Result<String>;
+
+    fn next(&mut self) -> Option<io::Result<String>> {
+        self.buffer.clear();
+        match self.reader.read_until(b'\n', &mut self.buffer) {
+            Ok(0) => None,
+            Ok(_) => {
+                if self.buffer.last() == Some(&b'\n') { self.buffer.pop(); }
+                if self.buffer.last() == Some(&b'\r') { self.buffer.pop(); }
+                Some(Ok(decode(&self.buffer).into_owned()))
+            },
+            Err(why) => Some(Err(why))
+        }
+    }
+}
+
+/// Returns an iterator over the decoded lines of the `reader`.
+pub fn lines<R: BufRead>(reader: R) -> Lines<R> { Lines { reader: reader, buffer: Vec::new() } }
+
+#[test]
+fn raw_round_trip() {
+    let inputs: &[&[u8]] = &[b"file.txt", b"caf\xc3\xa9", b"caf\xe9.txt", b"\xff\xfe", b"\xf4\x8f\xbf\xbf", b"a\xf4\x8f"];
+    for &input in inputs {
+        let decoded = decode(input);
+        assert!(decoded.chars().all(|character| character != '\u{FFFD}'));
+        assert_eq!(&*encode(&decoded), input);
+    }
+
+    assert_eq!(decode(b"caf\xc3\xa9"), Cow::Borrowed("caf\u{e9}"));
+    assert_eq!(decode(b"caf\xe9").chars().count(), 4);
+}
+
+#[test]
+fn raw_lines() {
+    let lines = lines(&b"one\n\xe9\r\nthree"[..]).map(Result::unwrap).map(|line| encode(&line).into_owned());
+    assert_eq!(lines.collect::<Vec<_>>(), vec![b"one".to_vec(), b"\xe9".to_vec(), b"three".to_vec()]);
+}
@@ -0,0 +1,63 @@
+use events::escape;
+use execute::Usage;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use sys_info;
+use time::Timespec;
+
+/// Writes the metadata of each job to `DIR/SEQ/job.json`, as given by the `--results-json` parameter, so
+/// that the results of a run may be analyzed without parsing the job log.
+#[derive(Clone)]
+pub struct ResultsDir {
+    path: PathBuf,
+    /// Jobs are executed on this host, so the host of each record will be the same.
+    host: String,
+}
+
+impl ResultsDir {
+    /// Creates the directory that the metadata of each job will be written within, if it does not exist.
+    pub fn open(path: &str) -> io::Result<ResultsDir> {
+        fs::create_dir_all(path)?;
+        Ok(ResultsDir { path: PathBuf::from(path), host: sys_info::hostname().unwrap_or_default() })
+    }
+
+    /// Writes the metadata of the given job, counting from 1. The record is written to a temporary file
+    /// which is then renamed, so that a record which is being read will never be incomplete.
+    pub fn job_finished(&self, job: usize, input: &str, command: &str, exit_value: i32, signal: i32,
+        start_time: Timespec, runtime: u64, usage: Usage) -> io::Result<()>
+    {
+        let directory = self.path.join(job.to_string());
+        fs::create_dir_all(&directory)?;
+        let partial = directory.join("job.json.partial");
+        let mut record = self::record(&self.host, job, input, command, exit_value, signal, start_time, runtime,
+            usage);
+        record.push('\n');
+        File::create(&partial)?.write_all(record.as_bytes())?;
+        fs::rename(&partial, directory.join("job.json"))
+    }
+}
+
+/// Formats the metadata of the given job, which was executed on `host`, as a single line of JSON.
+pub fn record(host: &str, job: usize, input: &str, command: &str, exit_value: i32, signal: i32, start_time: Timespec,
+    runtime: u64, usage: Usage) -> String
+{
+    format!("{{\"seq\":{},\"input\":\"{}\",\"command\":\"{}\",\"exit\":{},\"signal\":{},\"starttime\":{}.{:03},\
+        \"runtime\":{},\"maxrss\":{},\"usertime\":{},\"systime\":{},\"host\":\"{}\"}}", job, escape(input),
+        escape(command), exit_value, signal, start_time.sec, start_time.nsec / 1_000_000, seconds(runtime),
+        usage.max_rss, seconds(usage.user), seconds(usage.system), escape(host))
+}
+
+/// Displays a number of nanoseconds as seconds, with three decimal places.
+fn seconds(nanoseconds: u64) -> String {
+    format!("{}.{:03}", nanoseconds / 1_000_000_000, nanoseconds % 1_000_000_000 / 1_000_000)
+}
+
+#[test]
+fn job_records() {
+    let usage = Usage { max_rss: 1024, user: 1_500_000_000, system: 20_000_000 };
+    assert_eq!(record("host", 3, "a \"b\"", "echo a \"b\"", 1, 0, Timespec::new(10, 5_000_000), 2_250_000_000, usage),
+        "{\"seq\":3,\"input\":\"a \\\"b\\\"\",\"command\":\"echo a \\\"b\\\"\",\"exit\":1,\"signal\":0,\
+        \"starttime\":10.005,\"runtime\":2.250,\"maxrss\":1024,\"usertime\":1.500,\"systime\":0.020,\
+        \"host\":\"host\"}");
+}
@@ -0,0 +1,167 @@
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// The process IDs of the jobs which are currently running, so that they may all be signaled at once.
+static RUNNING: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+
+/// Whether the run has been suspended, in which case no more jobs should be started.
+static PAUSED: AtomicBool = AtomicBool::new(false);
+
+/// The actions that are performed once the run has been interrupted, before the process exits.
+static ON_INTERRUPT: Mutex<Vec<Box<dyn Fn() + Send>>> = Mutex::new(Vec::new());
+
+/// Returns `true` while the run is suspended.
+pub fn paused() -> bool { PAUSED.load(Ordering::SeqCst) }
+
+/// Records that a job is running with the given process ID.
+pub fn register(pid: u32) { RUNNING.lock().unwrap().push(pid); }
+
+/// Records that the job with the given process ID is no longer running.
+pub fn unregister(pid: u32) {
+    let mut running = RUNNING.lock().unwrap();
+    if let Some(pos) = running.iter().position(|&x| x == pid) {
+        running.swap_remove(pos);
+    }
+}
+
+/// Registers an action to perform once the run has been interrupted, before the process exits, such as
+/// flushing a file which records the jobs that have completed.
+pub fn on_interrupt<F: Fn() + Send + 'static>(action: F) { ON_INTERRUPT.lock().unwrap().push(Box::new(action)); }
+
+/// Returns the number of jobs that are currently running.
+pub fn count() -> usize { RUNNING.lock().unwrap().len() }
+
+/// Terminates every job that is currently running.
+pub fn terminate_all() {
+    for &pid in RUNNING.lock().unwrap().iter() { terminate(pid); }
+}
+
+#[cfg(unix)]
+mod platform {
+    use libc;
+    use std::process::{self, Command};
+    use std::os::unix::process::CommandExt;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+    static SUSPENDED: AtomicBool = AtomicBool::new(false);
+
+    /// The length of time that jobs are given to exit after an interrupt has been forwarded to them,
+    /// before they are killed.
+    const INTERRUPT_GRACE: Duration = Duration::from_secs(2);
+
+    /// Spawns the command as the leader of a new process group, so that signals sent to the job will
+    /// also reach any processes that it has spawned, such as each command in `sh -c 'sleep 999 | foo'`.
+    /// As the group is not in the foreground of the terminal, a job which reads from, or configures, the
+    /// terminal would be stopped by `SIGTTIN` or `SIGTTOU`, so these signals are ignored by the job, and
+    /// reading from the terminal fails instead.
+    pub fn set_process_group(command: &mut Command) {
+        unsafe {
+            command.pre_exec(|| {
+                libc::setpgid(0, 0);
+                libc::signal(libc::SIGTTIN, libc::SIG_IGN);
+                libc::signal(libc::SIGTTOU, libc::SIG_IGN);
+                Ok(())
+            });
+        }
+    }
+
+    /// Sends the signal to the process group of the job with the given process ID.
+    pub fn signal(pid: u32, signal: libc::c_int) {
+        unsafe { libc::kill(-(pid as libc::pid_t), signal); }
+    }
+
+    /// Sends `SIGTERM` to the process group of the job with the given process ID.
+    pub fn terminate(pid: u32) { signal(pid, libc::SIGTERM); }
+
+    /// Sends `SIGKILL` to the process group of the job with the given process ID.
+    pub fn kill(pid: u32) { signal(pid, libc::SIGKILL); }
+
+    /// Returns `true` while any process remains within the process group of the job with the given process ID.
+    fn group_exists(pid: u32) -> bool { unsafe { libc::kill(-(pid as libc::pid_t), 0) == 0 } }
+
+    /// Forwards the interrupt to the process group of each running job, and waits for the groups to exit,
+    /// killing those which remain once the grace period has elapsed, so that no jobs are left behind.
+    fn interrupt_all() {
+        let groups = super::RUNNING.lock().unwrap().clone();
+        log!(Info, "interrupted; forwarding the interrupt to {} running jobs", groups.len());
+        for &pid in &groups { signal(pid, libc::SIGINT); }
+
+        let start = Instant::now();
+        while groups.iter().any(|&pid| group_exists(pid)) {
+            if start.elapsed() >= INTERRUPT_GRACE {
+                log!(Info, "killing the jobs which did not exit after being interrupted");
+                for &pid in &groups { kill(pid); }
+                break
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    extern "C" fn interrupted(_signal: libc::c_int) { INTERRUPTED.store(true, Ordering::SeqCst); }
+
+    extern "C" fn suspended(_signal: libc::c_int) { SUSPENDED.store(true, Ordering::SeqCst); }
+
+    /// Jobs will not receive the `SIGINT` from a Ctrl-C, or the `SIGTSTP` from a Ctrl-Z, because they
+    /// are not within the foreground process group of the terminal. An interrupt will instead be
+    /// forwarded to each running job, which are killed if they have not exited shortly after, before
+    /// exiting, and a suspension will stop each running job along with this process, until this process
+    /// is continued.
+    pub fn handle_signals() {
+        unsafe {
+            libc::signal(libc::SIGINT, interrupted as extern "C" fn(libc::c_int) as libc::sighandler_t);
+            libc::signal(libc::SIGTERM, interrupted as extern "C" fn(libc::c_int) as libc::sighandler_t);
+            libc::signal(libc::SIGTSTP, suspended as extern "C" fn(libc::c_int) as libc::sighandler_t);
+        }
+
+        thread::spawn(|| loop {
+            thread::sleep(Duration::from_millis(50));
+            if INTERRUPTED.load(Ordering::SeqCst) {
+                interrupt_all();
+                for action in super::ON_INTERRUPT.lock().unwrap().iter() { action(); }
+                process::exit(130);
+            }
+
+            if SUSPENDED.swap(false, Ordering::SeqCst) { suspend(); }
+        });
+    }
+
+    /// Stops every running job, and then this process. Once this process has been continued, the
+    /// jobs will also be continued, and new jobs may be started again.
+    fn suspend() {
+        super::PAUSED.store(true, Ordering::SeqCst);
+        {
+            let running = super::RUNNING.lock().unwrap();
+            log!(Info, "suspended; stopping {} running jobs", running.len());
+            for &pid in running.iter() { signal(pid, libc::SIGSTOP); }
+            unsafe { libc::kill(libc::getpid(), libc::SIGSTOP); }
+            log!(Info, "continued; resuming {} running jobs", running.len());
+            for &pid in running.iter() { signal(pid, libc::SIGCONT); }
+        }
+        super::PAUSED.store(false, Ordering::SeqCst);
+    }
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::process::{Command, Stdio};
+
+    /// Processes on Windows receive the Ctrl-C from the console that they share.
+    pub fn set_process_group(_command: &mut Command) {}
+
+    /// Terminates the process with the given process ID, along with its child processes.
+    pub fn terminate(pid: u32) {
+        let _ = Command::new("taskkill").args(&["/F", "/T", "/PID", &pid.to_string()])
+            .stdout(Stdio::null()).stderr(Stdio::null()).status();
+    }
+
+    /// Windows has no distinction between terminating and killing a process.
+    pub fn kill(pid: u32) { terminate(pid) }
+
+    /// Jobs share the console of this process, so they will receive the Ctrl-C themselves.
+    pub fn handle_signals() {}
+}
+
+pub use self::platform::{handle_signals, kill, set_process_group, terminate};
@@ -1,4 +1,4 @@
-use arguments;
+use arguments::Config;
 use tokenizer::Token;
 use std::env;
 use std::fs;
@@ -21,7 +21,8 @@ pub fn required(kind: Kind) -> bool {
                 }
             }
         },
-        Kind::Input(arg) => if arg.as_bytes().iter().any(|&x| x == b';' || x == b'&' || x == b'|' || x == b'$' || x == b'<' || x == b'>' || x == b'[' || x == b']' || x == b'@') {
+        // Commands which span multiple lines must also be executed within a shell.
+        Kind::Input(arg) => if arg.as_bytes().iter().any(|&x| x == b';' || x == b'&' || x == b'|' || x == b'$' || x == b'<' || x == b'>' || x == b'[' || x == b']' || x == b'@' || x == b'\n') {
             return true
         }
     }
@@ -29,31 +30,22 @@ pub fn required(kind: Kind) -> bool {
 }
 
 /// Returns `true` if the Dash shell was found within the `PATH` environment variable.
-pub fn dash_exists() -> bool {
-    if let Ok(path) = env::var("PATH") {
-        for path in path.split(':') {
-            if let Ok(directory) = fs::read_dir(path) {
-                for entry in directory {
-                    if let Ok(entry) = entry {
-                        let path = entry.path();
-                        if path.is_file() && path.file_name() == Some(OsStr::new("dash")) { return true; }
-                    }
-                }
-            }
-        }
-    }
-    false
-}
+pub fn dash_exists() -> bool { exists("dash") }
 
 /// Returns `true` if the Ion shell was found within the `PATH` environment variable.
-pub fn ion_exists() -> bool {
-    if let Ok(path) = env::var("PATH") {
-        for path in path.split(':') {
+pub fn ion_exists() -> bool { exists("ion") }
+
+/// Searches each directory within the `PATH` environment variable for the given executable.
+/// On Windows, executables are expected to have the `.exe` extension.
+pub fn exists(name: &str) -> bool {
+    let name = if cfg!(windows) { format!("{}.exe", name) } else { name.to_owned() };
+    if let Some(path) = env::var_os("PATH") {
+        for path in env::split_paths(&path) {
             if let Ok(directory) = fs::read_dir(path) {
                 for entry in directory {
                     if let Ok(entry) = entry {
                         let path = entry.path();
-                        if path.is_file() && path.file_name() == Some(OsStr::new("ion")) { return true; }
+                        if path.is_file() && path.file_name() == Some(OsStr::new(&name)) { return true; }
                     }
                 }
             }
@@ -62,15 +54,28 @@ pub fn ion_exists() -> bool {
     false
 }
 
-/// Sets the corresponding flags if a shell is required and if dash exists.
-pub fn set_flags(flags: &mut u16, arguments: &[Token]) {
-    if required(Kind::Tokens(arguments)) {
-        if ion_exists() {
-            *flags |= arguments::SHELL_ENABLED + arguments::ION_EXISTS;
+/// Sets the corresponding options if a shell is required and if dash exists. A shell is required
+/// if the command contains shell syntax, or if the inputs are to be quoted.
+/// If the `--no-shell` parameter was supplied, commands will never be executed within a shell.
+pub fn set_flags(config: &mut Config, arguments: &[Token]) {
+    if config.no_shell {
+        // Without a shell, the quotes would be passed to the command verbatim.
+        config.quote_inputs = false;
+        return
+    }
+    // Quoted inputs must be interpreted by a shell to remove the quotes.
+    if config.quote_inputs || required(Kind::Tokens(arguments)) {
+        if cfg!(windows) {
+            // Commands are always executed within `cmd` on Windows.
+            config.shell_enabled = true;
+        } else if ion_exists() {
+            config.shell_enabled = true;
+            config.ion_exists = true;
         } else if dash_exists() {
-            *flags |= arguments::SHELL_ENABLED + arguments::DASH_EXISTS;
+            config.shell_enabled = true;
+            config.dash_exists = true;
         } else {
-            *flags |= arguments::SHELL_ENABLED;
+            config.shell_enabled = true;
         }
     }
 }
@@ -0,0 +1,142 @@
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use running;
+use summary;
+
+/// The interval between each rewrite of the `--status-file`.
+const STATUS_INTERVAL: Duration = Duration::from_secs(2);
+
+/// How often the status thread checks whether the run has finished while it waits.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A JSON file describing the progress of the run, which is periodically rewritten so that
+/// monitoring scripts may poll it, as given by the `--status-file` parameter.
+pub struct StatusFile {
+    path:  PathBuf,
+    /// The total number of jobs, which is unknown when inputs are streamed, unless given by `--total-jobs`.
+    total: Option<usize>,
+    start: Instant,
+}
+
+/// The progress of the run at the time that the status file is written.
+struct Status {
+    succeeded: usize,
+    failed:    usize,
+    timed_out: usize,
+    running:   usize,
+    elapsed:   Duration,
+    finished:  bool,
+}
+
+impl StatusFile {
+    pub fn new(path: PathBuf, total: Option<usize>) -> StatusFile {
+        StatusFile { path: path, total: total, start: Instant::now() }
+    }
+
+    /// Writes the current status to a temporary file which then replaces the status file, so that
+    /// readers will never observe a partially-written status.
+    fn write(&self, finished: bool) -> io::Result<()> {
+        let (succeeded, failed, timed_out) = summary::counts();
+        let status = Status {
+            succeeded: succeeded,
+            failed:    failed,
+            timed_out: timed_out,
+            running:   if finished { 0 } else { running::count() },
+            elapsed:   self.start.elapsed(),
+            finished:  finished,
+        };
+
+        let mut temporary = self.path.clone().into_os_string();
+        temporary.push(".tmp");
+        File::create(&temporary)?.write_all(self.render(&status).as_bytes())?;
+        fs::rename(&temporary, &self.path)
+    }
+
+    fn render(&self, status: &Status) -> String {
+        let completed = status.succeeded + status.failed + status.timed_out;
+        let elapsed = duration_to_millis(status.elapsed);
+
+        // The remaining jobs are estimated to take as long, on average, as the completed jobs.
+        let eta = match self.total {
+            Some(_) if status.finished => "0.000".to_owned(),
+            Some(total) if completed != 0 => {
+                let eta = elapsed / completed as u64 * total.saturating_sub(completed) as u64;
+                format!("{}.{:03}", eta / 1000, eta % 1000)
+            },
+            _ => "null".to_owned()
+        };
+
+        format!("{{\"total\":{},\"completed\":{},\"succeeded\":{},\"failed\":{},\"timed_out\":{},\"running\":{},\
+            \"elapsed\":{}.{:03},\"eta\":{},\"finished\":{}}}\n",
+            self.total.map_or("null".to_owned(), |total| total.to_string()), completed, status.succeeded,
+            status.failed, status.timed_out, status.running, elapsed / 1000, elapsed % 1000, eta, status.finished)
+    }
+}
+
+/// Periodically rewrites the status file on a separate thread until the run has finished.
+pub struct Monitor {
+    finished: Arc<AtomicBool>,
+    thread:   JoinHandle<StatusFile>,
+}
+
+impl Monitor {
+    pub fn start(status: StatusFile) -> Monitor {
+        let finished = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let finished = finished.clone();
+            thread::spawn(move || {
+                let mut last_write = Instant::now();
+                report(&status, false);
+                while !finished.load(Ordering::SeqCst) {
+                    thread::sleep(POLL_INTERVAL);
+                    if last_write.elapsed() >= STATUS_INTERVAL {
+                        report(&status, false);
+                        last_write = Instant::now();
+                    }
+                }
+                status
+            })
+        };
+
+        Monitor { finished: finished, thread: thread }
+    }
+
+    /// Stops the thread, and writes the final status of the run.
+    pub fn finish(self) {
+        self.finished.store(true, Ordering::SeqCst);
+        if let Ok(status) = self.thread.join() { report(&status, true); }
+    }
+}
+
+fn report(status: &StatusFile, finished: bool) {
+    if let Err(why) = status.write(finished) {
+        let stderr = io::stderr();
+        let _ = writeln!(stderr.lock(), "parallel: I/O error: unable to write status file {:?}: {}", status.path, why);
+    }
+}
+
+fn duration_to_millis(duration: Duration) -> u64 {
+    duration.as_secs() * 1000 + duration.subsec_nanos() as u64 / 1_000_000
+}
+
+#[test]
+fn status_rendering() {
+    let file = StatusFile::new(PathBuf::from("status.json"), Some(10));
+    let status = Status {
+        succeeded: 3, failed: 1, timed_out: 0, running: 2, elapsed: Duration::from_millis(8000), finished: false
+    };
+    assert_eq!(file.render(&status), "{\"total\":10,\"completed\":4,\"succeeded\":3,\"failed\":1,\"timed_out\":0,\
+        \"running\":2,\"elapsed\":8.000,\"eta\":12.000,\"finished\":false}\n");
+
+    let file = StatusFile::new(PathBuf::from("status.json"), None);
+    let status = Status {
+        succeeded: 0, failed: 0, timed_out: 0, running: 1, elapsed: Duration::from_millis(1500), finished: false
+    };
+    assert_eq!(file.render(&status), "{\"total\":null,\"completed\":0,\"succeeded\":0,\"failed\":0,\"timed_out\":0,\
+        \"running\":1,\"elapsed\":1.500,\"eta\":null,\"finished\":false}\n");
+}
@@ -0,0 +1,326 @@
+use execute::Usage;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::sync::Mutex;
+
+/// The number of failed jobs whose commands will be listed by the summary.
+pub const FAILURES_SHOWN: usize = 10;
+
+/// The number of the slowest jobs whose commands will be listed by the `--summary` statistics.
+pub const SLOWEST_SHOWN: usize = 5;
+
+/// A job that exited with a non-zero exit value, or that was killed by a signal.
+#[derive(Clone, Debug, PartialEq)]
+struct Failure {
+    job_id:     usize,
+    exit_value: i32,
+    signal:     i32,
+    timed_out:  bool,
+    command:    String,
+}
+
+/// A job whose runtime was among the longest of the run.
+#[derive(Clone, Debug, PartialEq)]
+struct Slow {
+    job_id:  usize,
+    runtime: u64,
+    command: String,
+}
+
+/// The job whose peak resident set size was the largest of the run.
+#[derive(Clone, Debug, PartialEq)]
+struct Largest {
+    job_id:  usize,
+    /// The peak resident set size of the job, in kilobytes.
+    max_rss: u64,
+    command: String,
+}
+
+/// The runtimes of the jobs that were given the same input by the `--repeat` parameter.
+#[derive(Clone, Debug, PartialEq)]
+struct Repetitions {
+    /// The command that was executed by the first of the jobs to finish.
+    command:  String,
+    runtimes: Vec<u64>,
+}
+
+impl Repetitions {
+    /// The mean and the sample standard deviation of the runtimes, in nanoseconds.
+    fn statistics(&self) -> (u64, u64) {
+        let count = self.runtimes.len() as f64;
+        let mean = self.runtimes.iter().map(|&runtime| runtime as f64).sum::<f64>() / count;
+        if self.runtimes.len() < 2 { return (mean as u64, 0) }
+        let variance = self.runtimes.iter().map(|&runtime| (runtime as f64 - mean).powi(2)).sum::<f64>() / (count - 1f64);
+        (mean as u64, variance.sqrt() as u64)
+    }
+}
+
+/// The outcomes of the jobs which have been executed within this run.
+#[derive(Debug, PartialEq)]
+struct Summary {
+    succeeded: usize,
+    failed:    usize,
+    timed_out: usize,
+    /// The failures with the lowest job IDs, sorted by their job IDs.
+    failures:  Vec<Failure>,
+    /// The runtime of every job, in nanoseconds, in the order that the jobs finished.
+    runtimes:  Vec<u64>,
+    /// The jobs with the longest runtimes, sorted from the longest runtime.
+    slowest:   Vec<Slow>,
+    /// The job which used the most memory, if the memory usage of jobs is known.
+    largest:   Option<Largest>,
+    /// The number of consecutive jobs that are given each input.
+    repeat:    usize,
+    /// The runtimes of the jobs of each input, by the index of the input, if inputs are repeated.
+    repeated:  BTreeMap<usize, Repetitions>,
+}
+
+static SUMMARY: Mutex<Summary> = Mutex::new(Summary::new());
+
+impl Summary {
+    const fn new() -> Summary {
+        Summary {
+            succeeded: 0, failed: 0, timed_out: 0, failures: Vec::new(), runtimes: Vec::new(), slowest: Vec::new(),
+            largest: None, repeat: 1, repeated: BTreeMap::new()
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn record(&mut self, job_id: usize, exit_value: i32, signal: i32, timed_out: bool, runtime: u64, usage: Usage,
+        command: &str)
+    {
+        self.runtimes.push(runtime);
+        if usage.max_rss != 0 && self.largest.as_ref().is_none_or(|largest| largest.max_rss < usage.max_rss) {
+            self.largest = Some(Largest { job_id, max_rss: usage.max_rss, command: command.to_owned() });
+        }
+        if self.repeat > 1 {
+            self.repeated.entry((job_id - 1) / self.repeat)
+                .or_insert_with(|| Repetitions { command: command.to_owned(), runtimes: Vec::new() })
+                .runtimes.push(runtime);
+        }
+        let pos = self.slowest.iter().position(|slow| slow.runtime < runtime).unwrap_or(self.slowest.len());
+        if pos < SLOWEST_SHOWN {
+            self.slowest.insert(pos, Slow { job_id, runtime, command: command.to_owned() });
+            self.slowest.truncate(SLOWEST_SHOWN);
+        }
+
+        if exit_value == 0 && signal == 0 {
+            self.succeeded += 1;
+            return
+        }
+
+        if timed_out { self.timed_out += 1; } else { self.failed += 1; }
+
+        // Jobs finish out of order, so only the failures of the earliest jobs are kept.
+        let pos = match self.failures.binary_search_by_key(&job_id, |failure| failure.job_id) {
+            Ok(pos) | Err(pos) => pos
+        };
+        if pos < FAILURES_SHOWN {
+            self.failures.insert(pos, Failure {
+                job_id, exit_value, signal, timed_out, command: command.to_owned()
+            });
+            self.failures.truncate(FAILURES_SHOWN);
+        }
+    }
+
+    fn write<W: Write>(&self, output: &mut W, wall_ns: u64, cpu_ns: Option<u64>) -> io::Result<()> {
+        write!(output, "parallel: {} succeeded, {} failed, {} timed out in {}",
+            self.succeeded, self.failed, self.timed_out, Seconds(wall_ns))?;
+        match cpu_ns {
+            Some(cpu_ns) => writeln!(output, " ({} CPU)", Seconds(cpu_ns))?,
+            None => writeln!(output)?
+        }
+
+        for failure in &self.failures {
+            if failure.timed_out {
+                writeln!(output, "    job {}: timed out: {}", failure.job_id, failure.command)?;
+            } else if failure.signal != 0 {
+                writeln!(output, "    job {}: killed by signal {}: {}", failure.job_id, failure.signal, failure.command)?;
+            } else {
+                writeln!(output, "    job {}: exited with {}: {}", failure.job_id, failure.exit_value, failure.command)?;
+            }
+        }
+
+        let unlisted = self.failed + self.timed_out - self.failures.len();
+        if unlisted != 0 {
+            writeln!(output, "    and {} more", unlisted)?;
+        }
+        Ok(())
+    }
+
+    fn write_statistics<W: Write>(&self, output: &mut W, wall_ns: u64, cpu_ns: Option<u64>) -> io::Result<()> {
+        let jobs = self.runtimes.len();
+        let throughput = if wall_ns == 0 { 0f64 } else { jobs as f64 / (wall_ns as f64 / 1_000_000_000f64) };
+        writeln!(output, "parallel: {} jobs at {:.2} jobs/s", jobs, throughput)?;
+        if jobs == 0 { return Ok(()) }
+
+        let mut runtimes = self.runtimes.clone();
+        runtimes.sort();
+        let mean = runtimes.iter().sum::<u64>() / jobs as u64;
+        let median = if jobs.is_multiple_of(2) {
+            (runtimes[jobs / 2 - 1] + runtimes[jobs / 2]) / 2
+        } else {
+            runtimes[jobs / 2]
+        };
+        writeln!(output, "    runtime: min {}, max {}, mean {}, median {}", Seconds(runtimes[0]),
+            Seconds(runtimes[jobs - 1]), Seconds(mean), Seconds(median))?;
+
+        if let Some(cpu_ns) = cpu_ns {
+            let ratio = if wall_ns == 0 { 0f64 } else { cpu_ns as f64 / wall_ns as f64 };
+            writeln!(output, "    CPU time: {} over {} of wall time ({:.2}x)", Seconds(cpu_ns), Seconds(wall_ns), ratio)?;
+        }
+
+        if let Some(ref largest) = self.largest {
+            writeln!(output, "    peak memory: {} KiB by job {}: {}", largest.max_rss, largest.job_id, largest.command)?;
+        }
+
+        writeln!(output, "    slowest:")?;
+        for slow in &self.slowest {
+            writeln!(output, "        job {}: {}: {}", slow.job_id, Seconds(slow.runtime), slow.command)?;
+        }
+
+        if !self.repeated.is_empty() {
+            writeln!(output, "    inputs:")?;
+            for (input, repetitions) in &self.repeated {
+                let (mean, deviation) = repetitions.statistics();
+                writeln!(output, "        input {}: mean {}, stddev {} over {} runs: {}", input + 1, Seconds(mean),
+                    Seconds(deviation), repetitions.runtimes.len(), repetitions.command)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Displays a number of nanoseconds as seconds, to the hundredth of a second.
+struct Seconds(u64);
+
+impl ::std::fmt::Display for Seconds {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        write!(f, "{}.{:02}s", self.0 / 1_000_000_000, self.0 % 1_000_000_000 / 10_000_000)
+    }
+}
+
+/// Records the outcome, the runtime in nanoseconds, and the resource usage of a job, given by its job ID
+/// counting from 1, and the command that it executed.
+pub fn job_finished(job_id: usize, exit_value: i32, signal: i32, timed_out: bool, runtime: u64, usage: Usage,
+    command: &str)
+{
+    SUMMARY.lock().unwrap().record(job_id, exit_value, signal, timed_out, runtime, usage, command);
+}
+
+/// Sets the number of consecutive jobs that are given each input by the `--repeat` parameter, so that the
+/// runtimes of the jobs of each input will be summarized together.
+pub fn repeat_inputs(repeat: usize) {
+    SUMMARY.lock().unwrap().repeat = repeat;
+}
+
+/// Returns the numbers of jobs that have succeeded, failed, and timed out.
+pub fn counts() -> (usize, usize, usize) {
+    let summary = SUMMARY.lock().unwrap();
+    (summary.succeeded, summary.failed, summary.timed_out)
+}
+
+/// Returns `true` if any job has failed or timed out.
+pub fn has_failures() -> bool {
+    let summary = SUMMARY.lock().unwrap();
+    summary.failed + summary.timed_out != 0
+}
+
+/// Writes the number of jobs that succeeded, failed, and timed out, the first failed commands, and the
+/// wall and CPU time that the run took, given the wall time in nanoseconds.
+pub fn report<W: Write>(output: &mut W, wall_ns: u64) -> io::Result<()> {
+    SUMMARY.lock().unwrap().write(output, wall_ns, cpu_time())
+}
+
+/// Writes the statistics of the runtimes of jobs for the `--summary` parameter: the throughput of the run,
+/// the minimum, maximum, mean, and median runtimes, the CPU time against the wall time, the job with the
+/// largest peak memory, and the slowest jobs.
+pub fn statistics<W: Write>(output: &mut W, wall_ns: u64) -> io::Result<()> {
+    SUMMARY.lock().unwrap().write_statistics(output, wall_ns, cpu_time())
+}
+
+/// The CPU time, in nanoseconds, that was consumed by the jobs which have exited.
+#[cfg(unix)]
+fn cpu_time() -> Option<u64> {
+    use libc;
+    use std::mem;
+
+    let mut usage: libc::rusage = unsafe { mem::zeroed() };
+    if unsafe { libc::getrusage(libc::RUSAGE_CHILDREN, &mut usage) } != 0 { return None }
+    let nanoseconds = |time: libc::timeval| time.tv_sec as u64 * 1_000_000_000 + time.tv_usec as u64 * 1000;
+    Some(nanoseconds(usage.ru_utime) + nanoseconds(usage.ru_stime))
+}
+
+/// The CPU time of jobs is not obtained on other platforms.
+#[cfg(not(unix))]
+fn cpu_time() -> Option<u64> { None }
+
+#[test]
+fn summary_report() {
+    let mut summary = Summary::new();
+    summary.record(2, 0, 0, false, 0, Usage::default(), "echo 2");
+    for job_id in (3..15).rev() {
+        summary.record(job_id, 1, 0, false, 0, Usage::default(), "false");
+    }
+    summary.record(1, 0, 15, true, 0, Usage::default(), "sleep 10");
+
+    assert_eq!(summary.succeeded, 1);
+    assert_eq!(summary.failed, 12);
+    assert_eq!(summary.timed_out, 1);
+    assert_eq!(summary.failures.len(), FAILURES_SHOWN);
+    assert_eq!(summary.failures[0].job_id, 1);
+    assert_eq!(summary.failures[9].job_id, 11);
+
+    let mut output = Vec::new();
+    summary.write(&mut output, 1_250_000_000, Some(30_000_000)).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    let mut lines = output.lines();
+    assert_eq!(lines.next(), Some("parallel: 1 succeeded, 12 failed, 1 timed out in 1.25s (0.03s CPU)"));
+    assert_eq!(lines.next(), Some("    job 1: timed out: sleep 10"));
+    assert_eq!(lines.next(), Some("    job 3: exited with 1: false"));
+    assert_eq!(lines.last(), Some("    and 3 more"));
+}
+
+#[test]
+fn summary_statistics() {
+    let mut summary = Summary::new();
+    for job_id in 1..9 {
+        let usage = Usage { max_rss: [1024, 4096, 2048][job_id % 3], ..Usage::default() };
+        summary.record(job_id, 0, 0, false, job_id as u64 * 100_000_000, usage, &format!("sleep 0.{}", job_id));
+    }
+
+    assert_eq!(summary.slowest.len(), SLOWEST_SHOWN);
+    assert_eq!(summary.slowest[0].job_id, 8);
+
+    let mut output = Vec::new();
+    summary.write_statistics(&mut output, 2_000_000_000, Some(3_000_000_000)).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    let mut lines = output.lines();
+    assert_eq!(lines.next(), Some("parallel: 8 jobs at 4.00 jobs/s"));
+    assert_eq!(lines.next(), Some("    runtime: min 0.10s, max 0.80s, mean 0.45s, median 0.45s"));
+    assert_eq!(lines.next(), Some("    CPU time: 3.00s over 2.00s of wall time (1.50x)"));
+    assert_eq!(lines.next(), Some("    peak memory: 4096 KiB by job 1: sleep 0.1"));
+    assert_eq!(lines.next(), Some("    slowest:"));
+    assert_eq!(lines.next(), Some("        job 8: 0.80s: sleep 0.8"));
+    assert_eq!(lines.last(), Some("        job 4: 0.40s: sleep 0.4"));
+}
+
+#[test]
+fn summary_repetitions() {
+    let mut summary = Summary::new();
+    summary.repeat = 3;
+    for (job_id, &runtime) in [100, 200, 300, 1000, 1000, 1000].iter().enumerate() {
+        summary.record(job_id + 1, 0, 0, false, runtime * 1_000_000, Usage::default(), if job_id < 3 { "a" } else { "b" });
+    }
+
+    assert_eq!(summary.repeated.len(), 2);
+    assert_eq!(summary.repeated[&0].statistics(), (200_000_000, 100_000_000));
+    assert_eq!(summary.repeated[&1].statistics(), (1_000_000_000, 0));
+
+    let mut output = Vec::new();
+    summary.write_statistics(&mut output, 1_000_000_000, None).unwrap();
+    let output = String::from_utf8(output).unwrap();
+    let mut lines = output.lines().skip_while(|&line| line != "    inputs:").skip(1);
+    assert_eq!(lines.next(), Some("        input 1: mean 0.20s, stddev 0.10s over 3 runs: a"));
+    assert_eq!(lines.next(), Some("        input 2: mean 1.00s, stddev 0.00s over 3 runs: b"));
+}
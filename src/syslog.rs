@@ -0,0 +1,41 @@
+/// Opens the connection to the system logger, which also reaches journald on systems with systemd.
+#[cfg(unix)]
+pub fn open() {
+    use libc;
+    unsafe { libc::openlog(b"parallel\0".as_ptr() as *const libc::c_char, libc::LOG_PID, libc::LOG_USER); }
+}
+
+/// Records the result of a completed job to the system logger, as a warning if the job failed.
+#[cfg(unix)]
+pub fn job_finished(job: usize, exit_value: i32, signal: i32, runtime: u64, command: &str) {
+    use libc;
+    use std::ffi::CString;
+
+    let priority = if exit_value == 0 && signal == 0 { libc::LOG_INFO } else { libc::LOG_WARNING };
+    let message = record(job, exit_value, signal, runtime, command);
+    if let Ok(message) = CString::new(message) {
+        unsafe {
+            libc::syslog(priority, b"%s\0".as_ptr() as *const libc::c_char, message.as_ptr());
+        }
+    }
+}
+
+/// There is no system logger to write to on this platform.
+#[cfg(not(unix))]
+pub fn open() {}
+
+/// There is no system logger to write to on this platform.
+#[cfg(not(unix))]
+pub fn job_finished(_job: usize, _exit_value: i32, _signal: i32, _runtime: u64, _command: &str) {}
+
+/// Formats the completion record of a job, where the runtime is given in nanoseconds.
+#[cfg(any(unix, test))]
+fn record(job: usize, exit_value: i32, signal: i32, runtime: u64, command: &str) -> String {
+    format!("seq={} exit={} signal={} runtime={}.{:03} command={}", job, exit_value, signal,
+        runtime / 1_000_000_000, runtime % 1_000_000_000 / 1_000_000, command)
+}
+
+#[test]
+fn syslog_record() {
+    assert_eq!(record(3, 1, 0, 1_500_000_000, "echo 3"), "seq=3 exit=1 signal=0 runtime=1.500 command=echo 3");
+}
@@ -1,15 +1,40 @@
-/// Removes the extension of a given input
+use raw;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+/// Obtains the position of the file name within the input, and the file name, unless the file name
+/// can not have an extension, such as `..`.
+fn file_name(input: &str) -> Option<(usize, &str)> {
+    let start = input.rfind('/').map_or(0, |index| index + 1);
+    let name = &input[start..];
+    if name == "." || name == ".." { None } else { Some((start, name)) }
+}
+
+/// Removes the last extension of a given input, so that `file.tar.gz` becomes `file.tar`. The leading
+/// dot of a hidden file, such as `.bashrc`, does not begin an extension, and a trailing dot is removed.
 pub fn remove_extension(input: &str) -> &str {
-    let mut dir_index = 0;
-    let mut ext_index = 0;
+    match file_name(input).and_then(|(start, name)| name.rfind('.').map(|dot| (start, dot))) {
+        Some((start, dot)) if dot != 0 => &input[..start + dot],
+        _ => input
+    }
+}
 
-    for (id, character) in input.bytes().enumerate() {
-        if character == b'/' { dir_index = id; }
-        if character == b'.' { ext_index = id; }
+/// Removes every extension of a given input, so that `file.tar.gz` becomes `file`, and `.config.tar`
+/// becomes `.config`.
+pub fn remove_extensions(input: &str) -> &str {
+    // The first character is skipped, as the leading dot of a hidden file does not begin an extension.
+    let first_dot = |(start, name): (usize, &str)| name.bytes().skip(1).position(|x| x == b'.').map(|dot| (start, dot));
+    match file_name(input).and_then(first_dot) {
+        Some((start, dot)) => &input[..start + dot + 1],
+        None => input
     }
+}
 
-    // Account for hidden files and directories
-    if ext_index == 0 || dir_index + 2 > ext_index { input } else { &input[0..ext_index] }
+/// Obtains the extension of a given input, without the `.`, or nothing if the input lacks an extension.
+pub fn extension(input: &str) -> &str {
+    let stem = remove_extension(input);
+    if stem.len() == input.len() { "" } else { &input[stem.len()+1..] }
 }
 
 pub fn remove_pattern<'a>(input: &'a str, pattern: &str) -> &'a str {
@@ -20,6 +45,20 @@ pub fn remove_pattern<'a>(input: &'a str, pattern: &str) -> &'a str {
     }
 }
 
+/// Resolves the input into an absolute path, with every symbolic link resolved. Paths which do not exist
+/// are instead made absolute relative to the current working directory.
+pub fn realpath(input: &str) -> String {
+    let path = raw::encode_os(input);
+    let path = Path::new(&*path);
+    match fs::canonicalize(path) {
+        Ok(path) => raw::decode_os(path.as_os_str()),
+        Err(_) => match env::current_dir() {
+            Ok(directory) => raw::decode_os(directory.join(path).as_os_str()),
+            Err(_) => input.to_owned()
+        }
+    }
+}
+
 pub fn basename(input: &str) -> &str {
     let mut index = 0;
     for (id, character) in input.bytes().enumerate() {
@@ -65,6 +104,34 @@ mod tests {
         assert_eq!(remove_extension(""), "");
     }
 
+    #[test]
+    fn path_remove_ext_multiple() {
+        assert_eq!(remove_extension("a.txt"), "a");
+        assert_eq!(remove_extension("dir/file.tar.gz"), "dir/file.tar");
+        assert_eq!(remove_extension("dir/.config.tar"), "dir/.config");
+        assert_eq!(remove_extension("file."), "file");
+        assert_eq!(remove_extension("dir.d/.."), "dir.d/..");
+        assert_eq!(remove_extensions("dir.d/file.tar.gz"), "dir.d/file");
+        assert_eq!(remove_extensions(".config.tar.gz"), ".config");
+        assert_eq!(remove_extensions(".bashrc"), ".bashrc");
+        assert_eq!(remove_extensions("dir/"), "dir/");
+        assert_eq!(remove_extensions("."), ".");
+    }
+
+    #[test]
+    fn path_extension() {
+        assert_eq!(extension("dir/foo.tar.gz"), "gz");
+        assert_eq!(extension("dir.d/foo"), "");
+        assert_eq!(extension(".foo"), "");
+    }
+
+    #[test]
+    fn path_realpath() {
+        let directory = env::current_dir().unwrap();
+        assert_eq!(realpath("."), raw::decode_os(fs::canonicalize(&directory).unwrap().as_os_str()));
+        assert_eq!(realpath("missing/file"), raw::decode_os(directory.join("missing/file").as_os_str()));
+    }
+
     #[test]
     fn path_basename_simple() {
         assert_eq!(basename("foo.txt"), "foo.txt");
@@ -1,23 +1,52 @@
 pub mod functions;
 
-use arrayvec::ArrayVec;
+use input_iterator::UNKNOWN_TOTAL;
 use std::fmt;
-use std::io;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Lines};
 use std::path::Path;
 use std::borrow::Cow;
 pub use self::functions::*;
 
+/// The byte range within a command template that a token was parsed from.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Span {
+    /// The index of the first byte of the token.
+    pub start: usize,
+    /// The index following the last byte of the token.
+    pub end:   usize,
+}
+
+impl Span {
+    fn new(start: usize, end: usize) -> Span { Span { start: start, end: end } }
+}
+
+/// The errors that may occur when tokenizing a command template.
 #[derive(Debug)]
 pub enum TokenErr {
+    /// The Nth input could not be read from the unprocessed file.
     File(io::Error),
-    OutOfBounds,
+    /// A `{N}` token referenced an input which does not exist, as `(span, N, number of inputs)`.
+    OutOfBounds(Span, usize, usize),
+    /// A `{N}` token was used while inputs are streamed, so the Nth input is unknown, as `(span, N)`.
+    Streamed(Span, usize),
+    /// A `{` was opened at the given byte position but was never closed.
+    Unclosed(usize),
 }
 
 impl fmt::Display for TokenErr {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
             TokenErr::File(ref io) => write!(f, "parallel: unable to obtain the Nth input: {}", io),
-            TokenErr::OutOfBounds  => write!(f, "parallel: input token out of bounds")
+            TokenErr::OutOfBounds(span, number, nargs) => {
+                write!(f, "parallel: input token out of bounds at {}..{}: {{{}}} exceeds the {} available inputs",
+                    span.start, span.end, number, nargs)
+            },
+            TokenErr::Streamed(span, number) => {
+                write!(f, "parallel: input token at {}..{}: {{{}}} can not be used when inputs are streamed",
+                    span.start, span.end, number)
+            },
+            TokenErr::Unclosed(position) => write!(f, "parallel: unclosed '{{' at position {}", position)
         }
     }
 }
@@ -29,24 +58,79 @@ pub enum Token {
     Argument(Cow<'static, str>),
     /// Takes the basename (file name) of the input with the extension removed.
     BaseAndExt,
+    /// Takes the basename (file name) of the input with every extension removed.
+    BaseAndExts,
     /// Takes the basename (file name) of the input with a custom suffix removed.
     BaseAndSuffix(&'static str),
     /// Takes the basename (file name) of the input with the directory path removed.
     Basename,
+    /// Transforms the case of the value of a token which is derived from the input.
+    Case(Case, Box<Token>),
     /// Takes the directory path of the input with the basename removed.
     Dirname,
+    /// Takes the extension of the input, without the `.`.
+    Extension,
     /// Returns the job ID of the current input.
     Job,
+    /// Returns the total number of jobs.
+    JobTotal,
     /// Takes the input, unmodified.
     Placeholder,
+    /// Takes the absolute path of the input, with symbolic links resolved.
+    RealPath,
     /// Removes the extension from the input.
     RemoveExtension,
+    /// Removes every extension from the input.
+    RemoveExtensions,
     /// Removes a specified extension pattern
     RemoveSuffix(&'static str),
+    /// Returns the number of the last record within the current input.
+    SeqEnd,
+    /// Returns the number of the first record within the current input.
+    SeqStart,
     /// Returns the thread ID.
     Slot
 }
 
+/// The transformation applied by a case modifier, such as `{:u}` or `{/:l}`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Case {
+    Lower,
+    Upper,
+}
+
+impl Case {
+    pub fn apply(self, value: &str) -> String {
+        match self {
+            Case::Lower => value.to_lowercase(),
+            Case::Upper => value.to_uppercase(),
+        }
+    }
+}
+
+impl Token {
+    /// Derives the value of the token from the given input, or returns `None` if the token is not
+    /// derived from the input, such as the job number.
+    pub fn derive<'a>(&self, input: &'a str) -> Option<Cow<'a, str>> {
+        let value = match *self {
+            Token::Basename              => basename(input),
+            Token::BaseAndExt            => basename(remove_extension(input)),
+            Token::BaseAndExts           => basename(remove_extensions(input)),
+            Token::BaseAndSuffix(pat)    => basename(remove_pattern(input, pat)),
+            Token::Case(case, ref token) => return token.derive(input).map(|value| Cow::Owned(case.apply(&value))),
+            Token::Dirname               => dirname(input),
+            Token::Extension             => extension(input),
+            Token::Placeholder           => input,
+            Token::RealPath              => return Some(Cow::Owned(realpath(input))),
+            Token::RemoveExtension       => remove_extension(input),
+            Token::RemoveExtensions      => remove_extensions(input),
+            Token::RemoveSuffix(pat)     => remove_pattern(input, pat),
+            _                            => return None
+        };
+        Some(Cow::Borrowed(value))
+    }
+}
+
 struct Number {
     id: usize,
     token: Token,
@@ -57,32 +141,86 @@ impl Number {
         Number{ id: id, token: token }
     }
 
-    fn into_argument(self, path: &Path) -> Result<String, TokenErr> {
-        use std::fs::File;
-        use std::io::{BufRead, BufReader};
-        let file = File::open(path).map_err(TokenErr::File)?;
-        let input = &BufReader::new(file).lines().nth(self.id-1).unwrap().map_err(TokenErr::File)?;
-        let argument = match self.token {
-            Token::Argument(_)        => unreachable!(),
-            Token::Basename           => basename(input),
-            Token::BaseAndExt         => basename(remove_extension(input)),
-            Token::BaseAndSuffix(pat) => basename(remove_pattern(input, pat)),
-            Token::Dirname            => dirname(input),
-            Token::Job                => unreachable!(),
-            Token::Placeholder        => input,
-            Token::RemoveExtension    => remove_extension(input),
-            Token::RemoveSuffix(pat)  => remove_pattern(input, pat),
-            Token::Slot               => unreachable!()
-        };
-        Ok(String::from(argument))
+    fn into_argument(self, inputs: &mut NthInputs) -> Result<String, TokenErr> {
+        let input = inputs.get(self.id)?;
+        Ok(self.token.derive(input).map_or_else(String::new, Cow::into_owned))
+    }
+}
+
+/// The inputs that `{N}` tokens refer to, which are read from the unprocessed file only once, and only
+/// as far as the largest N that has been requested, no matter how many `{N}` tokens a template has.
+struct NthInputs<'a> {
+    path:   &'a Path,
+    reader: Option<Lines<BufReader<File>>>,
+    /// The inputs which have been read so far.
+    lines:  Vec<String>,
+}
+
+impl<'a> NthInputs<'a> {
+    fn new(path: &'a Path) -> NthInputs<'a> {
+        NthInputs { path: path, reader: None, lines: Vec::new() }
+    }
+
+    /// Obtains the Nth input, counting from 1.
+    fn get(&mut self, number: usize) -> Result<&str, TokenErr> {
+        if self.reader.is_none() {
+            self.reader = Some(BufReader::new(File::open(self.path).map_err(TokenErr::File)?).lines());
+        }
+
+        let reader = self.reader.as_mut().unwrap();
+        while self.lines.len() < number {
+            match reader.next() {
+                Some(line) => self.lines.push(line.map_err(TokenErr::File)?),
+                None => return Err(TokenErr::File(io::Error::new(io::ErrorKind::UnexpectedEof,
+                    format!("input {} does not exist", number))))
+            }
+        }
+
+        Ok(&self.lines[number-1])
     }
 }
 
+/// The strings which will be replaced with the job number and the slot number.
+#[derive(Clone, Copy, Debug)]
+pub struct Replacements {
+    /// Replaced with the job number, which is `{#}` by default.
+    pub seq:  &'static str,
+    /// Replaced with the slot number, which is `{%}` by default.
+    pub slot: &'static str,
+}
+
+impl Default for Replacements {
+    fn default() -> Replacements { Replacements { seq: "{#}", slot: "{%}" } }
+}
+
 /// Takes the command arguments as the input and reduces it into tokens,
 /// which allows for easier management of string manipulation later on.
-pub fn tokenize(tokens: &mut ArrayVec<[Token; 128]>, template: &'static str, path: &Path, nargs: usize)
-    -> Result<(), TokenErr>
+pub fn tokenize(tokens: &mut Vec<Token>, template: &'static str, path: &Path, nargs: usize,
+    replace: &Replacements) -> Result<(), TokenErr>
+{
+    for (token, _) in tokenize_spans_with(template, path, nargs, replace)? {
+        tokens.push(token);
+    }
+    Ok(())
+}
+
+/// Reduces a command template into tokens, along with the byte span within the template that
+/// each token was parsed from. `{N}` tokens are resolved by reading the Nth input from the file
+/// at `path`, and are validated against `nargs`, the total number of inputs, which is `UNKNOWN_TOTAL`
+/// when inputs are streamed.
+///
+/// Returns an error if a `{` was left unclosed, or if a `{N}` token is out of bounds or can not be resolved.
+pub fn tokenize_spans(template: &'static str, path: &Path, nargs: usize) -> Result<Vec<(Token, Span)>, TokenErr> {
+    tokenize_spans_with(template, path, nargs, &Replacements::default())
+}
+
+/// The same as `tokenize_spans`, but the job and slot tokens are matched with the given `replace` strings.
+pub fn tokenize_spans_with(template: &'static str, path: &Path, nargs: usize, replace: &Replacements)
+    -> Result<Vec<(Token, Span)>, TokenErr>
 {
+    let mut tokens = Vec::new();
+    let mut inputs = NthInputs::new(path);
+
     // When set to true, the characters following will be collected into `pattern`.
     let mut pattern_matching = false;
     // Mark the index where the pattern's first character begins.
@@ -93,7 +231,30 @@ pub fn tokenize(tokens: &mut ArrayVec<[Token; 128]>, template: &'static str, pat
     // Mark the index where the argument's first character begins.
     let mut argument_start = 0;
 
-    for (id, character) in template.bytes().enumerate() {
+    let mut id = 0;
+    while let Some(&character) = template.as_bytes().get(id) {
+        // The job and slot replacement strings take precedence over all other patterns.
+        if !pattern_matching {
+            let matched = if !replace.seq.is_empty() && template[id..].starts_with(replace.seq) {
+                Some((Token::Job, replace.seq.len()))
+            } else if !replace.slot.is_empty() && template[id..].starts_with(replace.slot) {
+                Some((Token::Slot, replace.slot.len()))
+            } else {
+                None
+            };
+
+            if let Some((token, length)) = matched {
+                if argument_matching {
+                    argument_matching = false;
+                    let argument      = Cow::Borrowed(&template[argument_start..id]);
+                    tokens.push((Token::Argument(argument), Span::new(argument_start, id)));
+                }
+                tokens.push((token, Span::new(id, id + length)));
+                id += length;
+                continue
+            }
+        }
+
         match (character, pattern_matching) {
             // This condition initiates the pattern matching
             (b'{', false) => {
@@ -105,22 +266,23 @@ pub fn tokenize(tokens: &mut ArrayVec<[Token; 128]>, template: &'static str, pat
                 if argument_matching {
                     argument_matching = false;
                     let argument      = Cow::Borrowed(&template[argument_start..id]);
-                    tokens.push(Token::Argument(argument));
+                    tokens.push((Token::Argument(argument), Span::new(argument_start, id)));
                 }
             },
             // This condition ends the pattern matching process
             (b'}', true)  => {
                 pattern_matching = false;
+                let span = Span::new(pattern_start, id+1);
                 if id == pattern_start+1 {
                     // This condition will be met when the pattern is "{}".
-                    tokens.push(Token::Placeholder);
+                    tokens.push((Token::Placeholder, span));
                 } else {
                     // Supply the internal contents of the pattern to the token matcher.
-                    match match_token(&template[pattern_start+1..id], path, nargs)? {
+                    match match_token(&template[pattern_start+1..id], span, &mut inputs, nargs)? {
                         // If the token is a match, add the matched token.
-                        Some(token) => { tokens.push(token); },
+                        Some(token) => tokens.push((token, span)),
                         // If the token is not a match, add it as an argument.
-                        None => { tokens.push(Token::Argument(Cow::Borrowed(&template[pattern_start..id+1]))); }
+                        None => tokens.push((Token::Argument(Cow::Borrowed(&template[pattern_start..id+1])), span))
                     }
                 }
             },
@@ -132,29 +294,60 @@ pub fn tokenize(tokens: &mut ArrayVec<[Token; 128]>, template: &'static str, pat
             },
             (_, _) => ()
         }
+        id += 1;
     }
 
-    // In the event that there is leftover data that was not matched, this will add the final
-    // string to the token list.
+    // In the event that there is leftover data that was not matched, the pattern was never closed.
+    // Otherwise, this will add the final string to the token list.
     if pattern_matching {
-        tokens.push(Token::Argument(Cow::Borrowed(&template[pattern_start..])));
+        return Err(TokenErr::Unclosed(pattern_start));
     } else if argument_matching {
-        tokens.push(Token::Argument(Cow::Borrowed(&template[argument_start..])));
+        let span = Span::new(argument_start, template.len());
+        tokens.push((Token::Argument(Cow::Borrowed(&template[argument_start..])), span));
     }
 
-    Ok(())
+    Ok(tokens)
 }
 
 /// Matches a pattern to it's associated token.
-fn match_token(pattern: &'static str, path: &Path, nargs: usize) -> Result<Option<Token>, TokenErr> {
+fn match_token(pattern: &'static str, span: Span, inputs: &mut NthInputs, nargs: usize)
+    -> Result<Option<Token>, TokenErr>
+{
+    // A `:u` or `:l` modifier transforms the case of the value of the token which precedes it.
+    let case = if pattern.ends_with(":u") {
+        Some(Case::Upper)
+    } else if pattern.ends_with(":l") {
+        Some(Case::Lower)
+    } else {
+        None
+    };
+
+    if let Some(case) = case {
+        let pattern = &pattern[..pattern.len()-2];
+        let token = if pattern.is_empty() { Some(Token::Placeholder) } else { match_token(pattern, span, inputs, nargs)? };
+        return Ok(match token {
+            // The Nth input has already been substituted.
+            Some(Token::Argument(value)) => Some(Token::Argument(Cow::Owned(case.apply(&value)))),
+            None | Some(Token::Job) | Some(Token::JobTotal) | Some(Token::SeqEnd) | Some(Token::SeqStart) |
+                Some(Token::Slot) => None,
+            Some(token) => Some(Token::Case(case, Box::new(token))),
+        });
+    }
+
     match pattern {
         "."  => Ok(Some(Token::RemoveExtension)),
-        "#"  => Ok(Some(Token::Job)),
-        "%"  => Ok(Some(Token::Slot)),
         "/"  => Ok(Some(Token::Basename)),
         "//" => Ok(Some(Token::Dirname)),
         "/." => Ok(Some(Token::BaseAndExt)),
-        "##" => Ok(Some(Token::Argument(Cow::Owned(nargs.to_string())))),
+        ".." => Ok(Some(Token::RemoveExtensions)),
+        "/.." => Ok(Some(Token::BaseAndExts)),
+        "##" => Ok(Some(Token::JobTotal)),
+        "ext" => Ok(Some(Token::Extension)),
+        "realpath" => Ok(Some(Token::RealPath)),
+        "L"  => Ok(Some(Token::Case(Case::Lower, Box::new(Token::Placeholder)))),
+        "U"  => Ok(Some(Token::Case(Case::Upper, Box::new(Token::Placeholder)))),
+        "seq-end"   => Ok(Some(Token::SeqEnd)),
+        "seq-start" => Ok(Some(Token::SeqStart)),
         _    => {
             if pattern.starts_with('^') && pattern.len() > 1 {
                 Ok(Some(Token::RemoveSuffix(&pattern[1..])))
@@ -166,14 +359,16 @@ fn match_token(pattern: &'static str, path: &Path, nargs: usize) -> Result<Optio
                 if ndigits != 0 {
                     let number = pattern[0..ndigits].parse::<usize>().unwrap();
                     if ndigits == nchars {
-                        if number == 0 || number > nargs { return Err(TokenErr::OutOfBounds); }
-                        let argument = Number::new(number, Token::Placeholder).into_argument(path)?;
+                        check_bounds(span, number, nargs)?;
+                        let argument = Number::new(number, Token::Placeholder).into_argument(inputs)?;
                         Ok(Some(Token::Argument(Cow::Owned(argument))))
                     } else {
-                        match match_token(&pattern[ndigits..], path, nargs)? {
-                            None | Some(Token::Job) |  Some(Token::Slot) => Ok(None),
+                        match match_token(&pattern[ndigits..], span, inputs, nargs)? {
+                            None | Some(Token::Job) | Some(Token::JobTotal) | Some(Token::SeqEnd) |
+                                Some(Token::SeqStart) | Some(Token::Slot) => Ok(None),
                             Some(token) => {
-                                let argument = Number::new(number, token).into_argument(path)?;
+                                check_bounds(span, number, nargs)?;
+                                let argument = Number::new(number, token).into_argument(inputs)?;
                                 Ok(Some(Token::Argument(Cow::Owned(argument))))
                             },
                         }
@@ -186,75 +381,166 @@ fn match_token(pattern: &'static str, path: &Path, nargs: usize) -> Result<Optio
     }
 }
 
-// TODO: Fix Tests
-// #[test]
-// fn tokenizer_argument() {
-//     let mut tokens = Vec::new();
-//     let _ = tokenize(&mut tokens, "foo", &Path::new("."), 1);
-//     assert_eq!(tokens, vec![Token::Argument("foo".to_owned())]);
-// }
-
-// #[test]
-// fn tokenizer_placeholder() {
-//     let mut tokens = Vec::new();
-//     let _ = tokenize(&mut tokens, "{}", &Path::new("."), 1);
-//     assert_eq!(tokens, vec![Token::Placeholder]);
-// }
-
-// #[test]
-// fn tokenizer_remove_extension() {
-//     let mut tokens = Vec::new();
-//     let _ = tokenize(&mut tokens, "{.}", &Path::new("."), 1);
-//     assert_eq!(tokens, vec![Token::RemoveExtension]);
-// }
-
-// #[test]
-// fn tokenizer_basename() {
-//     let mut tokens = Vec::new();
-//     let _ = tokenize(&mut tokens, "{/}", &Path::new("."), 1);
-//     assert_eq!(tokens, vec![Token::Basename]);
-// }
-
-// #[test]
-// fn tokenizer_dirname() {
-//     let mut tokens = Vec::new();
-//     let _ = tokenize(&mut tokens, "{//}", &Path::new("."), 1);
-//     assert_eq!(tokens, vec![Token::Dirname]);
-// }
-
-// #[test]
-// fn tokenizer_base_and_ext() {
-//     let mut tokens = Vec::new();
-//     let _ = tokenize(&mut tokens, "{/.}", &Path::new("."), 1);
-//     assert_eq!(tokens, vec![Token::BaseAndExt]);
-// }
-
-// #[test]
-// fn tokenizer_slot() {
-//     let mut tokens = Vec::new();
-//     let _ = tokenize(&mut tokens, "{%}", &Path::new("."), 1);
-//     assert_eq!(tokens, vec![Token::Slot]);
-// }
-
-// #[test]
-// fn tokenizer_job() {
-//     let mut tokens = Vec::new();
-//     let _ = tokenize(&mut tokens, "{#}", &Path::new("."), 1);
-//     assert_eq!(tokens, vec![Token::Job]);
-// }
-
-// #[test]
-// fn tokenizer_multiple() {
-//     let mut tokens = Vec::new();
-//     let _ = tokenize(&mut tokens, "foo {} bar", &Path::new("."), 1);
-//     assert_eq!(tokens, vec![Token::Argument("foo ".to_owned()), Token::Placeholder,
-//         Token::Argument(" bar".to_owned())]);
-// }
-
-// #[test]
-// fn tokenizer_no_space() {
-//     let mut tokens = Vec::new();
-//     let _ = tokenize(&mut tokens, "foo{}bar", &Path::new("."), 1);
-//     assert_eq!(tokens, vec![Token::Argument("foo".to_owned()), Token::Placeholder,
-//         Token::Argument("bar".to_owned())]);
-// }
+/// Ensures that the Nth input, referenced by a `{N}` token, exists.
+fn check_bounds(span: Span, number: usize, nargs: usize) -> Result<(), TokenErr> {
+    if nargs == UNKNOWN_TOTAL {
+        // The Nth input has yet to be read when inputs are streamed.
+        Err(TokenErr::Streamed(span, number))
+    } else if number == 0 || number > nargs {
+        Err(TokenErr::OutOfBounds(span, number, nargs))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(template: &'static str) -> Vec<Token> {
+        tokenize_spans(template, &Path::new("."), 1).unwrap().into_iter().map(|(token, _)| token).collect()
+    }
+
+    #[test]
+    fn tokenizer_argument() {
+        assert_eq!(tokens("foo"), vec![Token::Argument(Cow::Borrowed("foo"))]);
+    }
+
+    #[test]
+    fn tokenizer_placeholder() {
+        assert_eq!(tokens("{}"), vec![Token::Placeholder]);
+    }
+
+    #[test]
+    fn tokenizer_remove_extension() {
+        assert_eq!(tokens("{.}"), vec![Token::RemoveExtension]);
+    }
+
+    #[test]
+    fn tokenizer_basename() {
+        assert_eq!(tokens("{/}"), vec![Token::Basename]);
+    }
+
+    #[test]
+    fn tokenizer_dirname() {
+        assert_eq!(tokens("{//}"), vec![Token::Dirname]);
+    }
+
+    #[test]
+    fn tokenizer_base_and_ext() {
+        assert_eq!(tokens("{/.}"), vec![Token::BaseAndExt]);
+    }
+
+    #[test]
+    fn tokenizer_extension() {
+        assert_eq!(tokens("out.{ext}"), vec![Token::Argument(Cow::Borrowed("out.")), Token::Extension]);
+    }
+
+    #[test]
+    fn tokenizer_case() {
+        let upper = |token| Token::Case(Case::Upper, Box::new(token));
+        assert_eq!(tokens("{U} {:u}"), vec![upper(Token::Placeholder), Token::Argument(Cow::Borrowed(" ")),
+            upper(Token::Placeholder)]);
+        assert_eq!(tokens("{/:l}"), vec![Token::Case(Case::Lower, Box::new(Token::Basename))]);
+        assert_eq!(tokens("{#:u}"), vec![Token::Argument(Cow::Borrowed("{#:u}"))]);
+        assert_eq!(upper(Token::BaseAndExt).derive("dir/file.txt").unwrap(), "FILE");
+        assert_eq!(upper(Token::Slot).derive("file"), None);
+    }
+
+    #[test]
+    fn tokenizer_realpath() {
+        assert_eq!(tokens("{realpath}"), vec![Token::RealPath]);
+    }
+
+    #[test]
+    fn tokenizer_remove_extensions() {
+        assert_eq!(tokens("{..} {/..}"), vec![Token::RemoveExtensions, Token::Argument(Cow::Borrowed(" ")),
+            Token::BaseAndExts]);
+    }
+
+    #[test]
+    fn tokenizer_slot() {
+        assert_eq!(tokens("{%}"), vec![Token::Slot]);
+    }
+
+    #[test]
+    fn tokenizer_job() {
+        assert_eq!(tokens("{#}"), vec![Token::Job]);
+    }
+
+    #[test]
+    fn tokenizer_job_total() {
+        assert_eq!(tokens("{##}"), vec![Token::JobTotal]);
+        assert_eq!(tokens("{seq-start}-{seq-end}"), vec![Token::SeqStart, Token::Argument("-".into()), Token::SeqEnd]);
+    }
+
+    #[test]
+    fn tokenizer_replacements() {
+        let replace = Replacements { seq: "SEQ", slot: "{slot}" };
+        let tokens = tokenize_spans_with("{#} SEQ{slot}", &Path::new("."), 1, &replace).unwrap()
+            .into_iter().map(|(token, _)| token).collect::<Vec<_>>();
+        assert_eq!(tokens, vec![Token::Argument(Cow::Borrowed("{#}")), Token::Argument(Cow::Borrowed(" ")),
+            Token::Job, Token::Slot]);
+    }
+
+    #[test]
+    fn tokenizer_multiple() {
+        assert_eq!(tokens("foo {} bar"), vec![Token::Argument(Cow::Borrowed("foo ")), Token::Placeholder,
+            Token::Argument(Cow::Borrowed(" bar"))]);
+    }
+
+    #[test]
+    fn tokenizer_no_space() {
+        assert_eq!(tokens("foo{}bar"), vec![Token::Argument(Cow::Borrowed("foo")), Token::Placeholder,
+            Token::Argument(Cow::Borrowed("bar"))]);
+    }
+
+    #[test]
+    fn tokenizer_spans() {
+        let spans = tokenize_spans("foo {.} bar", &Path::new("."), 1).unwrap()
+            .into_iter().map(|(_, span)| (span.start, span.end)).collect::<Vec<_>>();
+        assert_eq!(spans, vec![(0, 4), (4, 7), (7, 11)]);
+    }
+
+    #[test]
+    fn tokenizer_unclosed() {
+        match tokenize_spans("foo {bar", &Path::new("."), 1) {
+            Err(TokenErr::Unclosed(4)) => (),
+            _ => panic!("expected an unclosed error")
+        }
+    }
+
+    #[test]
+    fn tokenizer_out_of_bounds() {
+        match tokenize_spans("foo {3}", &Path::new("."), 2) {
+            Err(TokenErr::OutOfBounds(span, 3, 2)) => assert_eq!(span, Span::new(4, 7)),
+            _ => panic!("expected an out of bounds error")
+        }
+        match tokenize_spans("foo {3/}", &Path::new("."), 2) {
+            Err(TokenErr::OutOfBounds(span, 3, 2)) => assert_eq!(span, Span::new(4, 8)),
+            _ => panic!("expected an out of bounds error")
+        }
+    }
+
+    #[test]
+    fn tokenizer_nth_inputs() {
+        use std::env;
+        use std::fs;
+
+        let path = env::temp_dir().join(format!("parallel_nth_inputs_{}", ::std::process::id()));
+        fs::write(&path, "a/one.txt\nb/two.txt\n").unwrap();
+        let tokens = tokenize_spans("{2/.} {1} {2//}", &path, 2).unwrap()
+            .into_iter().map(|(token, _)| token).collect::<Vec<_>>();
+        let _ = fs::remove_file(&path);
+        assert_eq!(tokens, vec![Token::Argument("two".into()), Token::Argument(" ".into()),
+            Token::Argument("a/one.txt".into()), Token::Argument(" ".into()), Token::Argument("b".into())]);
+    }
+
+    #[test]
+    fn tokenizer_streamed() {
+        match tokenize_spans("foo {2.}", &Path::new("."), UNKNOWN_TOTAL) {
+            Err(TokenErr::Streamed(span, 2)) => assert_eq!(span, Span::new(4, 8)),
+            _ => panic!("expected a streamed error")
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use std::fs::File;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use time::get_time;
+
+/// The phases of the pipeline through which each job passes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Phase {
+    /// Obtaining the next input, including any delays and waits for memory before the job may start.
+    Fetch,
+    /// Building the command from the template and the input.
+    Build,
+    /// Spawning the process of the command.
+    Spawn,
+    /// Running the command, including the capturing of its outputs.
+    Run,
+    /// Replaying the captured outputs of the job, in order, to the standard output and error.
+    Replay,
+}
+
+const PHASES: [Phase; 5] = [Phase::Fetch, Phase::Build, Phase::Spawn, Phase::Run, Phase::Replay];
+
+impl Phase {
+    fn name(self) -> &'static str {
+        match self {
+            Phase::Fetch  => "fetch",
+            Phase::Build  => "build",
+            Phase::Spawn  => "spawn",
+            Phase::Run    => "run",
+            Phase::Replay => "replay",
+        }
+    }
+}
+
+/// Emits one JSON object per line for each phase of each job, as given by the `--trace-file` parameter,
+/// followed by the total time that was spent within each phase, so that it may be seen whether a run
+/// is limited by the overhead of spawning commands, replaying their outputs, or by the jobs themselves.
+#[derive(Clone)]
+pub struct TraceLog {
+    output: Arc<Mutex<Box<dyn Write + Send>>>,
+    /// The nanoseconds spent within each phase by every job, indexed by the order of `PHASES`.
+    totals: Arc<[AtomicU64; 5]>,
+    jobs:   Arc<AtomicUsize>,
+}
+
+impl TraceLog {
+    /// Opens the trace log at the given path, where a path of `-` denotes the standard error.
+    pub fn open(path: &str) -> io::Result<TraceLog> {
+        let output: Box<dyn Write + Send> = if path == "-" {
+            Box::new(io::stderr())
+        } else {
+            Box::new(File::create(path)?)
+        };
+
+        Ok(TraceLog {
+            output: Arc::new(Mutex::new(output)),
+            totals: Arc::new([AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0),
+                AtomicU64::new(0)]),
+            jobs:   Arc::new(AtomicUsize::new(0)),
+        })
+    }
+
+    /// Records that the given job spent the time since `start` within the given phase, and returns the
+    /// current time, which is when the next phase begins.
+    pub fn span(&self, job: usize, phase: Phase, start: Instant) -> Instant {
+        let now = Instant::now();
+        let duration = nanoseconds(now.duration_since(start));
+        let index = PHASES.iter().position(|&x| x == phase).unwrap();
+        self.totals[index].fetch_add(duration, Ordering::SeqCst);
+        if phase == Phase::Fetch { self.jobs.fetch_add(1, Ordering::SeqCst); }
+
+        let end = get_time();
+        let end = end.sec as u64 * 1_000_000 + end.nsec as u64 / 1_000;
+        let start = end.saturating_sub(duration / 1_000);
+        self.write(&format!("{{\"job\":{},\"phase\":\"{}\",\"start\":{}.{:06},\"duration\":{}}}\n",
+            job, phase.name(), start / 1_000_000, start % 1_000_000, seconds(duration)));
+        now
+    }
+
+    /// Records the total and mean time that was spent within each phase, once all jobs have been processed.
+    pub fn summary(&self) {
+        let jobs = self.jobs.load(Ordering::SeqCst);
+        let mut summary = format!("{{\"phase\":\"total\",\"jobs\":{}", jobs);
+        for (phase, total) in PHASES.iter().zip(self.totals.iter()) {
+            let total = total.load(Ordering::SeqCst);
+            summary.push_str(&format!(",\"{}\":{},\"{}_mean\":{}", phase.name(), seconds(total), phase.name(),
+                seconds(if jobs == 0 { 0 } else { total / jobs as u64 })));
+        }
+        summary.push_str("}\n");
+        self.write(&summary);
+    }
+
+    fn write(&self, span: &str) {
+        let mut output = self.output.lock().unwrap();
+        let _ = output.write_all(span.as_bytes()).and_then(|_| output.flush());
+    }
+}
+
+fn nanoseconds(duration: Duration) -> u64 {
+    duration.as_secs() * 1_000_000_000 + duration.subsec_nanos() as u64
+}
+
+/// Formats nanoseconds as seconds, with six decimal places.
+fn seconds(nanoseconds: u64) -> String {
+    format!("{}.{:06}", nanoseconds / 1_000_000_000, nanoseconds % 1_000_000_000 / 1_000)
+}
+
+#[test]
+fn trace_seconds() {
+    assert_eq!(seconds(1_500_000_000), "1.500000");
+    assert_eq!(seconds(42_000), "0.000042");
+}
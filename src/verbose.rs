@@ -1,3 +1,4 @@
+use execute::Usage;
 use std::io::{Stdout, Write};
 use itoa;
 
@@ -21,7 +22,8 @@ pub fn processing_task(stdout: &Stdout, job: usize, total: usize, input: &str) {
     let _ = stdout.write(b"'\n");
 }
 
-pub fn task_complete(stdout: &Stdout, job: usize, total: usize, input: &str) {
+/// Also reports the peak memory, in kilobytes, and the user and system CPU time that the task consumed.
+pub fn task_complete(stdout: &Stdout, job: usize, total: usize, input: &str, usage: Usage) {
     let mut stdout = stdout.lock();
     let _ = stdout.write(b"parallel:  completed task #");
     let _ = itoa::write(&mut stdout, job);
@@ -29,5 +31,7 @@ pub fn task_complete(stdout: &Stdout, job: usize, total: usize, input: &str) {
     let _ = itoa::write(&mut stdout, total);
     let _ = stdout.write(b": '");
     let _ = stdout.write(input.as_bytes());
-    let _ = stdout.write(b"'\n");
+    let _ = writeln!(stdout, "' (peak memory {} KiB, user {}.{:03}s, system {}.{:03}s)", usage.max_rss,
+        usage.user / 1_000_000_000, usage.user % 1_000_000_000 / 1_000_000,
+        usage.system / 1_000_000_000, usage.system % 1_000_000_000 / 1_000_000);
 }
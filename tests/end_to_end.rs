@@ -0,0 +1,145 @@
+//! End-to-end tests, which run the `parallel` binary against scripted commands within a sandbox directory,
+//! and check what a user would observe: the order of outputs, the job log, exit codes, and resumed runs.
+#![cfg(unix)]
+
+use std::env;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+use std::process::{self, Command, Output, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Distinguishes the sandboxes of tests that are running at the same time.
+static SANDBOXES: AtomicUsize = AtomicUsize::new(0);
+
+/// A temporary directory in which the binary is run, which is removed once the test has finished.
+struct Sandbox {
+    path: PathBuf,
+}
+
+impl Sandbox {
+    fn new() -> Sandbox {
+        let id = SANDBOXES.fetch_add(1, Ordering::SeqCst);
+        let path = env::temp_dir().join(format!("parallel-test-{}-{}", process::id(), id));
+        fs::create_dir_all(&path).unwrap();
+        Sandbox { path: path }
+    }
+
+    /// Writes an executable shell script into the sandbox, which may be run as `./NAME`.
+    fn script(&self, name: &str, body: &str) {
+        let path = self.path.join(name);
+        fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    /// Runs the binary within the sandbox, with a tempdir of its own, so that tests do not share state.
+    /// The standard input is an empty pipe, as inputs would otherwise be read from a redirected standard input.
+    fn run(&self, arguments: &[&str]) -> Output {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_parallel"))
+            .arg("--tmpdir").arg(self.path.join("tmp"))
+            .args(arguments)
+            .current_dir(&self.path)
+            .env_remove("PARALLEL")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        drop(child.stdin.take());
+        child.wait_with_output().unwrap()
+    }
+
+    fn read(&self, name: &str) -> String { fs::read_to_string(self.path.join(name)).unwrap() }
+
+    fn exists(&self, name: &str) -> bool { self.path.join(name).exists() }
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) { let _ = fs::remove_dir_all(&self.path); }
+}
+
+fn stdout(output: &Output) -> &str { std::str::from_utf8(&output.stdout).unwrap() }
+
+#[test]
+fn outputs_in_input_order() {
+    let sandbox = Sandbox::new();
+    sandbox.script("job.sh", "sleep \"$1\"; echo \"$1\"");
+    let output = sandbox.run(&["-j3", "./job.sh", ":::", "0.3", "0.1", "0.2"]);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "0.3\n0.1\n0.2\n");
+}
+
+#[test]
+fn ungrouped_outputs_in_completion_order() {
+    let sandbox = Sandbox::new();
+    sandbox.script("job.sh", "sleep \"$1\"; echo \"$1\"");
+    let output = sandbox.run(&["-u", "-j2", "./job.sh", ":::", "0.3", "0.1"]);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "0.1\n0.3\n");
+}
+
+#[test]
+fn joblog_contents() {
+    let sandbox = Sandbox::new();
+    sandbox.script("exit.sh", "exit \"$1\"");
+    sandbox.run(&["-j2", "--joblog", "log", "./exit.sh", ":::", "0", "3"]);
+
+    let log = sandbox.read("log");
+    let mut lines = log.lines();
+    assert!(lines.next().unwrap().starts_with("Sequence"));
+    let entries = lines.map(|line| {
+        let columns = line.split_whitespace().collect::<Vec<&str>>();
+        (columns[0].to_owned(), columns[3].to_owned(), columns[5..].join(" "))
+    }).collect::<Vec<_>>();
+    assert_eq!(entries, vec![
+        ("1".to_owned(), "0".to_owned(), "./exit.sh 0".to_owned()),
+        ("2".to_owned(), "3".to_owned(), "./exit.sh 3".to_owned()),
+    ]);
+}
+
+#[test]
+fn exit_codes() {
+    let sandbox = Sandbox::new();
+    sandbox.script("ok.sh", "true");
+    assert_eq!(sandbox.run(&["./ok.sh", ":::", "a", "b"]).status.code(), Some(0));
+
+    // Each job whose command could not be executed is counted as an error.
+    let output = sandbox.run(&["--no-shell", "./missing.sh", ":::", "a", "b"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(std::str::from_utf8(&output.stderr).unwrap().contains("encountered errors during processing"));
+
+    assert_eq!(sandbox.run(&["--frobnicate", "./ok.sh", ":::", "a"]).status.code(), Some(1));
+}
+
+#[test]
+fn checkpoint_resume() {
+    let sandbox = Sandbox::new();
+    sandbox.script("check.sh", "echo \"$1\"; test \"$1\" != 3");
+    let arguments = ["-j1", "--checkpoint", "checkpoint", "--halt", "now,fail=1", "./check.sh", ":::", "1", "2", "3", "4", "5"];
+
+    // The run halts at the third job, and the checkpoint records that three jobs were completed.
+    assert_eq!(stdout(&sandbox.run(&arguments)), "1\n2\n3\n");
+    assert!(sandbox.read("checkpoint").contains("completed=3"));
+
+    // Running it again resumes after the completed jobs, and removes the checkpoint once every input is processed.
+    assert_eq!(stdout(&sandbox.run(&arguments)), "4\n5\n");
+    assert!(!sandbox.exists("checkpoint"));
+}
+
+#[test]
+fn jobs_within_workdir() {
+    let sandbox = Sandbox::new();
+    sandbox.script("job.sh", "cat name");
+    for dir in &["a", "b"] {
+        fs::create_dir(sandbox.path.join(dir)).unwrap();
+        fs::write(sandbox.path.join(dir).join("name"), format!("in {}\n", dir)).unwrap();
+    }
+
+    let script = sandbox.path.join("job.sh");
+    let output = sandbox.run(&["--workdir", "{}", script.to_str().unwrap(), ":::", "a", "b"]);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "in a\nin b\n");
+
+    // A job whose directory does not exist fails, rather than being executed elsewhere.
+    assert_eq!(sandbox.run(&["--workdir", "{}", script.to_str().unwrap(), ":::", "c"]).status.code(), Some(1));
+}